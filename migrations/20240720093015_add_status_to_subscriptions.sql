@@ -0,0 +1,7 @@
+-- 구독자의 상태를 기록하는 열을 추가한다.
+-- 기존 구독자는 확인된 것으로 간주한다.
+ALTER TABLE subscriptions ADD COLUMN status TEXT NULL;
+UPDATE subscriptions
+    SET status = 'confirmed'
+    WHERE status IS NULL;
+ALTER TABLE subscriptions ALTER COLUMN status SET NOT NULL;
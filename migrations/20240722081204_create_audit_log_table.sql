@@ -0,0 +1,25 @@
+-- 관리 작업을 기록하는 audit_log 테이블을 생성한다.
+CREATE TABLE audit_log(
+    id BIGSERIAL PRIMARY KEY,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    target TEXT NULL,
+    request_id TEXT NULL,
+    ip TEXT NULL,
+    diff JSONB NOT NULL DEFAULT '{}'::JSONB
+);
+CREATE INDEX audit_log_occurred_at_idx ON audit_log (occurred_at);
+CREATE INDEX audit_log_actor_idx ON audit_log (actor);
+CREATE INDEX audit_log_action_idx ON audit_log (action);
+
+-- 감사 기록은 추가만 할 수 있다.
+CREATE FUNCTION reject_audit_log_modification() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION reject_audit_log_modification();
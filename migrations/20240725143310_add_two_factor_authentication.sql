@@ -0,0 +1,33 @@
+-- 관리자의 역할과 TOTP 2단계 인증 정보를 추가한다.
+-- `owner`만 보안 설정을 변경할 수 있다.
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'admin';
+ALTER TABLE users ADD COLUMN totp_secret TEXT NULL;
+ALTER TABLE users ADD COLUMN totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+-- 재사용을 막기 위해 마지막으로 사용한 TOTP 타임 스텝을 기록한다.
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT NULL;
+
+-- 일회용 복구 코드는 해시값만 저장한다.
+CREATE TABLE recovery_codes(
+    user_id UUID NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ NULL,
+    PRIMARY KEY (user_id, code_hash)
+);
+
+-- 로그인 세션
+-- 토큰은 해시값만 저장한다.
+CREATE TABLE admin_sessions(
+    token_hash TEXT NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- 2단계 인증을 기다리는 세션은 관리용 엔드포인트에 사용할 수 없다.
+    second_factor_pending BOOLEAN NOT NULL
+);
+
+-- 보안 설정은 하나의 행만 갖는다.
+CREATE TABLE security_settings(
+    id BOOLEAN NOT NULL PRIMARY KEY DEFAULT TRUE CHECK (id),
+    require_two_factor BOOLEAN NOT NULL DEFAULT FALSE
+);
+INSERT INTO security_settings DEFAULT VALUES;
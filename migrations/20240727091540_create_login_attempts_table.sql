@@ -0,0 +1,11 @@
+-- 로그인 실패 횟수를 사용자 이름과 클라이언트 IP별로 기록한다.
+-- 여러 인스턴스가 같은 카운터를 공유하도록 DB에 저장한다.
+CREATE TABLE login_attempts(
+    -- 'username' 또는 'ip'
+    scope TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    failures INTEGER NOT NULL,
+    last_failure_at TIMESTAMPTZ NOT NULL,
+    locked_until TIMESTAMPTZ NULL,
+    PRIMARY KEY (scope, subject)
+);
@@ -0,0 +1,2 @@
+-- 인덱스만 추가했으므로 데이터 손실 없이 되돌릴 수 있다.
+DROP INDEX subscriptions_subscribed_at_id_idx;
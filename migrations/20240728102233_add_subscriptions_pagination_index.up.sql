@@ -0,0 +1,2 @@
+-- 관리용 구독자 목록을 최신 순으로 페이지 단위로 조회한다.
+CREATE INDEX subscriptions_subscribed_at_id_idx ON subscriptions (subscribed_at DESC, id DESC);
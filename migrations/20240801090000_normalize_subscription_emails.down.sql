@@ -0,0 +1,5 @@
+-- 중복으로 지운 구독자와 정규화하기 전의 주소는 되돌리지 않는다.
+DROP INDEX subscriptions_canonical_email_idx;
+ALTER TABLE subscriptions DROP COLUMN canonical_email;
+DROP INDEX subscriptions_lower_email_idx;
+ALTER TABLE subscriptions ADD CONSTRAINT subscriptions_email_key UNIQUE (email);
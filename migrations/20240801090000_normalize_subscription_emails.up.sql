@@ -0,0 +1,29 @@
+-- 대소문자만 다른 이메일을 같은 구독자로 취급한다.
+-- 도메인을 소문자로 바꾸면 겹치는 구독자는 하나만 남긴다.
+-- 확인된 구독자를 우선하고, 그 다음은 먼저 구독한 구독자를 남긴다.
+-- 앞뒤 공백을 먼저 지우면 기존의 고유 제약에 걸릴 수 있으므로 중복부터 지운다.
+DELETE FROM subscriptions
+WHERE id IN (
+    SELECT id
+    FROM (
+        SELECT id, row_number() OVER (
+            PARTITION BY lower(trim(email))
+            ORDER BY status = 'confirmed' DESC, subscribed_at, id
+        ) AS rank
+        FROM subscriptions
+    ) ranked
+    WHERE rank > 1
+);
+UPDATE subscriptions SET email = trim(email);
+UPDATE subscriptions
+    SET email = substring(email FROM '^(.*)@') || '@' || lower(substring(email FROM '@([^@]*)$'))
+    WHERE email LIKE '%@%';
+ALTER TABLE subscriptions DROP CONSTRAINT subscriptions_email_key;
+CREATE UNIQUE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email));
+
+-- 제공자별 규칙(플러스 태그 등)을 적용한 주소로 중복을 검사한다.
+-- 기존 구독자는 규칙 없이 소문자로 바꾼 주소를 사용한다.
+ALTER TABLE subscriptions ADD COLUMN canonical_email TEXT NULL;
+UPDATE subscriptions SET canonical_email = lower(email);
+ALTER TABLE subscriptions ALTER COLUMN canonical_email SET NOT NULL;
+CREATE UNIQUE INDEX subscriptions_canonical_email_idx ON subscriptions (canonical_email);
@@ -0,0 +1,10 @@
+-- 삭제 표시된 구독자는 완전히 지운다.
+DROP TRIGGER subscriptions_history ON subscriptions;
+DROP FUNCTION record_subscription_history();
+DROP TABLE subscription_history;
+DELETE FROM subscriptions WHERE deleted_at IS NOT NULL;
+DROP INDEX subscriptions_canonical_email_idx;
+CREATE UNIQUE INDEX subscriptions_canonical_email_idx ON subscriptions (canonical_email);
+DROP INDEX subscriptions_lower_email_idx;
+CREATE UNIQUE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email));
+ALTER TABLE subscriptions DROP COLUMN deleted_at;
@@ -0,0 +1,66 @@
+-- 삭제한 구독자는 유예 기간 동안 복원할 수 있도록 표시만 한다.
+ALTER TABLE subscriptions ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+-- 삭제한 구독자의 주소로 다시 구독할 수 있어야 한다.
+DROP INDEX subscriptions_lower_email_idx;
+CREATE UNIQUE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email))
+    WHERE deleted_at IS NULL;
+DROP INDEX subscriptions_canonical_email_idx;
+CREATE UNIQUE INDEX subscriptions_canonical_email_idx ON subscriptions (canonical_email)
+    WHERE deleted_at IS NULL;
+
+-- 구독자 레코드의 변경 기록
+-- 구독자를 완전히 지우면 기록도 함께 지운다.
+CREATE TABLE subscription_history(
+    id BIGSERIAL PRIMARY KEY,
+    subscriber_id UUID NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    -- insert, update, delete, restore
+    operation TEXT NOT NULL,
+    email TEXT NOT NULL,
+    name TEXT NOT NULL,
+    status TEXT NOT NULL
+);
+CREATE INDEX subscription_history_subscriber_id_idx ON subscription_history (subscriber_id, id);
+
+-- 기존 구독자는 현재 상태를 첫 기록으로 남긴다.
+INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+SELECT id, subscribed_at, 'insert', email, name, status
+FROM subscriptions;
+
+-- 어느 경로로 바꾸든 빠짐없이 기록하도록 트리거를 사용한다.
+CREATE FUNCTION record_subscription_history() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        DELETE FROM subscription_history WHERE subscriber_id = OLD.id;
+        RETURN OLD;
+    END IF;
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NEW;
+    END IF;
+    INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+    VALUES (
+        NEW.id,
+        clock_timestamp(),
+        CASE
+            WHEN TG_OP = 'INSERT' THEN 'insert'
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        NEW.email,
+        NEW.name,
+        NEW.status
+    );
+    -- 익명화한 구독자의 개인 정보가 기록에 남지 않도록 한다.
+    IF NEW.status = 'anonymised' THEN
+        UPDATE subscription_history
+        SET email = NEW.email, name = NEW.name
+        WHERE subscriber_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER subscriptions_history
+    AFTER INSERT OR UPDATE OR DELETE ON subscriptions
+    FOR EACH ROW EXECUTE FUNCTION record_subscription_history();
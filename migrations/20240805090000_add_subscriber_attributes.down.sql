@@ -0,0 +1,37 @@
+CREATE OR REPLACE FUNCTION record_subscription_history() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        DELETE FROM subscription_history WHERE subscriber_id = OLD.id;
+        RETURN OLD;
+    END IF;
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NEW;
+    END IF;
+    INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+    VALUES (
+        NEW.id,
+        clock_timestamp(),
+        CASE
+            WHEN TG_OP = 'INSERT' THEN 'insert'
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        NEW.email,
+        NEW.name,
+        NEW.status
+    );
+    -- 익명화한 구독자의 개인 정보가 기록에 남지 않도록 한다.
+    IF NEW.status = 'anonymised' THEN
+        UPDATE subscription_history
+        SET email = NEW.email, name = NEW.name
+        WHERE subscriber_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TABLE subscriber_attributes;
+ALTER TABLE subscription_history DROP COLUMN attributes;
+DROP INDEX subscriptions_attributes_idx;
+ALTER TABLE subscriptions DROP COLUMN attributes;
@@ -0,0 +1,49 @@
+-- 구독 양식에서 받는 추가 항목
+-- 관리자가 정의한 속성만 저장하므로 항목을 추가할 때 마이그레이션이 필요 없다.
+ALTER TABLE subscriptions ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+-- `@>`로 속성을 검색한다.
+CREATE INDEX subscriptions_attributes_idx ON subscriptions USING GIN (attributes jsonb_path_ops);
+ALTER TABLE subscription_history ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+
+-- 관리자가 정의한 구독자 속성
+CREATE TABLE subscriber_attributes(
+    name TEXT NOT NULL PRIMARY KEY,
+    -- string, number, boolean, list
+    type TEXT NOT NULL
+);
+
+CREATE OR REPLACE FUNCTION record_subscription_history() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        DELETE FROM subscription_history WHERE subscriber_id = OLD.id;
+        RETURN OLD;
+    END IF;
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NEW;
+    END IF;
+    INSERT INTO subscription_history (
+        subscriber_id, occurred_at, operation, email, name, status, attributes
+    )
+    VALUES (
+        NEW.id,
+        clock_timestamp(),
+        CASE
+            WHEN TG_OP = 'INSERT' THEN 'insert'
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        NEW.email,
+        NEW.name,
+        NEW.status,
+        NEW.attributes
+    );
+    -- 익명화한 구독자의 개인 정보가 기록에 남지 않도록 한다.
+    IF NEW.status = 'anonymised' THEN
+        UPDATE subscription_history
+        SET email = NEW.email, name = NEW.name, attributes = NEW.attributes
+        WHERE subscriber_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,15 @@
+-- 관리자가 구독자에게 붙이는 태그
+CREATE TABLE subscription_tags(
+    subscriber_id UUID NOT NULL REFERENCES subscriptions (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (subscriber_id, tag)
+);
+CREATE INDEX subscription_tags_tag_idx ON subscription_tags (tag);
+
+-- 필터 식으로 정의한 구독자 집합
+-- 식은 조회할 때마다 현재 속성 정의로 해석한다.
+CREATE TABLE segments(
+    name TEXT NOT NULL PRIMARY KEY,
+    expression TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
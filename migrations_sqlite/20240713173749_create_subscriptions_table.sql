@@ -0,0 +1,8 @@
+-- subscriptions 테이블을 생성한다.
+-- SQLite에는 UUID와 TIMESTAMPTZ 타입이 없으므로 sqlx의 인코딩에 맞춰 BLOB과 TEXT를 사용한다.
+CREATE TABLE subscriptions(
+    id BLOB NOT NULL PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    subscribed_at TEXT NOT NULL
+);
@@ -0,0 +1,16 @@
+-- 구독자의 상태를 기록하는 열을 추가한다.
+-- 기존 구독자는 확인된 것으로 간주한다.
+-- SQLite는 기본값 없이 NOT NULL 열을 추가할 수 없으므로 테이블을 다시 만든다.
+-- 새 구독자의 상태는 항상 명시해야 한다.
+CREATE TABLE subscriptions_new(
+    id BLOB NOT NULL PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    subscribed_at TEXT NOT NULL,
+    status TEXT NOT NULL
+);
+INSERT INTO subscriptions_new (id, email, name, subscribed_at, status)
+SELECT id, email, name, subscribed_at, 'confirmed'
+FROM subscriptions;
+DROP TABLE subscriptions;
+ALTER TABLE subscriptions_new RENAME TO subscriptions;
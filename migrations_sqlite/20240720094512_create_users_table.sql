@@ -0,0 +1,8 @@
+-- 관리자 계정을 저장하는 users 테이블을 생성한다.
+CREATE TABLE users(
+    user_id BLOB NOT NULL PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,10 @@
+-- 로그인 실패 횟수를 사용자 이름과 클라이언트 IP별로 기록한다.
+CREATE TABLE login_attempts(
+    -- 'username' 또는 'ip'
+    scope TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    failures INTEGER NOT NULL,
+    last_failure_at TEXT NOT NULL,
+    locked_until TEXT NULL,
+    PRIMARY KEY (scope, subject)
+);
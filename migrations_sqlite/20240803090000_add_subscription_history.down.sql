@@ -0,0 +1,19 @@
+-- 삭제 표시된 구독자는 완전히 지운다.
+DROP TABLE subscription_history;
+CREATE TABLE subscriptions_old(
+    id BLOB NOT NULL PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    subscribed_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    canonical_email TEXT NOT NULL DEFAULT ''
+);
+INSERT INTO subscriptions_old (id, email, name, subscribed_at, status, canonical_email)
+SELECT id, email, name, subscribed_at, status, canonical_email
+FROM subscriptions
+WHERE deleted_at IS NULL;
+DROP TABLE subscriptions;
+ALTER TABLE subscriptions_old RENAME TO subscriptions;
+CREATE INDEX subscriptions_subscribed_at_id_idx ON subscriptions (subscribed_at DESC, id DESC);
+CREATE UNIQUE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email));
+CREATE UNIQUE INDEX subscriptions_canonical_email_idx ON subscriptions (canonical_email);
@@ -0,0 +1,79 @@
+-- 삭제한 구독자는 유예 기간 동안 복원할 수 있도록 표시만 한다.
+-- 삭제한 구독자의 주소로 다시 구독할 수 있어야 하는데,
+-- SQLite는 기존의 `UNIQUE` 제약을 지울 수 없으므로 테이블을 다시 만든다.
+CREATE TABLE subscriptions_new(
+    id BLOB NOT NULL PRIMARY KEY,
+    email TEXT NOT NULL,
+    name TEXT NOT NULL,
+    subscribed_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    canonical_email TEXT NOT NULL,
+    deleted_at TEXT NULL
+);
+INSERT INTO subscriptions_new (id, email, name, subscribed_at, status, canonical_email)
+SELECT id, email, name, subscribed_at, status, canonical_email
+FROM subscriptions;
+DROP TABLE subscriptions;
+ALTER TABLE subscriptions_new RENAME TO subscriptions;
+CREATE INDEX subscriptions_subscribed_at_id_idx ON subscriptions (subscribed_at DESC, id DESC);
+CREATE UNIQUE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email))
+    WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX subscriptions_canonical_email_idx ON subscriptions (canonical_email)
+    WHERE deleted_at IS NULL;
+
+-- 구독자 레코드의 변경 기록
+-- 구독자를 완전히 지우면 기록도 함께 지운다.
+CREATE TABLE subscription_history(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subscriber_id BLOB NOT NULL,
+    occurred_at TEXT NOT NULL,
+    -- insert, update, delete, restore
+    operation TEXT NOT NULL,
+    email TEXT NOT NULL,
+    name TEXT NOT NULL,
+    status TEXT NOT NULL
+);
+CREATE INDEX subscription_history_subscriber_id_idx ON subscription_history (subscriber_id, id);
+
+-- 기존 구독자는 현재 상태를 첫 기록으로 남긴다.
+INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+SELECT id, subscribed_at, 'insert', email, name, status
+FROM subscriptions;
+
+-- 어느 경로로 바꾸든 빠짐없이 기록하도록 트리거를 사용한다.
+-- 시각은 sqlx가 읽을 수 있는 RFC 3339 형식으로 기록한다.
+CREATE TRIGGER subscriptions_history_insert AFTER INSERT ON subscriptions
+BEGIN
+    INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+    VALUES (NEW.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'insert', NEW.email, NEW.name, NEW.status);
+END;
+
+CREATE TRIGGER subscriptions_history_update AFTER UPDATE ON subscriptions
+WHEN OLD.email IS NOT NEW.email
+    OR OLD.name IS NOT NEW.name
+    OR OLD.status IS NOT NEW.status
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+BEGIN
+    INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+    VALUES (
+        NEW.id,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        NEW.email,
+        NEW.name,
+        NEW.status
+    );
+    -- 익명화한 구독자의 개인 정보가 기록에 남지 않도록 한다.
+    UPDATE subscription_history
+    SET email = NEW.email, name = NEW.name
+    WHERE subscriber_id = NEW.id AND NEW.status = 'anonymised';
+END;
+
+CREATE TRIGGER subscriptions_history_delete AFTER DELETE ON subscriptions
+BEGIN
+    DELETE FROM subscription_history WHERE subscriber_id = OLD.id;
+END;
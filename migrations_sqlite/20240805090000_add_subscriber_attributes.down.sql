@@ -0,0 +1,36 @@
+DROP TRIGGER subscriptions_history_insert;
+CREATE TRIGGER subscriptions_history_insert AFTER INSERT ON subscriptions
+BEGIN
+    INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+    VALUES (NEW.id, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), 'insert', NEW.email, NEW.name, NEW.status);
+END;
+
+DROP TRIGGER subscriptions_history_update;
+CREATE TRIGGER subscriptions_history_update AFTER UPDATE ON subscriptions
+WHEN OLD.email IS NOT NEW.email
+    OR OLD.name IS NOT NEW.name
+    OR OLD.status IS NOT NEW.status
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+BEGIN
+    INSERT INTO subscription_history (subscriber_id, occurred_at, operation, email, name, status)
+    VALUES (
+        NEW.id,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        NEW.email,
+        NEW.name,
+        NEW.status
+    );
+    -- 익명화한 구독자의 개인 정보가 기록에 남지 않도록 한다.
+    UPDATE subscription_history
+    SET email = NEW.email, name = NEW.name
+    WHERE subscriber_id = NEW.id AND NEW.status = 'anonymised';
+END;
+
+DROP TABLE subscriber_attributes;
+ALTER TABLE subscription_history DROP COLUMN attributes;
+ALTER TABLE subscriptions DROP COLUMN attributes;
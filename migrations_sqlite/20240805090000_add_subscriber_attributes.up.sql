@@ -0,0 +1,59 @@
+-- 구독 양식에서 받는 추가 항목
+-- 관리자가 정의한 속성만 저장하므로 항목을 추가할 때 마이그레이션이 필요 없다.
+-- SQLite는 JSON을 텍스트로 저장한다.
+ALTER TABLE subscriptions ADD COLUMN attributes TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE subscription_history ADD COLUMN attributes TEXT NOT NULL DEFAULT '{}';
+
+-- 관리자가 정의한 구독자 속성
+CREATE TABLE subscriber_attributes(
+    name TEXT NOT NULL PRIMARY KEY,
+    -- string, number, boolean, list
+    type TEXT NOT NULL
+);
+
+DROP TRIGGER subscriptions_history_insert;
+CREATE TRIGGER subscriptions_history_insert AFTER INSERT ON subscriptions
+BEGIN
+    INSERT INTO subscription_history (
+        subscriber_id, occurred_at, operation, email, name, status, attributes
+    )
+    VALUES (
+        NEW.id,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        'insert',
+        NEW.email,
+        NEW.name,
+        NEW.status,
+        NEW.attributes
+    );
+END;
+
+DROP TRIGGER subscriptions_history_update;
+CREATE TRIGGER subscriptions_history_update AFTER UPDATE ON subscriptions
+WHEN OLD.email IS NOT NEW.email
+    OR OLD.name IS NOT NEW.name
+    OR OLD.status IS NOT NEW.status
+    OR OLD.deleted_at IS NOT NEW.deleted_at
+    OR OLD.attributes IS NOT NEW.attributes
+BEGIN
+    INSERT INTO subscription_history (
+        subscriber_id, occurred_at, operation, email, name, status, attributes
+    )
+    VALUES (
+        NEW.id,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END,
+        NEW.email,
+        NEW.name,
+        NEW.status,
+        NEW.attributes
+    );
+    -- 익명화한 구독자의 개인 정보가 기록에 남지 않도록 한다.
+    UPDATE subscription_history
+    SET email = NEW.email, name = NEW.name, attributes = NEW.attributes
+    WHERE subscriber_id = NEW.id AND NEW.status = 'anonymised';
+END;
@@ -0,0 +1,160 @@
+use actix_web::HttpRequest;
+use chrono::{DateTime, Utc};
+use tracing_actix_web::RequestId;
+
+use crate::authentication::{client_ip, AuthenticatedUser, LoginSource};
+
+/// 감사 로그에 기록하는 관리 작업
+#[derive(Debug, Clone, Copy)]
+pub enum AuditAction {
+    UserCreate,
+    UserDisable,
+    UserEnable,
+    UserUnlock,
+    LoginLockout,
+    TwoFactorEnable,
+    TwoFactorDisable,
+    RecoveryCodeUse,
+    SecuritySettingsUpdate,
+    AttributeSettingsUpdate,
+    SubscriberConfirm,
+    SubscriberDelete,
+    SubscriberRestore,
+    SubscriberImport,
+    SubscriberPurge,
+    SubscriberTagsUpdate,
+    SegmentUpdate,
+    SegmentDelete,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::UserCreate => "user.create",
+            AuditAction::UserDisable => "user.disable",
+            AuditAction::UserEnable => "user.enable",
+            AuditAction::UserUnlock => "user.unlock",
+            AuditAction::LoginLockout => "login.lockout",
+            AuditAction::TwoFactorEnable => "user.two_factor.enable",
+            AuditAction::TwoFactorDisable => "user.two_factor.disable",
+            AuditAction::RecoveryCodeUse => "user.recovery_code.use",
+            AuditAction::SecuritySettingsUpdate => "settings.security.update",
+            AuditAction::AttributeSettingsUpdate => "settings.attributes.update",
+            AuditAction::SubscriberConfirm => "subscriber.confirm",
+            AuditAction::SubscriberDelete => "subscriber.delete",
+            AuditAction::SubscriberRestore => "subscriber.restore",
+            AuditAction::SubscriberImport => "subscriber.import",
+            AuditAction::SubscriberPurge => "subscriber.purge",
+            AuditAction::SubscriberTagsUpdate => "subscriber.tags.update",
+            AuditAction::SegmentUpdate => "segment.update",
+            AuditAction::SegmentDelete => "segment.delete",
+        }
+    }
+}
+
+/// 누가 어디에서 관리 작업을 수행했는지를 나타낸다.
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: String,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl AuditContext {
+    /// HTTP 요청으로 수행한 작업
+    pub fn http(user: &AuthenticatedUser, request_id: &RequestId, request: &HttpRequest) -> Self {
+        Self {
+            actor: user.username.clone(),
+            request_id: Some(request_id.to_string()),
+            ip: client_ip(request),
+        }
+    }
+
+    /// 인증되지 않은 요청에서 발생한 작업
+    pub fn unauthenticated(source: &LoginSource) -> Self {
+        Self {
+            actor: "anonymous".into(),
+            request_id: source.request_id.clone(),
+            ip: source.ip.clone(),
+        }
+    }
+
+    /// 관리용 CLI로 수행한 작업
+    /// 운영체제의 사용자 이름을 actor로 기록한다.
+    pub fn cli() -> Self {
+        let user = std::env::var("USER").unwrap_or("unknown".into());
+        Self {
+            actor: format!("cli:{}", user),
+            request_id: None,
+            ip: None,
+        }
+    }
+
+    /// 서버가 주기적으로 수행한 작업
+    pub fn system(task: &str) -> Self {
+        Self {
+            actor: format!("system:{}", task),
+            request_id: None,
+            ip: None,
+        }
+    }
+
+    /// 이 컨텍스트로 감사 로그 항목을 만든다.
+    pub fn entry(
+        &self,
+        action: AuditAction,
+        target: impl ToString,
+        diff: serde_json::Value,
+    ) -> NewAuditEntry {
+        NewAuditEntry {
+            occurred_at: Utc::now(),
+            actor: self.actor.clone(),
+            action,
+            target: Some(target.to_string()),
+            request_id: self.request_id.clone(),
+            ip: self.ip.clone(),
+            diff,
+        }
+    }
+}
+
+/// 감사 로그에 추가할 항목
+#[derive(Debug)]
+pub struct NewAuditEntry {
+    pub occurred_at: DateTime<Utc>,
+    pub actor: String,
+    pub action: AuditAction,
+    pub target: Option<String>,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+    pub diff: serde_json::Value,
+}
+
+/// 감사 로그에 저장된 항목
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub actor: String,
+    pub action: String,
+    pub target: Option<String>,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+    pub diff: serde_json::Value,
+}
+
+/// 감사 로그 조회 조건
+/// 지정하지 않은 조건은 무시한다.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    /// 이 시각 이후(포함)에 기록된 항목
+    pub since: Option<DateTime<Utc>>,
+    /// 이 시각 이전(미포함)에 기록된 항목
+    pub until: Option<DateTime<Utc>>,
+    /// 이 id보다 작은 항목
+    /// 이전 페이지의 `next_cursor`를 그대로 전달한다.
+    pub before: Option<i64>,
+}
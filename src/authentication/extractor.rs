@@ -0,0 +1,194 @@
+use std::{future::Future, marker::PhantomData, ops::Deref, pin::Pin};
+
+use actix_web::{
+    dev::Payload,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use anyhow::Context;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{
+    basic_authentication, bearer_token, hash_token, validate_basic_credentials, LoginSource,
+};
+use crate::database::basic::Zero2ProdDatabase;
+
+#[derive(thiserror::Error, Debug)]
+pub enum AuthError {
+    #[error("Invalid credentials.")]
+    InvalidCredentials(#[source] anyhow::Error),
+    #[error("Two-factor authentication must be enabled first.")]
+    TwoFactorEnrollmentRequired,
+    #[error("Too many failed attempts. Retry after {retry_after} seconds.")]
+    TooManyAttempts { retry_after: i64 },
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::InvalidCredentials(_) => StatusCode::UNAUTHORIZED,
+            AuthError::TwoFactorEnrollmentRequired => StatusCode::FORBIDDEN,
+            AuthError::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuthError::InvalidCredentials(_) => HttpResponse::Unauthorized()
+                // 클라이언트가 Basic 인증을 사용해야 함을 알린다.
+                .insert_header((
+                    header::WWW_AUTHENTICATE,
+                    HeaderValue::from_static(r#"Basic realm="admin""#),
+                ))
+                .finish(),
+            AuthError::TwoFactorEnrollmentRequired => {
+                HttpResponse::Forbidden().body(self.to_string())
+            }
+            AuthError::TooManyAttempts { retry_after } => HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, *retry_after))
+                .body(self.to_string()),
+            AuthError::UnexpectedError(_) => HttpResponse::InternalServerError().finish(),
+        }
+    }
+}
+
+/// 관리자 계정의 역할
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserRole {
+    /// 보안 설정을 변경할 수 있다.
+    Owner,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Owner => "owner",
+            UserRole::Admin => "admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, anyhow::Error> {
+        match s {
+            "owner" => Ok(Self::Owner),
+            "admin" => Ok(Self::Admin),
+            other => Err(anyhow::anyhow!("{} is not a valid user role.", other)),
+        }
+    }
+}
+
+/// 자격 증명을 확인한 사용자
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: UserRole,
+    pub two_factor_enabled: bool,
+}
+
+/// 세션 토큰이나 Basic 인증으로 자격 증명을 확인한 사용자를 추출한다.
+///
+/// 보안 정책을 확인하지 않으므로 2단계 인증 등록처럼
+/// 정책을 충족하기 위한 엔드포인트에서만 직접 사용한다.
+/// 나머지 관리용 엔드포인트는 `AdminUser`를 사용한다.
+/// `D`는 애플리케이션 상태에 등록된 데이터베이스의 타입이다.
+pub struct Authenticated<D>(AuthenticatedUser, PhantomData<D>);
+
+impl<D> Deref for Authenticated<D> {
+    type Target = AuthenticatedUser;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<D: Zero2ProdDatabase> FromRequest for Authenticated<D> {
+    type Error = AuthError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = bearer_token(req.headers());
+        let credentials = basic_authentication(req.headers());
+        let source = LoginSource::from_request(req);
+        let pool = req.app_data::<web::Data<D>>().cloned();
+        Box::pin(async move {
+            let pool = pool.context("The database pool is not registered.")?;
+            let user = match (token, credentials) {
+                // `POST /admin/login`으로 발급받은 세션 토큰
+                (Ok(token), _) => {
+                    let session = pool
+                        .fetch_session_user(&hash_token(&token), Utc::now())
+                        .await
+                        .context("Failed to fetch the session.")?
+                        .ok_or_else(|| anyhow::anyhow!("Unknown or expired session."))
+                        .map_err(AuthError::InvalidCredentials)?;
+                    if session.second_factor_pending {
+                        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+                            "The second factor has not been verified."
+                        )));
+                    }
+                    AuthenticatedUser {
+                        user_id: session.user_id,
+                        username: session.username,
+                        role: UserRole::parse(&session.role)?,
+                        two_factor_enabled: session.totp_enabled,
+                    }
+                }
+                (Err(_), Ok(credentials)) => {
+                    let user =
+                        validate_basic_credentials(credentials, &source, pool.get_ref()).await?;
+                    // 2단계 인증을 사용하는 계정은 로그인 절차를 거쳐야 한다.
+                    if user.two_factor_enabled {
+                        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+                            "Basic authentication is not allowed with two-factor authentication."
+                        )));
+                    }
+                    user
+                }
+                (Err(e), Err(_)) => return Err(AuthError::InvalidCredentials(e)),
+            };
+            Ok(Authenticated(user, PhantomData))
+        })
+    }
+}
+
+/// 인증과 보안 정책을 모두 통과한 관리자
+///
+/// 핸들러의 인자로 사용하면 인증된 요청만 핸들러에 도달한다.
+pub struct AdminUser<D>(AuthenticatedUser, PhantomData<D>);
+
+impl<D> Deref for AdminUser<D> {
+    type Target = AuthenticatedUser;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<D: Zero2ProdDatabase> FromRequest for AdminUser<D> {
+    type Error = AuthError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = Authenticated::<D>::from_request(req, payload);
+        let pool = req.app_data::<web::Data<D>>().cloned();
+        Box::pin(async move {
+            let Authenticated(user, _) = user.await?;
+            let pool = pool.context("The database pool is not registered.")?;
+            if !user.two_factor_enabled
+                && pool
+                    .fetch_require_two_factor()
+                    .await
+                    .context("Failed to fetch the security settings.")?
+            {
+                return Err(AuthError::TwoFactorEnrollmentRequired);
+            }
+            Ok(AdminUser(user, PhantomData))
+        })
+    }
+}
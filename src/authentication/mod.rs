@@ -0,0 +1,11 @@
+mod extractor;
+mod password;
+mod session;
+mod throttle;
+mod two_factor;
+
+pub use extractor::*;
+pub use password::*;
+pub use session::*;
+pub use throttle::*;
+pub use two_factor::*;
@@ -0,0 +1,159 @@
+use actix_web::http::header::{self, HeaderMap};
+use anyhow::Context;
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+};
+use base64::Engine;
+use secrecy::{ExposeSecret, Secret};
+
+use super::{
+    begin_login_attempt, check_login_throttle, finish_first_factor, finish_successful_login,
+    record_failed_attempt, AuthError, AuthenticatedUser, LoginSource, UserRole,
+};
+use crate::database::basic::Zero2ProdDatabase;
+
+/// 비밀번호를 Argon2id로 해싱해서 PHC 문자열 형식으로 반환한다.
+///
+/// 솔트는 매번 무작위로 생성하므로 같은 비밀번호라도 해시값은 달라진다.
+pub fn compute_password_hash(password: Secret<String>) -> Result<Secret<String>, anyhow::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    // OWASP가 권장하는 파라미터를 사용한다.
+    let password_hash = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(15000, 2, 1, None)?,
+    )
+    .hash_password(password.expose_secret().as_bytes(), &salt)?
+    .to_string();
+    Ok(Secret::new(password_hash))
+}
+
+pub struct Credentials {
+    pub username: String,
+    pub password: Secret<String>,
+}
+
+/// `Authorization: Basic ...` 헤더에서 자격 증명을 추출한다.
+pub fn basic_authentication(headers: &HeaderMap) -> Result<Credentials, anyhow::Error> {
+    // 헤더값이 존재한다면 유효한 UTF8 문자열이어야 한다.
+    let header_value = headers
+        .get(header::AUTHORIZATION)
+        .context("The 'Authorization' header was missing.")?
+        .to_str()
+        .context("The 'Authorization' header was not a valid UTF8 string.")?;
+    let base64encoded_segment = header_value
+        .strip_prefix("Basic ")
+        .context("The authorization scheme was not 'Basic'.")?;
+    let decoded_bytes = base64::engine::general_purpose::STANDARD
+        .decode(base64encoded_segment)
+        .context("Failed to base64-decode 'Basic' credentials.")?;
+    let decoded_credentials = String::from_utf8(decoded_bytes)
+        .context("The decoded credential string is not valid UTF8.")?;
+
+    // `:` 구분자를 사용해서 두 개의 세그먼트로 나눈다.
+    let (username, password) = decoded_credentials
+        .split_once(':')
+        .context("A password must be provided in 'Basic' auth.")?;
+    Ok(Credentials {
+        username: username.to_string(),
+        password: Secret::new(password.to_string()),
+    })
+}
+
+/// 로그인할 때 자격 증명을 확인하고 사용자의 정보를 반환한다.
+///
+/// 사용자가 존재하지 않거나 비활성화된 경우에도 해시 검증을 수행해서
+/// 응답 시간으로 사용자의 존재 여부를 추측할 수 없게 한다.
+/// 실패가 반복되면 `AuthError::TooManyAttempts`로 시도 자체를 거부한다.
+#[tracing::instrument(name = "Validate credentials", skip(credentials, source, pool))]
+pub async fn validate_credentials(
+    credentials: Credentials,
+    source: &LoginSource,
+    pool: &impl Zero2ProdDatabase,
+) -> Result<AuthenticatedUser, AuthError> {
+    begin_login_attempt(pool, &credentials.username, source).await?;
+    let username = credentials.username.clone();
+    let user = verify_credentials(credentials, pool).await?;
+    if user.two_factor_enabled {
+        finish_first_factor(pool, &username, source).await?;
+    } else {
+        finish_successful_login(pool, &username, source).await?;
+    }
+    Ok(user)
+}
+
+/// 로그인 이외의 엔드포인트에서 Basic 인증의 자격 증명을 확인한다.
+///
+/// 성공한 요청은 실패 기록에 쓰지 않고, 확인에 실패한 경우에만 실패를 기록한다.
+#[tracing::instrument(name = "Validate basic credentials", skip(credentials, source, pool))]
+pub async fn validate_basic_credentials(
+    credentials: Credentials,
+    source: &LoginSource,
+    pool: &impl Zero2ProdDatabase,
+) -> Result<AuthenticatedUser, AuthError> {
+    check_login_throttle(pool, &credentials.username, source).await?;
+    let username = credentials.username.clone();
+    match verify_credentials(credentials, pool).await {
+        Err(AuthError::InvalidCredentials(e)) => {
+            record_failed_attempt(pool, &username, source).await?;
+            Err(AuthError::InvalidCredentials(e))
+        }
+        result => result,
+    }
+}
+
+async fn verify_credentials(
+    credentials: Credentials,
+    pool: &impl Zero2ProdDatabase,
+) -> Result<AuthenticatedUser, AuthError> {
+    let mut user = None;
+    let mut expected_password_hash = Secret::new(
+        "$argon2id$v=19$m=15000,t=2,p=1$\
+        gZiV/M1gPc22ElAH/Jh1Hw$\
+        CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno"
+            .to_string(),
+    );
+
+    if let Some(stored) = pool
+        .fetch_user_credentials(&credentials.username)
+        .await
+        .context("Failed to retrieve stored credentials.")?
+    {
+        if !stored.disabled {
+            user = Some(AuthenticatedUser {
+                user_id: stored.user_id,
+                username: credentials.username.clone(),
+                role: UserRole::parse(&stored.role)?,
+                two_factor_enabled: stored.totp_enabled,
+            });
+        }
+        expected_password_hash = stored.password_hash;
+    }
+
+    // 해시 검증은 CPU를 많이 사용하므로 블로킹 스레드에서 수행한다.
+    tokio::task::spawn_blocking(move || {
+        verify_password_hash(expected_password_hash, credentials.password)
+    })
+    .await
+    .context("Failed to spawn blocking task.")??;
+
+    user.ok_or_else(|| anyhow::anyhow!("Unknown or disabled username."))
+        .map_err(AuthError::InvalidCredentials)
+}
+
+#[tracing::instrument(name = "Verify password hash", skip_all)]
+fn verify_password_hash(
+    expected_password_hash: Secret<String>,
+    password_candidate: Secret<String>,
+) -> Result<(), AuthError> {
+    let expected_password_hash = PasswordHash::new(expected_password_hash.expose_secret())
+        .context("Failed to parse hash in PHC string format.")?;
+    Argon2::default()
+        .verify_password(
+            password_candidate.expose_secret().as_bytes(),
+            &expected_password_hash,
+        )
+        .context("Invalid password.")
+        .map_err(AuthError::InvalidCredentials)
+}
@@ -0,0 +1,41 @@
+use actix_web::http::header::{self, HeaderMap};
+use anyhow::Context;
+use rand::{distributions::Alphanumeric, Rng};
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+
+/// 로그인 세션의 유효 기간
+pub const SESSION_LIFETIME: chrono::Duration = chrono::Duration::hours(12);
+/// 2단계 인증을 기다리는 세션의 유효 기간
+pub const PENDING_SESSION_LIFETIME: chrono::Duration = chrono::Duration::minutes(5);
+
+/// 세션 토큰을 무작위로 생성한다.
+pub fn generate_session_token() -> Secret<String> {
+    let token = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    Secret::new(token)
+}
+
+/// 토큰이나 복구 코드처럼 엔트로피가 충분한 값을 저장용으로 해싱한다.
+///
+/// 비밀번호와 달리 무차별 대입이 불가능하므로 느린 해시 함수를 사용하지 않는다.
+pub fn hash_token(token: &Secret<String>) -> String {
+    let digest = Sha256::digest(token.expose_secret().as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// `Authorization: Bearer ...` 헤더에서 세션 토큰을 추출한다.
+pub fn bearer_token(headers: &HeaderMap) -> Result<Secret<String>, anyhow::Error> {
+    let header_value = headers
+        .get(header::AUTHORIZATION)
+        .context("The 'Authorization' header was missing.")?
+        .to_str()
+        .context("The 'Authorization' header was not a valid UTF8 string.")?;
+    let token = header_value
+        .strip_prefix("Bearer ")
+        .context("The authorization scheme was not 'Bearer'.")?;
+    Ok(Secret::new(token.to_string()))
+}
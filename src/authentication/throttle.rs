@@ -0,0 +1,281 @@
+use actix_web::{HttpMessage, HttpRequest};
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use tracing_actix_web::RequestId;
+
+use super::AuthError;
+use crate::{
+    audit::{AuditAction, AuditContext},
+    database::basic::{LoginThrottle, Zero2ProdDatabase, Zero2ProdTransaction},
+};
+
+/// 인증 실패를 세는 단위
+#[derive(Debug, Clone, Copy)]
+pub enum ThrottleScope {
+    Username,
+    Ip,
+}
+
+impl ThrottleScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThrottleScope::Username => "username",
+            ThrottleScope::Ip => "ip",
+        }
+    }
+
+    fn policy(&self) -> ThrottlePolicy {
+        match self {
+            ThrottleScope::Username => ThrottlePolicy {
+                free_failures: 3,
+                lockout_failures: 10,
+            },
+            // 같은 IP를 여러 관리자가 공유할 수 있으므로 더 관대하게 설정한다.
+            ThrottleScope::Ip => ThrottlePolicy {
+                free_failures: 10,
+                lockout_failures: 50,
+            },
+        }
+    }
+}
+
+/// 이 기간 동안 실패가 없으면 실패 횟수를 새로 센다.
+const FAILURE_WINDOW: Duration = Duration::hours(1);
+/// 지연 시간은 1초부터 실패할 때마다 두 배로 늘어난다.
+const BASE_DELAY: Duration = Duration::seconds(1);
+const MAX_DELAY: Duration = Duration::seconds(30);
+const LOCKOUT_DURATION: Duration = Duration::minutes(15);
+
+struct ThrottlePolicy {
+    /// 지연 없이 허용하는 실패 횟수
+    free_failures: i32,
+    /// 이 횟수만큼 실패하면 `LOCKOUT_DURATION` 동안 잠근다.
+    lockout_failures: i32,
+}
+
+impl ThrottlePolicy {
+    /// 다음 인증 시도를 허용하는 시각
+    fn retry_at(&self, throttle: &LoginThrottle) -> DateTime<Utc> {
+        let delay = match throttle.failures - self.free_failures {
+            exceeded if exceeded < 0 => Duration::zero(),
+            // 2^5초가 이미 `MAX_DELAY`보다 크다.
+            exceeded => (BASE_DELAY * 2i32.pow(exceeded.min(5) as u32)).min(MAX_DELAY),
+        };
+        let retry_at = throttle.last_failure_at + delay;
+        match throttle.locked_until {
+            Some(locked_until) => locked_until.max(retry_at),
+            None => retry_at,
+        }
+    }
+
+    /// 지연 중이거나 잠긴 경우 `AuthError::TooManyAttempts`를 반환한다.
+    fn check(&self, throttle: &LoginThrottle) -> Result<(), AuthError> {
+        let now = Utc::now();
+        let retry_at = self.retry_at(throttle);
+        if retry_at > now {
+            return Err(AuthError::TooManyAttempts {
+                // 남은 시간을 올림해서 알려준다.
+                retry_after: (retry_at - now).num_seconds() + 1,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 인증 시도의 출처
+#[derive(Debug, Clone, Default)]
+pub struct LoginSource {
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// 요청을 보낸 클라이언트의 IP 주소
+/// `X-Forwarded-For`와 `Forwarded`는 클라이언트가 조작할 수 있으므로 실제 연결된 주소를 사용한다.
+/// 감사 로그와 인증 제한이 같은 주소를 기록하도록 이 함수로만 읽는다.
+pub fn client_ip(request: &HttpRequest) -> Option<String> {
+    request.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+impl LoginSource {
+    pub fn from_request(request: &HttpRequest) -> Self {
+        Self {
+            ip: client_ip(request),
+            request_id: request
+                .extensions()
+                .get::<RequestId>()
+                .map(ToString::to_string),
+        }
+    }
+
+    fn subjects<'a>(&'a self, username: &'a str) -> Vec<(ThrottleScope, &'a str)> {
+        let mut subjects = vec![(ThrottleScope::Username, username)];
+        if let Some(ip) = &self.ip {
+            subjects.push((ThrottleScope::Ip, ip.as_str()));
+        }
+        subjects
+    }
+}
+
+/// 인증을 시도하기 전에 호출한다.
+/// 최근 실패 기록에 따라 지연 중이거나 잠긴 경우 인증을 시도하지 않고 거부하고,
+/// 그렇지 않으면 이번 시도를 실패로 미리 센다.
+///
+/// 확인과 기록을 한 트랜잭션에서 수행하므로 동시에 보낸 시도가 모두 확인을 통과할 수 없다.
+/// 인증에 성공하면 `finish_successful_login`으로 미리 센 실패를 되돌린다.
+#[tracing::instrument(name = "Begin login attempt", skip(pool, source))]
+pub async fn begin_login_attempt<D: Zero2ProdDatabase>(
+    pool: &D,
+    username: &str,
+    source: &LoginSource,
+) -> Result<(), AuthError> {
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    for (scope, subject) in source.subjects(username) {
+        let throttle = transaction
+            .lock_login_throttle(scope.as_str(), subject, Utc::now())
+            .await
+            .context("Failed to lock the login throttle.")?;
+        // 기다리는 동안 다른 시도가 기록했을 수 있으므로 잠근 뒤의 시각과 비교한다.
+        if let Err(e) = scope.policy().check(&throttle) {
+            transaction
+                .rollback()
+                .await
+                .context("Failed to roll back the transaction.")?;
+            return Err(e);
+        }
+    }
+    record_login_failure(&transaction, username, source).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(())
+}
+
+/// 기록을 남기지 않고 인증 시도를 허용하는지 확인한다.
+///
+/// 로그인 이외의 엔드포인트에서 Basic 인증을 확인할 때 사용한다.
+/// 올바른 자격 증명으로 보낸 요청은 실패 기록에 쓰지 않으므로 동시에 보내도 거부되지 않는다.
+/// 확인에 실패하면 `record_failed_attempt`로 실패를 기록한다.
+#[tracing::instrument(name = "Check login throttle", skip(pool, source))]
+pub async fn check_login_throttle(
+    pool: &impl Zero2ProdDatabase,
+    username: &str,
+    source: &LoginSource,
+) -> Result<(), AuthError> {
+    for (scope, subject) in source.subjects(username) {
+        let throttle = pool
+            .fetch_login_throttle(scope.as_str(), subject)
+            .await
+            .context("Failed to fetch the login throttle.")?;
+        if let Some(throttle) = throttle {
+            scope.policy().check(&throttle)?;
+        }
+    }
+    Ok(())
+}
+
+/// 자격 증명 확인에 실패한 뒤 실패를 기록한다.
+///
+/// 기록을 잠근 채로 횟수를 늘리고 잠금과 감사 로그를 남기므로
+/// 동시에 실패한 시도가 잠금을 중복해서 기록하지 않는다.
+pub async fn record_failed_attempt<D: Zero2ProdDatabase>(
+    pool: &D,
+    username: &str,
+    source: &LoginSource,
+) -> Result<(), anyhow::Error> {
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    for (scope, subject) in source.subjects(username) {
+        transaction
+            .lock_login_throttle(scope.as_str(), subject, Utc::now())
+            .await
+            .context("Failed to lock the login throttle.")?;
+    }
+    record_login_failure(&transaction, username, source).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(())
+}
+
+/// 인증 실패를 기록하고 한도를 넘으면 잠근다.
+/// 잠금은 감사 로그에 기록한다.
+#[tracing::instrument(name = "Record login failure", skip(pool, source))]
+pub async fn record_login_failure(
+    pool: &impl Zero2ProdDatabase,
+    username: &str,
+    source: &LoginSource,
+) -> Result<(), anyhow::Error> {
+    let now = Utc::now();
+    for (scope, subject) in source.subjects(username) {
+        let throttle = pool
+            .record_login_failure(scope.as_str(), subject, now, now - FAILURE_WINDOW)
+            .await
+            .context("Failed to record the login failure.")?;
+        if throttle.failures < scope.policy().lockout_failures || throttle.locked_until.is_some() {
+            continue;
+        }
+        let locked_until = now + LOCKOUT_DURATION;
+        pool.lock_login(scope.as_str(), subject, locked_until)
+            .await
+            .context("Failed to lock the login.")?;
+        tracing::warn!(scope = scope.as_str(), subject, "Login has been locked.");
+        let entry = AuditContext::unauthenticated(source).entry(
+            AuditAction::LoginLockout,
+            subject,
+            serde_json::json!({
+                "scope": scope.as_str(),
+                "failures": throttle.failures,
+                "locked_until": locked_until,
+            }),
+        );
+        pool.insert_audit_entry(&entry)
+            .await
+            .context("Failed to record the audit entry.")?;
+    }
+    Ok(())
+}
+
+/// 인증에 성공하면 사용자 이름의 실패 기록을 지운다.
+///
+/// 공격자가 자신의 계정으로 로그인해서 카운터를 초기화할 수 있으므로
+/// IP의 실패 기록은 미리 센 이번 시도만 되돌린다.
+pub async fn finish_successful_login(
+    pool: &impl Zero2ProdDatabase,
+    username: &str,
+    source: &LoginSource,
+) -> Result<(), anyhow::Error> {
+    pool.clear_login_failures(ThrottleScope::Username.as_str(), username)
+        .await
+        .context("Failed to clear the login failures.")?;
+    if let Some(ip) = &source.ip {
+        pool.forgive_login_failure(ThrottleScope::Ip.as_str(), ip)
+            .await
+            .context("Failed to forgive the login failure.")?;
+    }
+    Ok(())
+}
+
+/// 2단계 인증이 남은 사용자의 비밀번호를 확인하면 미리 센 이번 시도만 되돌린다.
+///
+/// 여기서 실패 기록을 지우면 비밀번호를 아는 공격자가 로그인을 반복해서
+/// 2단계 인증 코드의 실패 횟수를 초기화할 수 있다.
+/// 실패 기록은 2단계 인증을 마친 뒤 `finish_successful_login`으로 지운다.
+pub async fn finish_first_factor(
+    pool: &impl Zero2ProdDatabase,
+    username: &str,
+    source: &LoginSource,
+) -> Result<(), anyhow::Error> {
+    for (scope, subject) in source.subjects(username) {
+        pool.forgive_login_failure(scope.as_str(), subject)
+            .await
+            .context("Failed to forgive the login failure.")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use secrecy::{ExposeSecret, Secret};
+use totp_rs::{Algorithm, TOTP};
+use uuid::Uuid;
+
+use super::hash_token;
+use crate::database::basic::{TotpState, Zero2ProdDatabase};
+
+/// 인증 앱에 표시되는 발급자 이름
+const TOTP_ISSUER: &str = "zero2prod";
+/// RFC 6238의 기본 타임 스텝(초)
+const TOTP_STEP: u64 = 30;
+/// 2단계 인증을 활성화할 때 발급하는 복구 코드의 수
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// base32로 인코딩된 TOTP 비밀키를 무작위로 생성한다.
+pub fn generate_totp_secret() -> Secret<String> {
+    match totp_rs::Secret::generate_secret().to_encoded() {
+        totp_rs::Secret::Encoded(secret) => Secret::new(secret),
+        totp_rs::Secret::Raw(_) => unreachable!("`to_encoded` always returns an encoded secret."),
+    }
+}
+
+/// 사용자의 TOTP 생성기
+pub fn totp(secret: &Secret<String>, username: &str) -> Result<TOTP, anyhow::Error> {
+    let secret = totp_rs::Secret::Encoded(secret.expose_secret().clone())
+        .to_bytes()
+        .context("The TOTP secret is not valid base32.")?;
+    // 시계 오차는 `verify_totp_code`에서 직접 처리하므로 skew는 0으로 둔다.
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        username.to_string(),
+    )
+    .context("Failed to build the TOTP generator.")
+}
+
+/// 코드가 유효하면 코드의 타임 스텝을 반환한다.
+///
+/// 앞뒤로 한 스텝의 시계 오차를 허용한다.
+/// 반환된 타임 스텝을 기록해서 같은 코드가 다시 사용되는 것을 막아야 한다.
+pub fn verify_totp_code(totp: &TOTP, code: &str, now: DateTime<Utc>) -> Option<i64> {
+    let current_step = now.timestamp().max(0) as u64 / TOTP_STEP;
+    [
+        current_step.saturating_sub(1),
+        current_step,
+        current_step + 1,
+    ]
+    .into_iter()
+    .find(|step| totp.check(code, step * TOTP_STEP))
+    .map(|step| step as i64)
+}
+
+/// 일회용 복구 코드를 생성한다.
+/// `xxxxx-xxxxx` 형식이다.
+pub fn generate_recovery_codes() -> Vec<Secret<String>> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(10)
+                .map(|c| char::from(c).to_ascii_lowercase())
+                .collect();
+            Secret::new(format!("{}-{}", &code[..5], &code[5..]))
+        })
+        .collect()
+}
+
+/// 사용자가 입력한 복구 코드를 저장된 형식으로 정규화한다.
+pub fn normalize_recovery_code(code: &str) -> Secret<String> {
+    Secret::new(code.trim().to_ascii_lowercase())
+}
+
+/// TOTP 코드를 확인하고 사용한 타임 스텝을 기록한다.
+///
+/// 이미 사용한 코드나 그보다 이전의 코드는 거부한다.
+pub async fn check_totp_code(
+    pool: &impl Zero2ProdDatabase,
+    user_id: Uuid,
+    state: &TotpState,
+    code: &str,
+) -> Result<bool, anyhow::Error> {
+    let Some(secret) = &state.secret else {
+        return Ok(false);
+    };
+    let Some(step) = verify_totp_code(&totp(secret, &state.username)?, code.trim(), Utc::now())
+    else {
+        return Ok(false);
+    };
+    let rows_affected = pool
+        .record_totp_step(user_id, step)
+        .await
+        .context("Failed to record the TOTP step.")?;
+    Ok(rows_affected == 1)
+}
+
+/// 복구 코드를 확인하고 사용한 것으로 표시한다.
+pub async fn check_recovery_code(
+    pool: &impl Zero2ProdDatabase,
+    user_id: Uuid,
+    code: &str,
+) -> Result<bool, anyhow::Error> {
+    let code_hash = hash_token(&normalize_recovery_code(code));
+    let rows_affected = pool
+        .use_recovery_code(user_id, &code_hash, Utc::now())
+        .await
+        .context("Failed to use the recovery code.")?;
+    Ok(rows_affected == 1)
+}
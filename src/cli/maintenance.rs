@@ -0,0 +1,33 @@
+use chrono::Utc;
+
+use crate::{
+    configuration::{DefaultDBPool, RetentionSettings},
+    maintenance::run_retention,
+};
+
+#[derive(clap::Subcommand)]
+pub enum MaintenanceCommand {
+    /// 확인하지 않은 구독자와 유예 기간이 지난 삭제된 구독자를 지금 정리한다.
+    /// `retention.enabled`와 관계없이 `retention` 구성을 사용한다.
+    Retention,
+}
+
+impl MaintenanceCommand {
+    pub async fn run(
+        self,
+        pool: &DefaultDBPool,
+        settings: &RetentionSettings,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            MaintenanceCommand::Retention => match run_retention(pool, settings, Utc::now()).await?
+            {
+                Some(report) => println!(
+                    "{} deleted, {} anonymised, {} purged.",
+                    report.deleted, report.anonymised, report.purged
+                ),
+                None => anyhow::bail!("Another instance is running the retention task."),
+            },
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Context;
+
+use crate::{
+    configuration::DefaultDBPool,
+    database::{
+        basic::SqlxDatabase,
+        migration::{check_schema, MigrationState},
+    },
+};
+
+#[derive(clap::Subcommand)]
+pub enum MigrateCommand {
+    /// 적용하지 않은 마이그레이션을 모두 적용한다.
+    Up,
+    /// 마이그레이션의 적용 상태를 출력한다.
+    /// 스키마가 바이너리와 맞지 않으면 실패한다.
+    Status,
+    /// 마지막으로 적용한 마이그레이션을 되돌린다.
+    /// 되돌리는 마이그레이션(`.down.sql`)이 없으면 실패한다.
+    Down,
+}
+
+impl MigrateCommand {
+    pub async fn run(self, pool: &DefaultDBPool) -> Result<(), anyhow::Error> {
+        match self {
+            MigrateCommand::Up => {
+                pool.migrate()
+                    .await
+                    .context("Failed to apply migrations.")?;
+                println!("Migrations have been applied.");
+            }
+            MigrateCommand::Status => {
+                let statuses = pool
+                    .migration_status()
+                    .await
+                    .context("Failed to read the migration status.")?;
+                for status in &statuses {
+                    println!(
+                        "{}\t{}\t{}{}",
+                        status.version,
+                        status.state.as_str(),
+                        status.description,
+                        if status.reversible {
+                            " (reversible)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+                check_schema(&statuses)?;
+            }
+            MigrateCommand::Down => {
+                let statuses = pool
+                    .migration_status()
+                    .await
+                    .context("Failed to read the migration status.")?;
+                let mut applied = statuses
+                    .iter()
+                    .rev()
+                    .filter(|status| status.state != MigrationState::Pending);
+                let Some(latest) = applied.next() else {
+                    anyhow::bail!("There is no applied migration.");
+                };
+                if latest.state != MigrationState::Applied {
+                    anyhow::bail!(
+                        "Migration {} is {}. Fix it before reverting.",
+                        latest.version,
+                        latest.state.as_str()
+                    );
+                }
+                if !latest.reversible {
+                    anyhow::bail!("Migration {} is not reversible.", latest.version);
+                }
+                // 바로 이전에 적용된 마이그레이션까지만 남긴다.
+                let target = applied.next().map(|status| status.version).unwrap_or(0);
+                pool.revert_migrations(target)
+                    .await
+                    .context("Failed to revert the migration.")?;
+                println!(
+                    "Migration {} {} has been reverted.",
+                    latest.version, latest.description
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+mod maintenance;
+mod migrate;
+mod subscribers;
+mod users;
+
+pub use maintenance::*;
+pub use migrate::*;
+pub use subscribers::*;
+pub use users::*;
+
+use anyhow::Context;
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    configuration::{DefaultDBPool, Settings},
+    database::basic::{Zero2ProdDatabase, Zero2ProdTransaction},
+};
+
+/// 뉴스레터 서버와 관리용 명령
+///
+/// 하위 명령을 지정하지 않으면 서버를 구동한다.
+#[derive(clap::Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// 구성 파일을 읽을 디렉터리
+    /// 지정하지 않으면 `APP_CONFIG_DIR`, 현재 디렉터리의 `configuration` 순으로 찾는다.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub config_dir: Option<std::path::PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// 관리용 하위 명령
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// 바이너리에 포함된 마이그레이션을 관리한다.
+    /// 하위 명령을 지정하지 않으면 `up`을 실행한다.
+    Migrate {
+        #[command(subcommand)]
+        command: Option<MigrateCommand>,
+    },
+    /// 관리자 계정을 관리한다.
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// 구독자를 관리한다.
+    #[command(subcommand)]
+    Subscribers(SubscribersCommand),
+    /// 주기적인 관리 작업을 바로 실행한다.
+    #[command(subcommand)]
+    Maintenance(MaintenanceCommand),
+}
+
+impl Command {
+    pub async fn run(
+        self,
+        pool: &DefaultDBPool,
+        configuration: &Settings,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Command::Migrate { command } => command.unwrap_or(MigrateCommand::Up).run(pool).await,
+            Command::Users(command) => command.run(pool).await,
+            Command::Subscribers(command) => command.run(pool, configuration).await,
+            Command::Maintenance(command) => command.run(pool, &configuration.retention).await,
+        }
+    }
+}
+
+/// 변경과 감사 로그를 함께 적용하기 위한 트랜잭션을 시작한다.
+async fn begin<D: Zero2ProdDatabase>(pool: &D) -> Result<D::Transaction, anyhow::Error> {
+    pool.begin().await.context("Failed to begin a transaction.")
+}
+
+async fn commit(transaction: impl Zero2ProdTransaction) -> Result<(), anyhow::Error> {
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")
+}
+
+/// CLI로 수행한 관리 작업을 감사 로그에 기록한다.
+/// 변경과 함께 커밋되도록 변경을 적용한 트랜잭션에서 기록한다.
+async fn record_audit_entry(
+    transaction: &impl Zero2ProdTransaction,
+    action: AuditAction,
+    target: impl ToString,
+    diff: serde_json::Value,
+) -> Result<(), anyhow::Error> {
+    let entry = AuditContext::cli().entry(action, target, diff);
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    Ok(())
+}
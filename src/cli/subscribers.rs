@@ -0,0 +1,238 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{begin, commit, record_audit_entry};
+use crate::{
+    audit::AuditAction,
+    configuration::{DefaultDBPool, Settings},
+    database::basic::{Subscriber, Zero2ProdDatabase},
+    import::{parse_import, ImportReport},
+};
+
+#[derive(clap::Subcommand)]
+pub enum SubscribersCommand {
+    /// 모든 구독자를 출력한다.
+    List,
+    /// 이메일이나 이름에 `query`가 포함된 구독자를 출력한다.
+    Search { query: String },
+    /// 구독자를 CSV 형식으로 내보낸다.
+    Export {
+        /// 출력할 파일 경로
+        /// 지정하지 않으면 stdout으로 출력한다.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// CSV 파일에서 구독자를 가져온다.
+    /// 열은 `email`, `name`, `subscribed_at`(선택), `list`(선택)이다.
+    Import {
+        /// 읽을 파일 경로
+        /// 지정하지 않으면 stdin에서 읽는다.
+        input: Option<PathBuf>,
+    },
+    /// 구독자를 수동으로 확인 상태로 변경한다.
+    Confirm { id: Uuid },
+    /// 구독자를 삭제한다.
+    /// `retention.deleted_subscriber_grace_days` 동안은 복원할 수 있다.
+    Remove { id: Uuid },
+    /// 삭제한 구독자를 복원한다.
+    Restore { id: Uuid },
+}
+
+impl SubscribersCommand {
+    pub async fn run(
+        self,
+        pool: &DefaultDBPool,
+        configuration: &Settings,
+    ) -> Result<(), anyhow::Error> {
+        let email_rules = &configuration.application.email_rules;
+        match self {
+            SubscribersCommand::List => print_subscribers(pool, None).await?,
+            SubscribersCommand::Search { query } => print_subscribers(pool, Some(&query)).await?,
+            SubscribersCommand::Export { output } => {
+                let subscribers = fetch_subscribers(pool, None).await?;
+                match output {
+                    Some(path) => {
+                        let file = std::fs::File::create(&path)
+                            .with_context(|| format!("Failed to create {}.", path.display()))?;
+                        write_csv(file, &subscribers)?;
+                    }
+                    None => write_csv(std::io::stdout().lock(), &subscribers)?,
+                }
+            }
+            SubscribersCommand::Import { input } => {
+                let parsed = match input {
+                    Some(path) => {
+                        let file = std::fs::File::open(&path)
+                            .with_context(|| format!("Failed to open {}.", path.display()))?;
+                        parse_import(file, email_rules)?
+                    }
+                    None => parse_import(std::io::stdin().lock(), email_rules)?,
+                };
+                let transaction = begin(pool).await?;
+                let counts = transaction
+                    .import_subscribers(&parsed.subscribers)
+                    .await
+                    .context("Failed to import subscribers.")?;
+                let report = ImportReport::new(parsed.subscribers.len(), parsed.errors, counts);
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::SubscriberImport,
+                    "subscriptions",
+                    report.summary(),
+                )
+                .await?;
+                commit(transaction).await?;
+                // 잘못된 행은 stderr로 출력한다.
+                for error in &report.errors {
+                    eprintln!(
+                        "line {}\t{}\t{}",
+                        error.line,
+                        error.email.as_deref().unwrap_or_default(),
+                        error.error
+                    );
+                }
+                println!(
+                    "{} inserted, {} updated, {} skipped, {} rejected.",
+                    report.inserted,
+                    report.updated,
+                    report.skipped,
+                    report.errors.len()
+                );
+            }
+            SubscribersCommand::Confirm { id } => {
+                let transaction = begin(pool).await?;
+                let subscriber = fetch_subscriber(&transaction, id).await?;
+                let rows_affected = transaction
+                    .confirm_subscriber(id)
+                    .await
+                    .context("Failed to confirm the subscriber.")?;
+                if rows_affected == 0 {
+                    anyhow::bail!("Subscriber {} does not exist.", id);
+                }
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::SubscriberConfirm,
+                    id,
+                    serde_json::json!({
+                        "status": { "from": subscriber.status, "to": "confirmed" }
+                    }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("Subscriber {} has been confirmed.", id);
+            }
+            SubscribersCommand::Remove { id } => {
+                let transaction = begin(pool).await?;
+                let subscriber = fetch_subscriber(&transaction, id).await?;
+                let rows_affected = transaction
+                    .delete_subscriber(id, Utc::now())
+                    .await
+                    .context("Failed to remove the subscriber.")?;
+                if rows_affected == 0 {
+                    anyhow::bail!("Subscriber {} does not exist.", id);
+                }
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::SubscriberDelete,
+                    id,
+                    serde_json::json!({ "before": subscriber }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("Subscriber {} has been removed.", id);
+            }
+            SubscribersCommand::Restore { id } => {
+                let transaction = begin(pool).await?;
+                let deleted_after = configuration.retention.restorable_after(Utc::now());
+                let rows_affected = transaction
+                    .restore_subscriber(id, deleted_after)
+                    .await
+                    .context("Failed to restore the subscriber.")?;
+                if rows_affected == 0 {
+                    anyhow::bail!("Subscriber {} cannot be restored.", id);
+                }
+                let subscriber = fetch_subscriber(&transaction, id).await?;
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::SubscriberRestore,
+                    id,
+                    serde_json::json!({ "after": subscriber }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("Subscriber {} has been restored.", id);
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn fetch_subscribers(
+    pool: &DefaultDBPool,
+    search: Option<&str>,
+) -> Result<Vec<Subscriber>, anyhow::Error> {
+    pool.fetch_subscribers(search)
+        .await
+        .context("Failed to fetch subscribers.")
+}
+
+async fn fetch_subscriber(
+    pool: &impl Zero2ProdDatabase,
+    id: Uuid,
+) -> Result<Subscriber, anyhow::Error> {
+    pool.fetch_subscriber(id)
+        .await
+        .context("Failed to fetch the subscriber.")?
+        .with_context(|| format!("Subscriber {} does not exist.", id))
+}
+
+async fn print_subscribers(
+    pool: &DefaultDBPool,
+    search: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    for subscriber in fetch_subscribers(pool, search).await? {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            subscriber.id,
+            subscriber.email,
+            subscriber.name,
+            subscriber.status,
+            subscriber.subscribed_at.to_rfc3339()
+        );
+    }
+    Ok(())
+}
+
+/// 내보내는 CSV의 한 행
+/// CSV에는 중첩된 값을 쓸 수 없으므로 속성은 JSON 문자열로 쓴다.
+#[derive(serde::Serialize)]
+struct CsvRow<'a> {
+    id: Uuid,
+    email: &'a str,
+    name: &'a str,
+    status: &'a str,
+    subscribed_at: chrono::DateTime<Utc>,
+    attributes: String,
+}
+
+fn write_csv(writer: impl std::io::Write, subscribers: &[Subscriber]) -> Result<(), anyhow::Error> {
+    // 헤더는 `CsvRow`의 필드 이름으로 작성된다.
+    let mut writer = csv::Writer::from_writer(writer);
+    for subscriber in subscribers {
+        writer
+            .serialize(CsvRow {
+                id: subscriber.id,
+                email: &subscriber.email,
+                name: &subscriber.name,
+                status: &subscriber.status,
+                subscribed_at: subscriber.subscribed_at,
+                attributes: subscriber.attributes.to_string(),
+            })
+            .context("Failed to write a subscriber.")?;
+    }
+    writer.flush().context("Failed to flush the CSV output.")?;
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::io::IsTerminal;
+
+use anyhow::Context;
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use uuid::Uuid;
+
+use super::{begin, commit, record_audit_entry};
+use crate::{
+    audit::AuditAction,
+    authentication::{compute_password_hash, ThrottleScope, UserRole},
+    configuration::DefaultDBPool,
+    database::basic::Zero2ProdDatabase,
+};
+
+#[derive(clap::Subcommand)]
+pub enum UsersCommand {
+    /// 관리자 계정을 생성한다.
+    /// 비밀번호는 터미널에서 입력받거나 stdin의 첫 줄에서 읽는다.
+    Create {
+        username: String,
+        /// 보안 설정을 변경할 수 있는 owner 계정으로 생성한다.
+        #[arg(long)]
+        owner: bool,
+    },
+    /// 관리자 계정을 비활성화한다.
+    Disable { username: String },
+    /// 비활성화된 관리자 계정을 다시 활성화한다.
+    Enable { username: String },
+    /// 인증 기기를 분실한 관리자의 2단계 인증을 해제한다.
+    ResetTwoFactor { username: String },
+    /// 인증 실패로 잠긴 관리자 계정의 잠금과 실패 기록을 해제한다.
+    Unlock {
+        username: String,
+        /// 관리자가 접속하는 IP의 잠금과 실패 기록도 해제한다.
+        /// IP별 잠금은 사용자 이름과 연결되어 있지 않으므로 주소를 지정해야 한다.
+        #[arg(long, value_name = "ADDRESS")]
+        ip: Vec<String>,
+    },
+}
+
+impl UsersCommand {
+    pub async fn run(self, pool: &DefaultDBPool) -> Result<(), anyhow::Error> {
+        match self {
+            UsersCommand::Create { username, owner } => {
+                // 셸 히스토리에 남지 않도록 비밀번호는 인자로 받지 않는다.
+                let password = read_password().context("Failed to read the password.")?;
+                if password.expose_secret().is_empty() {
+                    anyhow::bail!("The password must not be empty.");
+                }
+                let password_hash = compute_password_hash(password)?;
+                let user_id = Uuid::new_v4();
+                let role = if owner {
+                    UserRole::Owner
+                } else {
+                    UserRole::Admin
+                };
+                let transaction = begin(pool).await?;
+                transaction
+                    .insert_user(
+                        user_id,
+                        &username,
+                        password_hash.expose_secret(),
+                        role.as_str(),
+                        Utc::now(),
+                    )
+                    .await
+                    .context("Failed to insert the user.")?;
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::UserCreate,
+                    &username,
+                    serde_json::json!({ "user_id": user_id, "role": role.as_str() }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("User '{}' has been created.", username);
+            }
+            UsersCommand::Disable { username } => {
+                let transaction = begin(pool).await?;
+                set_user_disabled(&transaction, &username, true).await?;
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::UserDisable,
+                    &username,
+                    serde_json::json!({ "disabled": true }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("User '{}' has been disabled.", username);
+            }
+            UsersCommand::Enable { username } => {
+                let transaction = begin(pool).await?;
+                set_user_disabled(&transaction, &username, false).await?;
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::UserEnable,
+                    &username,
+                    serde_json::json!({ "disabled": false }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("User '{}' has been enabled.", username);
+            }
+            UsersCommand::ResetTwoFactor { username } => {
+                let transaction = begin(pool).await?;
+                let user = transaction
+                    .fetch_user_credentials(&username)
+                    .await
+                    .context("Failed to fetch the user.")?
+                    .with_context(|| format!("User '{}' does not exist.", username))?;
+                transaction
+                    .disable_totp(user.user_id)
+                    .await
+                    .context("Failed to reset two-factor authentication.")?;
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::TwoFactorDisable,
+                    &username,
+                    serde_json::json!({ "totp_enabled": { "from": user.totp_enabled, "to": false } }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!(
+                    "Two-factor authentication of user '{}' has been reset.",
+                    username
+                );
+            }
+            UsersCommand::Unlock { username, ip } => {
+                let transaction = begin(pool).await?;
+                let mut rows_affected = transaction
+                    .clear_login_failures(ThrottleScope::Username.as_str(), &username)
+                    .await
+                    .context("Failed to unlock the user.")?;
+                for ip in &ip {
+                    rows_affected += transaction
+                        .clear_login_failures(ThrottleScope::Ip.as_str(), ip)
+                        .await
+                        .with_context(|| format!("Failed to unlock {}.", ip))?;
+                }
+                if rows_affected == 0 {
+                    println!("User '{}' has no failed login attempts.", username);
+                    return Ok(());
+                }
+                record_audit_entry(
+                    &transaction,
+                    AuditAction::UserUnlock,
+                    &username,
+                    serde_json::json!({ "ips": ip }),
+                )
+                .await?;
+                commit(transaction).await?;
+                println!("User '{}' has been unlocked.", username);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 터미널이면 입력을 화면에 표시하지 않고 비밀번호를 읽는다.
+/// 파이프로 전달된 경우에는 첫 줄을 비밀번호로 사용한다.
+fn read_password() -> Result<Secret<String>, std::io::Error> {
+    let stdin = std::io::stdin();
+    let password = if stdin.is_terminal() {
+        rpassword::prompt_password("Password: ")?
+    } else {
+        let mut line = String::new();
+        stdin.read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    };
+    Ok(Secret::new(password))
+}
+
+async fn set_user_disabled(
+    pool: &impl Zero2ProdDatabase,
+    username: &str,
+    disabled: bool,
+) -> Result<(), anyhow::Error> {
+    let rows_affected = pool
+        .set_user_disabled(username, disabled)
+        .await
+        .context("Failed to update the user.")?;
+    if rows_affected == 0 {
+        anyhow::bail!("User '{}' does not exist.", username);
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use secrecy::Secret;
+
+use super::{ConfigurationError, ConfigurationProblem, DatabaseSettings};
+
+/// 파일에서 비밀 값을 읽는다.
+/// Docker와 Kubernetes의 시크릿 파일처럼 끝에 붙은 줄바꿈은 제거한다.
+pub fn read_secret_file(path: &Path) -> Result<Secret<String>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("{} could not be read: {}", path.display(), e))?;
+    let secret = contents.trim_end_matches(['\n', '\r']);
+    if secret.is_empty() {
+        return Err(format!("{} is empty.", path.display()));
+    }
+    warn_if_exposed(path);
+    Ok(Secret::new(secret.to_string()))
+}
+
+/// 소유자가 아닌 사용자도 접근할 수 있는 파일이면 경고한다.
+#[cfg(unix)]
+fn warn_if_exposed(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = std::fs::metadata(path) {
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            tracing::warn!(
+                "The secret file {} can be accessed by other users (mode {:o}). Consider chmod 600.",
+                path.display(),
+                mode
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_exposed(_path: &Path) {}
+
+impl DatabaseSettings {
+    /// `password_file`과 `url_file`이 지정되어 있으면 파일에서 읽은 값으로 교체한다.
+    /// 파일이 직접 지정한 값보다 우선한다.
+    pub fn read_secret_files(&mut self) -> Result<(), ConfigurationError> {
+        let mut problems = Vec::new();
+        if let Some(path) = &self.password_file {
+            match read_secret_file(path) {
+                Ok(password) => self.password = password,
+                Err(message) => {
+                    problems.push(ConfigurationProblem::new("database.password_file", message))
+                }
+            }
+        }
+        if let Some(path) = &self.url_file {
+            match read_secret_file(path) {
+                Ok(url) => self.url = Some(url),
+                Err(message) => {
+                    problems.push(ConfigurationProblem::new("database.url_file", message))
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigurationError { problems })
+        }
+    }
+}
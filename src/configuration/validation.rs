@@ -0,0 +1,271 @@
+use std::net::IpAddr;
+
+use super::{ApplicationSettings, DatabaseSettings, RetentionSettings, Settings};
+
+/// 구성 값 하나의 문제
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationProblem {
+    /// 문제가 있는 구성 키 (`database.max_connections`)
+    pub key: String,
+    pub message: String,
+    /// 값을 지정한 구성 파일의 경로나 `APP_*` 환경 변수
+    /// 기본값을 사용했으면 `None`이다.
+    pub source: Option<String>,
+}
+
+impl ConfigurationProblem {
+    pub fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            message: message.into(),
+            source: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigurationProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)?;
+        if let Some(source) = &self.source {
+            write!(f, " ({})", source)?;
+        }
+        Ok(())
+    }
+}
+
+/// 구성을 읽거나 검증할 때 발견한 모든 문제
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub struct ConfigurationError {
+    pub problems: Vec<ConfigurationProblem>,
+}
+
+impl std::fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The configuration has {} problem(s):",
+            self.problems.len()
+        )?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ConfigurationProblem> for ConfigurationError {
+    fn from(problem: ConfigurationProblem) -> Self {
+        Self {
+            problems: vec![problem],
+        }
+    }
+}
+
+/// 문제가 없으면 `Ok`를 반환한다.
+fn collect(problems: Vec<ConfigurationProblem>) -> Result<(), ConfigurationError> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigurationError { problems })
+    }
+}
+
+impl Settings {
+    /// 모든 구성을 검증하고 발견한 문제를 한꺼번에 반환한다.
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        let mut problems = Vec::new();
+        for result in [
+            self.application.validate(),
+            self.database.validate(),
+            self.retention.validate(),
+        ] {
+            if let Err(e) = result {
+                problems.extend(e.problems);
+            }
+        }
+        collect(problems)
+    }
+}
+
+impl ApplicationSettings {
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        let mut problems = Vec::new();
+        if self.port == 0 {
+            problems.push(ConfigurationProblem::new(
+                "application.port",
+                "must be between 1 and 65535.",
+            ));
+        }
+        validate_host("application.host", &self.host, &mut problems);
+        collect(problems)
+    }
+}
+
+impl DatabaseSettings {
+    /// 접속 정보와 풀 설정이 올바르고 서로 모순되지 않는지 확인한다.
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        let mut problems = Vec::new();
+        let mut problem = |key: &str, message: String| {
+            problems.push(ConfigurationProblem::new(
+                format!("database.{}", key),
+                message,
+            ))
+        };
+        if self.database_name.is_empty() {
+            problem(
+                "database_name",
+                "is required unless database.url is set.".into(),
+            );
+        }
+        // SQLite는 파일 하나만 사용한다.
+        #[cfg(not(feature = "sqlite"))]
+        {
+            if self.username.is_empty() {
+                problem("username", "is required unless database.url is set.".into());
+            }
+            if self.port == 0 {
+                problem("port", "must be between 1 and 65535.".into());
+            }
+        }
+        if self.ssl_client_cert.is_some() != self.ssl_client_key.is_some() {
+            problem(
+                "ssl_client_key",
+                "must be set together with database.ssl_client_cert.".into(),
+            );
+        }
+        for (name, path) in [
+            ("ssl_root_cert", &self.ssl_root_cert),
+            ("ssl_client_cert", &self.ssl_client_cert),
+            ("ssl_client_key", &self.ssl_client_key),
+        ] {
+            if let Some(path) = path {
+                if !path.is_file() {
+                    problem(
+                        name,
+                        format!("{} does not exist or is not a file.", path.display()),
+                    );
+                }
+            }
+        }
+        if self.max_connections == 0 {
+            problem("max_connections", "must be at least 1.".into());
+        }
+        if self.min_connections > self.max_connections {
+            problem(
+                "min_connections",
+                format!(
+                    "{} must not exceed database.max_connections ({}).",
+                    self.min_connections, self.max_connections
+                ),
+            );
+        }
+        if self.acquire_timeout_seconds == 0 {
+            problem("acquire_timeout_seconds", "must be at least 1.".into());
+        }
+        if self.connect_retry_initial_delay_milliseconds == 0
+            || self.connect_retry_initial_delay_milliseconds
+                > self.connect_retry_max_delay_milliseconds
+        {
+            problem(
+                "connect_retry_initial_delay_milliseconds",
+                format!(
+                    "must be between 1 and database.connect_retry_max_delay_milliseconds ({}).",
+                    self.connect_retry_max_delay_milliseconds
+                ),
+            );
+        }
+        if self.connect_eagerly && self.connect_deadline_seconds == 0 {
+            problem(
+                "connect_deadline_seconds",
+                "must be at least 1 when database.connect_eagerly is set.".into(),
+            );
+        }
+        // Postgres는 63바이트보다 긴 이름을 잘라낸다.
+        if self.application_name.len() > 63 {
+            problem("application_name", "must be at most 63 bytes.".into());
+        }
+        #[cfg(not(feature = "sqlite"))]
+        {
+            if self.host.is_empty() {
+                problems.push(ConfigurationProblem::new(
+                    "database.host",
+                    "is required unless database.url is set.",
+                ));
+            } else {
+                validate_database_host("database.host", &self.host, &mut problems);
+            }
+            for (i, replica) in self.replicas.iter().enumerate() {
+                validate_database_host(
+                    &format!("database.replicas[{}].host", i),
+                    &replica.host,
+                    &mut problems,
+                );
+                if replica.port == 0 {
+                    problems.push(ConfigurationProblem::new(
+                        format!("database.replicas[{}].port", i),
+                        "must be between 1 and 65535.",
+                    ));
+                }
+            }
+        }
+        collect(problems)
+    }
+}
+
+impl RetentionSettings {
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        let mut problems = Vec::new();
+        for (key, value) in [
+            ("interval_seconds", self.interval_seconds),
+            (
+                "unconfirmed_subscriber_days",
+                self.unconfirmed_subscriber_days.into(),
+            ),
+            (
+                "deleted_subscriber_grace_days",
+                self.deleted_subscriber_grace_days.into(),
+            ),
+        ] {
+            if value == 0 {
+                problems.push(ConfigurationProblem::new(
+                    format!("retention.{}", key),
+                    "must be at least 1.",
+                ));
+            }
+        }
+        collect(problems)
+    }
+}
+
+/// Postgres는 절대 경로인 호스트를 Unix 도메인 소켓이 있는 디렉터리로 사용한다.
+#[cfg(not(feature = "sqlite"))]
+fn validate_database_host(key: &str, host: &str, problems: &mut Vec<ConfigurationProblem>) {
+    if !std::path::Path::new(host).is_absolute() {
+        validate_host(key, host, problems);
+    }
+}
+
+/// IP 주소이거나 RFC 1123 형식의 호스트 이름이어야 한다.
+fn validate_host(key: &str, host: &str, problems: &mut Vec<ConfigurationProblem>) {
+    if host.is_empty() {
+        problems.push(ConfigurationProblem::new(key, "is required."));
+        return;
+    }
+    if host.parse::<IpAddr>().is_ok() {
+        return;
+    }
+    let valid = host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid {
+        problems.push(ConfigurationProblem::new(
+            key,
+            format!("{} is not a valid host name or IP address.", host),
+        ));
+    }
+}
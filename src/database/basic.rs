@@ -1,29 +1,528 @@
-use std::ops::Deref;
-
+use base64::Engine;
 use chrono::{DateTime, Utc};
+use secrecy::Secret;
 use sqlx::{types::Uuid, ConnectOptions, Database};
 
-use crate::configuration::DatabaseSettings;
+use super::{error::StorageError, migration::MigrationStatus};
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    configuration::DatabaseSettings,
+    domain::{
+        AttributeSchema, SubscriberAttributes, SubscriberEmail, SubscriberName, SubscriberTag,
+    },
+    segment::SegmentExpression,
+};
+
+/// 구독자 한 명에 대한 레코드
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct Subscriber {
+    pub id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: DateTime<Utc>,
+    /// 관리자가 정의한 속성
+    pub attributes: serde_json::Value,
+}
+
+/// 구독자 레코드의 변경 이력 한 건
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct SubscriberHistoryEntry {
+    pub occurred_at: DateTime<Utc>,
+    /// `insert`, `update`, `delete`, `restore` 중 하나
+    pub operation: String,
+    /// 변경 후의 값. 개인 정보를 지운 구독자의 이력에서는 비어 있다.
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub attributes: serde_json::Value,
+}
+
+/// 구독자 목록에서의 위치
+///
+/// 최신 순으로 정렬한 목록에서 이전 페이지의 마지막 구독자를 가리킨다.
+/// 새로 추가되는 구독자는 항상 앞쪽에 위치하므로 다음 페이지가 밀리지 않는다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SubscriberCursor {
+    pub subscribed_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl From<&Subscriber> for SubscriberCursor {
+    fn from(subscriber: &Subscriber) -> Self {
+        Self {
+            subscribed_at: subscriber.subscribed_at,
+            id: subscriber.id,
+        }
+    }
+}
+
+impl From<SubscriberCursor> for String {
+    fn from(cursor: SubscriberCursor) -> Self {
+        // DB에 저장된 정밀도인 마이크로초까지 표현해야 같은 위치를 가리킨다.
+        let raw = format!(
+            "{}_{}",
+            cursor
+                .subscribed_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            cursor.id
+        );
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+}
+
+impl TryFrom<String> for SubscriberCursor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("{} is not a valid cursor.", value);
+        let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(&value)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+        let (subscribed_at, id) = raw.split_once('_').ok_or_else(invalid)?;
+        Ok(Self {
+            subscribed_at: DateTime::parse_from_rfc3339(subscribed_at)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// 구독자 목록 조회 조건
+/// 지정하지 않은 조건은 무시한다.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SubscriberFilter {
+    pub status: Option<String>,
+    /// 이메일에 이 문자열이 포함된 구독자
+    pub email: Option<String>,
+    /// 이 시각 이후(포함)에 구독한 구독자
+    pub since: Option<DateTime<Utc>>,
+    /// 이 시각 이전(미포함)에 구독한 구독자
+    pub until: Option<DateTime<Utc>>,
+    /// 이전 페이지의 `next_cursor`를 그대로 전달한다.
+    pub cursor: Option<SubscriberCursor>,
+    /// 이 속성을 모두 가진 구독자
+    /// 쿼리 문자열로 받은 조건은 핸들러가 스키마로 검증해서 채운다.
+    #[serde(skip)]
+    pub attributes: Option<SubscriberAttributes>,
+}
+
+/// 한꺼번에 가져올 구독자 한 명
+#[derive(Debug, Clone)]
+pub struct NewSubscriber {
+    pub id: Uuid,
+    pub email: SubscriberEmail,
+    pub name: SubscriberName,
+    pub subscribed_at: DateTime<Utc>,
+}
+
+/// 구독자를 한꺼번에 가져온 결과
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportCounts {
+    /// 새로 추가한 구독자의 수
+    pub inserted: u64,
+    /// 이름을 갱신한 기존 구독자의 수
+    pub updated: u64,
+}
+
+/// 저장된 세그먼트
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct Segment {
+    pub name: String,
+    /// `SegmentExpression::parse`로 해석하는 필터 식
+    pub expression: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 세그먼트에 속한 구독자의 수
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, sqlx::FromRow)]
+pub struct SegmentSize {
+    /// 상태와 관계없이 조건에 맞는 구독자
+    pub total: i64,
+    /// 그 중에서 확인된 구독자. 발송 대상이 된다.
+    pub confirmed: i64,
+}
+
+/// 인증에 필요한 관리자 계정 정보
+pub struct UserCredentials {
+    pub user_id: Uuid,
+    pub password_hash: Secret<String>,
+    pub disabled: bool,
+    pub role: String,
+    pub totp_enabled: bool,
+}
+
+/// 유효한 세션의 사용자 정보
+#[derive(sqlx::FromRow)]
+pub struct SessionUser {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: String,
+    pub totp_enabled: bool,
+    pub second_factor_pending: bool,
+}
+
+/// 사용자의 TOTP 상태
+pub struct TotpState {
+    pub username: String,
+    /// 등록을 시작하면 저장되고 검증을 마치면 `enabled`가 참이 된다.
+    pub secret: Option<Secret<String>>,
+    pub enabled: bool,
+}
+
+/// 인증 실패 기록
+#[derive(sqlx::FromRow)]
+pub struct LoginThrottle {
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
 
 /// 데이터베이스 변경을 편하게 하기 위한 트레이트
+///
+/// HTTP 계층은 이 트레이트에만 의존한다.
+/// 복제본에서 읽을 수 있는 메서드는 문서에 표시한다.
+/// 인증에 관련된 조회는 복제 지연의 영향을 받지 않도록 주 DB에서 읽는다.
+/// 구현이 `sqlx::Pool`일 필요가 없으므로 다른 구현을 감싸는 데코레이터나
+/// 테스트 더블도 핸들러의 변경 없이 사용할 수 있다.
+/// 오류도 `StorageError`로 반환하므로 sqlx의 타입이 드러나지 않는다.
 #[trait_variant::make()]
-pub trait Zero2ProdDatabase: Deref<Target = sqlx::Pool<Self::DB>> {
-    type DB: sqlx::Database;
-    type ConnectOutput: Zero2ProdDatabase<DB = Self::DB>;
+pub trait Zero2ProdDatabase: Send + Sync + 'static {
+    /// 이 저장소에서 시작한 트랜잭션
+    type Transaction: Zero2ProdTransaction;
 
-    /// DB에 연결한다.
-    async fn connect(
-        database_settings: &DatabaseSettings,
-    ) -> Result<Self::ConnectOutput, sqlx::Error>;
+    /// 트랜잭션을 시작한다.
+    /// 트랜잭션 안에서 시작하면 세이브포인트를 만든다.
+    async fn begin(&self) -> Result<Self::Transaction, StorageError>;
 
     /// 구독자를 DB에 추가한다.
+    /// 같은 주소나 같은 정규화된 주소를 가진 구독자가 있으면 실패한다.
     async fn insert_subscriptions(
         &self,
         id: Uuid,
-        email: &str,
+        email: &SubscriberEmail,
         name: &str,
+        attributes: &SubscriberAttributes,
         subscribed_at: DateTime<Utc>,
-    ) -> Result<<Self::DB as sqlx::Database>::QueryResult, sqlx::Error>;
+    ) -> Result<(), StorageError>;
+
+    /// 구독자 목록을 가져온다.
+    /// `search`가 주어지면 이메일이나 이름에 해당 문자열이 포함된 구독자만 가져온다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn fetch_subscribers(
+        &self,
+        search: Option<&str>,
+    ) -> Result<Vec<Subscriber>, StorageError>;
+
+    /// 조건에 맞는 구독자를 최신 순으로 최대 `limit`명 가져온다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn fetch_subscriber_page(
+        &self,
+        filter: &SubscriberFilter,
+        limit: i64,
+    ) -> Result<Vec<Subscriber>, StorageError>;
+
+    /// 구독자 한 명을 가져온다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn fetch_subscriber(&self, id: Uuid) -> Result<Option<Subscriber>, StorageError>;
+
+    /// 구독자를 수동으로 확인 상태로 변경한다.
+    /// 변경된 행의 수를 반환한다.
+    async fn confirm_subscriber(&self, id: Uuid) -> Result<u64, StorageError>;
+
+    /// 구독자를 삭제한 것으로 표시한다.
+    /// 삭제한 구독자는 조회되지 않으며 `restore_subscriber`로 복원할 수 있다.
+    /// 표시한 행의 수를 반환한다.
+    async fn delete_subscriber(
+        &self,
+        id: Uuid,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// `deleted_after` 이후에 삭제한 구독자를 복원한다.
+    /// 같은 주소로 다시 구독한 구독자가 있으면 실패한다.
+    /// 복원한 행의 수를 반환한다.
+    async fn restore_subscriber(
+        &self,
+        id: Uuid,
+        deleted_after: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// `deleted_before` 전에 삭제한 구독자를 변경 이력과 함께 영구히 삭제한다.
+    /// 삭제된 행의 수를 반환한다.
+    async fn purge_deleted_subscribers(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// 구독자 레코드의 변경 이력을 오래된 순으로 가져온다.
+    /// 삭제한 구독자의 이력도 영구히 삭제하기 전까지는 가져올 수 있다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn fetch_subscriber_history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<SubscriberHistoryEntry>, StorageError>;
+
+    /// `subscribed_before` 전에 구독하고 확인하지 않은 구독자를 삭제한 것으로 표시한다.
+    /// `delete_subscriber`와 같이 변경 이력이 남고 유예 기간 동안 복원할 수 있다.
+    /// 표시한 행의 수를 반환한다.
+    async fn delete_unconfirmed_subscribers(
+        &self,
+        subscribed_before: DateTime<Utc>,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// `subscribed_before` 전에 구독하고 확인하지 않은 구독자의 개인 정보를 지운다.
+    /// 이메일은 구독자 id로 만든 주소로 바꾸고 상태는 `anonymised`가 된다.
+    /// 바뀐 행의 수를 반환한다.
+    async fn anonymise_unconfirmed_subscribers(
+        &self,
+        subscribed_before: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// 구독자를 한꺼번에 가져온다.
+    /// 정규화된 주소가 같은 구독자가 있으면 이름을 갱신하고, 없으면 확인된 구독자로 추가한다.
+    /// 대소문자만 다른 주소처럼 다른 제약 조건에 걸리는 구독자는 건너뛴다.
+    /// 트랜잭션이 아니면 모두 가져오거나 하나도 가져오지 않는다.
+    async fn import_subscribers(
+        &self,
+        subscribers: &[NewSubscriber],
+    ) -> Result<ImportCounts, StorageError>;
+
+    /// 구독자의 태그를 이름 순으로 가져온다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn fetch_subscriber_tags(&self, id: Uuid) -> Result<Vec<String>, StorageError>;
+
+    /// 구독자의 태그를 모두 교체한다.
+    /// 트랜잭션이 아니면 모두 교체하거나 하나도 교체하지 않는다.
+    async fn set_subscriber_tags(
+        &self,
+        id: Uuid,
+        tags: &[SubscriberTag],
+    ) -> Result<(), StorageError>;
+
+    /// 저장된 세그먼트를 이름 순으로 가져온다.
+    async fn fetch_segments(&self) -> Result<Vec<Segment>, StorageError>;
+
+    /// 세그먼트 하나를 가져온다.
+    async fn fetch_segment(&self, name: &str) -> Result<Option<Segment>, StorageError>;
+
+    /// 세그먼트를 저장한다. 같은 이름의 세그먼트가 있으면 식을 교체한다.
+    async fn save_segment(
+        &self,
+        name: &str,
+        expression: &str,
+        updated_at: DateTime<Utc>,
+    ) -> Result<(), StorageError>;
+
+    /// 세그먼트를 삭제한다.
+    /// 삭제된 행의 수를 반환한다.
+    async fn delete_segment(&self, name: &str) -> Result<u64, StorageError>;
+
+    /// 세그먼트 식에 맞는 구독자의 수를 센다. 삭제한 구독자는 제외한다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn count_segment(
+        &self,
+        expression: &SegmentExpression,
+    ) -> Result<SegmentSize, StorageError>;
+
+    /// 관리자가 정의한 구독자 속성을 가져온다.
+    async fn fetch_attribute_schema(&self) -> Result<AttributeSchema, StorageError>;
+
+    /// 구독자 속성의 정의를 모두 교체한다.
+    /// 정의에서 빠진 속성의 값은 구독자에게 그대로 남는다.
+    async fn set_attribute_schema(&self, schema: &AttributeSchema) -> Result<(), StorageError>;
+
+    /// 관리자 계정을 추가한다.
+    async fn insert_user(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), StorageError>;
+
+    /// 관리자 계정의 비활성화 여부를 변경한다.
+    /// 변경된 행의 수를 반환한다.
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<u64, StorageError>;
+
+    /// 인증에 필요한 관리자 계정 정보를 가져온다.
+    async fn fetch_user_credentials(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserCredentials>, StorageError>;
+
+    /// 세션을 추가한다.
+    async fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: Uuid,
+        created_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+        second_factor_pending: bool,
+    ) -> Result<(), StorageError>;
+
+    /// 만료되지 않은 세션과 활성화된 사용자의 정보를 가져온다.
+    async fn fetch_session_user(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<SessionUser>, StorageError>;
+
+    /// 2단계 인증을 마친 세션의 만료 시각을 갱신한다.
+    /// 변경된 행의 수를 반환한다.
+    async fn complete_second_factor(
+        &self,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// 세션을 삭제한다.
+    async fn delete_session(&self, token_hash: &str) -> Result<u64, StorageError>;
+
+    /// 사용자의 TOTP 상태를 가져온다.
+    async fn fetch_totp_state(&self, user_id: Uuid) -> Result<Option<TotpState>, StorageError>;
+
+    /// 등록 중인 TOTP 비밀키를 저장한다.
+    /// 이미 2단계 인증이 활성화된 경우에는 변경하지 않는다.
+    async fn set_pending_totp_secret(
+        &self,
+        user_id: Uuid,
+        secret: &str,
+    ) -> Result<u64, StorageError>;
+
+    /// 2단계 인증을 활성화하고 복구 코드를 교체한다.
+    async fn enable_totp(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), StorageError>;
+
+    /// 2단계 인증을 비활성화하고 비밀키와 복구 코드를 삭제한다.
+    async fn disable_totp(&self, user_id: Uuid) -> Result<(), StorageError>;
+
+    /// 사용한 TOTP 타임 스텝을 기록한다.
+    /// 이미 같거나 더 나중의 스텝이 사용되었다면 변경하지 않고 0을 반환한다.
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> Result<u64, StorageError>;
+
+    /// 사용하지 않은 복구 코드를 사용한 것으로 표시한다.
+    /// 일치하는 코드가 없으면 0을 반환한다.
+    async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// 모든 관리자에게 2단계 인증을 요구하는지 여부를 가져온다.
+    async fn fetch_require_two_factor(&self) -> Result<bool, StorageError>;
+
+    /// 모든 관리자에게 2단계 인증을 요구하는지 여부를 변경한다.
+    async fn set_require_two_factor(&self, required: bool) -> Result<(), StorageError>;
+
+    /// 인증 실패 기록을 잠그거나 만들지 않고 가져온다.
+    async fn fetch_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> Result<Option<LoginThrottle>, StorageError>;
+
+    /// 인증 실패 기록을 가져온다. 기록이 없으면 실패 횟수가 0인 기록을 만든다.
+    /// 트랜잭션 안에서 호출하면 트랜잭션이 끝날 때까지 같은 기록에 대한 다른 시도는 기다린다.
+    async fn lock_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: DateTime<Utc>,
+    ) -> Result<LoginThrottle, StorageError>;
+
+    /// 인증 실패 횟수를 하나 늘리고 갱신된 기록을 반환한다.
+    /// 마지막 실패가 `window_start` 이전이거나 잠금이 풀렸다면 횟수를 새로 센다.
+    async fn record_login_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+    ) -> Result<LoginThrottle, StorageError>;
+
+    /// `locked_until`까지 인증을 잠근다.
+    async fn lock_login(
+        &self,
+        scope: &str,
+        subject: &str,
+        locked_until: DateTime<Utc>,
+    ) -> Result<u64, StorageError>;
+
+    /// 미리 센 인증 실패를 하나 되돌린다.
+    async fn forgive_login_failure(&self, scope: &str, subject: &str) -> Result<u64, StorageError>;
+
+    /// 인증 실패 기록과 잠금을 삭제한다.
+    /// 삭제된 행의 수를 반환한다.
+    async fn clear_login_failures(&self, scope: &str, subject: &str) -> Result<u64, StorageError>;
+
+    /// 감사 로그에 항목을 추가한다.
+    async fn insert_audit_entry(&self, entry: &NewAuditEntry) -> Result<(), StorageError>;
+
+    /// 조건에 맞는 감사 로그 항목을 최신 항목부터 최대 `limit`개 가져온다.
+    /// 복제본에서 읽을 수 있으므로 최근의 변경이 보이지 않을 수 있다.
+    async fn fetch_audit_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, StorageError>;
+}
+
+/// 여러 쿼리를 하나로 묶어 적용하는 작업 단위
+///
+/// 저장소와 같은 쿼리 메서드를 제공한다.
+/// `commit`이나 `rollback`을 호출하지 않고 버리면 롤백한다.
+#[trait_variant::make()]
+pub trait Zero2ProdTransaction: Zero2ProdDatabase + Sized {
+    async fn commit(self) -> Result<(), StorageError>;
+
+    /// 트랜잭션이 끝날 때까지 유지되는 잠금을 시도한다.
+    /// 다른 트랜잭션이 같은 `key`로 잠그고 있으면 기다리지 않고 `false`를 반환한다.
+    async fn try_advisory_lock(&self, key: i64) -> Result<bool, StorageError>;
+
+    /// 구독자를 나눠서 가져오기 위한 임시 테이블을 만든다.
+    /// `stage_import`로 구독자를 추가하고 `finish_import`로 반영한다.
+    async fn begin_import(&self) -> Result<(), StorageError>;
+
+    /// 가져올 구독자를 임시 테이블에 추가한다.
+    async fn stage_import(&self, subscribers: &[NewSubscriber]) -> Result<(), StorageError>;
+
+    /// 임시 테이블의 구독자를 `import_subscribers`와 같은 방식으로 반영하고 임시 테이블을 지운다.
+    async fn finish_import(&self) -> Result<ImportCounts, StorageError>;
+
+    async fn rollback(self) -> Result<(), StorageError>;
+}
+
+/// sqlx로 구현한 백엔드의 연결과 마이그레이션
+#[trait_variant::make()]
+pub trait SqlxDatabase: Zero2ProdDatabase + Sized {
+    type DB: sqlx::Database;
+
+    /// DB에 연결한다.
+    async fn connect(database_settings: &DatabaseSettings) -> Result<Self, sqlx::Error>;
+
+    /// 커넥션을 하나 얻어서 DB가 응답하는지 확인한다.
+    async fn ping(&self) -> Result<(), sqlx::Error>;
+
+    /// 바이너리에 포함된 마이그레이션을 적용한다.
+    async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError>;
+
+    /// 바이너리에 포함된 마이그레이션과 DB에 적용된 마이그레이션의 상태를 가져온다.
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>, sqlx::migrate::MigrateError>;
+
+    /// `target`보다 나중에 적용된 마이그레이션을 되돌린다.
+    /// 되돌리는 마이그레이션이 없는 버전은 건너뛴다.
+    async fn revert_migrations(&self, target: i64) -> Result<(), sqlx::migrate::MigrateError>;
 
     fn connect_option_without_db(
         database_settings: &DatabaseSettings,
@@ -0,0 +1,21 @@
+/// 저장소 작업이 실패한 이유
+///
+/// HTTP 계층이 백엔드의 오류 타입에 의존하지 않도록 핸들러가 구분해야 하는 경우만 나눈다.
+#[derive(thiserror::Error, Debug)]
+pub enum StorageError {
+    /// 고유 제약 조건에 걸렸다.
+    #[error("A record with the same key already exists.")]
+    Conflict(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// 그 밖의 실패
+    #[error(transparent)]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<sqlx::Error> for StorageError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => Self::Conflict(Box::new(e)),
+            _ => Self::Other(Box::new(e)),
+        }
+    }
+}
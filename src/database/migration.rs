@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Context;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+
+use super::basic::SqlxDatabase;
+
+/// 마이그레이션의 적용 상태
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    /// 바이너리에 포함되어 있지만 아직 적용하지 않았다.
+    Pending,
+    /// 적용한 뒤에 내용이 바뀌었다.
+    Modified,
+    /// 적용하는 중에 실패했다.
+    Failed,
+    /// 적용되어 있지만 바이너리에 포함되어 있지 않다.
+    /// 더 새로운 바이너리가 적용한 마이그레이션이다.
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Failed => "failed",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+/// 마이그레이션 하나의 상태
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    /// 바이너리에 포함되지 않은 마이그레이션은 비어 있다.
+    pub description: String,
+    pub state: MigrationState,
+    /// 되돌리는 마이그레이션(`.down.sql`)이 있다.
+    pub reversible: bool,
+}
+
+/// 바이너리에 포함된 마이그레이션과 DB에 적용된 마이그레이션을 비교한다.
+/// 결과는 버전 순으로 정렬된다.
+pub async fn migration_status<C: Migrate + Send>(
+    migrator: &Migrator,
+    conn: &mut C,
+) -> Result<Vec<MigrationStatus>, MigrateError> {
+    conn.ensure_migrations_table().await?;
+    let failed = conn.dirty_version().await?;
+    let mut applied: HashMap<_, _> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum))
+        .collect();
+    let reversible: HashSet<_> = migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .collect();
+
+    let mut statuses: Vec<_> = migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let state = match applied.remove(&migration.version) {
+                _ if failed == Some(migration.version) => MigrationState::Failed,
+                Some(checksum) if checksum != migration.checksum => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+                None => MigrationState::Pending,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                reversible: reversible.contains(&migration.version),
+            }
+        })
+        .collect();
+    statuses.extend(applied.into_keys().map(|version| MigrationStatus {
+        version,
+        description: String::new(),
+        state: if failed == Some(version) {
+            MigrationState::Failed
+        } else {
+            MigrationState::Unknown
+        },
+        reversible: false,
+    }));
+    statuses.sort_by_key(|status| status.version);
+    Ok(statuses)
+}
+
+/// DB 스키마가 바이너리가 기대하는 스키마와 다른 경우
+#[derive(thiserror::Error, Debug)]
+pub enum SchemaMismatch {
+    #[error("Migrations {0:?} have been modified or failed. Fix them before serving.")]
+    Inconsistent(Vec<i64>),
+    #[error("The database schema is newer than this binary. Unknown migrations: {0:?}")]
+    Newer(Vec<i64>),
+    #[error("The database schema is older than this binary. Run `migrate up` to apply {0:?}.")]
+    Older(Vec<i64>),
+}
+
+/// 모든 마이그레이션이 적용되어 있고 모르는 마이그레이션이 없는지 확인한다.
+pub fn check_schema(statuses: &[MigrationStatus]) -> Result<(), SchemaMismatch> {
+    let versions = |states: &[MigrationState]| -> Vec<i64> {
+        statuses
+            .iter()
+            .filter(|status| states.contains(&status.state))
+            .map(|status| status.version)
+            .collect()
+    };
+    let inconsistent = versions(&[MigrationState::Modified, MigrationState::Failed]);
+    if !inconsistent.is_empty() {
+        return Err(SchemaMismatch::Inconsistent(inconsistent));
+    }
+    let unknown = versions(&[MigrationState::Unknown]);
+    if !unknown.is_empty() {
+        return Err(SchemaMismatch::Newer(unknown));
+    }
+    let pending = versions(&[MigrationState::Pending]);
+    if !pending.is_empty() {
+        return Err(SchemaMismatch::Older(pending));
+    }
+    Ok(())
+}
+
+/// 서버를 구동하기 전에 DB 스키마를 준비한다.
+///
+/// `run_migrations`가 참이면 마이그레이션을 먼저 적용한다.
+/// 스키마가 바이너리와 맞지 않으면 요청을 처리하지 않도록 오류를 반환한다.
+#[tracing::instrument(name = "Prepare the database schema", skip(pool))]
+pub async fn prepare_schema(
+    pool: &impl SqlxDatabase,
+    run_migrations: bool,
+) -> Result<(), anyhow::Error> {
+    if run_migrations {
+        pool.migrate()
+            .await
+            .context("Failed to apply migrations.")?;
+    }
+    let statuses = pool
+        .migration_status()
+        .await
+        .context("Failed to read the migration status.")?;
+    check_schema(&statuses)?;
+    Ok(())
+}
@@ -1,2 +1,7 @@
 pub mod basic;
+pub mod error;
+pub mod migration;
 pub mod postgres;
+pub mod retry;
+pub mod sqlite;
+pub mod transaction;
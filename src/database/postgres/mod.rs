@@ -1,4 +1,6 @@
 pub mod pool;
 mod query;
+mod replica;
+pub mod transaction;
 
 pub use query::*;
@@ -1,47 +1,144 @@
-use std::ops::Deref;
+use std::{ops::Deref, sync::Arc};
 
 use secrecy::ExposeSecret;
 use sqlx::{
-    postgres::{PgConnectOptions, PgPoolOptions, PgQueryResult, PgSslMode},
-    PgPool, Postgres,
+    migrate::Migrator,
+    pool::PoolConnection,
+    postgres::{PgConnectOptions, PgSslMode},
+    Connection, PgConnection, PgPool, Postgres,
 };
 
-use crate::{configuration::DatabaseSettings, database::basic::Zero2ProdDatabase};
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    configuration::{DatabaseSettings, SslMode},
+    database::{
+        basic::{
+            ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser,
+            SqlxDatabase, Subscriber, SubscriberFilter, SubscriberHistoryEntry, TotpState,
+            UserCredentials, Zero2ProdDatabase, Zero2ProdTransaction,
+        },
+        error::StorageError,
+        migration::{migration_status, MigrationStatus},
+        transaction::SharedTransaction,
+    },
+    domain::{AttributeSchema, SubscriberAttributes, SubscriberEmail, SubscriberTag},
+    segment::SegmentExpression,
+};
+
+use super::replica::ReplicaSet;
+use super::transaction::PostgresTransaction;
+use super::*;
 
-use super::pg_insert_subscriptions;
+/// 마이그레이션은 컴파일 시점에 바이너리에 포함된다.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 #[derive(Clone)]
 pub struct PostgresPool {
+    /// 주 DB
+    /// 쓰기와 일관된 읽기가 필요한 조회는 모두 여기서 실행한다.
     pg_pool: PgPool,
+    replicas: Arc<ReplicaSet>,
 }
 
-impl Zero2ProdDatabase for PostgresPool {
+impl PostgresPool {
+    /// 복제본에서 읽어도 되는 조회에 사용할 커넥션을 가져온다.
+    /// 사용할 수 있는 복제본이 없으면 주 DB의 커넥션을 가져온다.
+    async fn read_connection(&self) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        match self.replicas.acquire().await {
+            Some(connection) => Ok(connection),
+            None => self.pg_pool.acquire().await,
+        }
+    }
+
+    /// 마이그레이션을 실행할 커넥션을 가져온다.
+    /// 오래 걸리는 DDL이나 잠금 대기가 `statement_timeout`에 걸려 중단되지 않도록 제한을 해제한다.
+    /// 설정을 바꾼 커넥션이 풀로 돌아가지 않도록 풀에서 분리한다.
+    async fn migration_connection(&self) -> Result<PgConnection, sqlx::Error> {
+        let mut connection = self.pg_pool.acquire().await?.detach();
+        sqlx::query("SET statement_timeout = 0")
+            .execute(&mut connection)
+            .await?;
+        Ok(connection)
+    }
+}
+
+impl SqlxDatabase for PostgresPool {
     type DB = Postgres;
-    type ConnectOutput = Self;
 
     async fn connect(database_settings: &DatabaseSettings) -> Result<Self, sqlx::Error> {
-        let pg_pool = PgPoolOptions::new()
-            .acquire_timeout(std::time::Duration::from_secs(2))
+        let pg_pool = database_settings
+            .pool_options()
             .connect_lazy_with(Self::connect_option_with_db(database_settings));
-        let postgres_pool = Self { pg_pool };
+        let mut replicas = ReplicaSet::default();
+        for replica in &database_settings.replicas {
+            let options = Self::connect_option_with_db(database_settings)
+                .host(&replica.host)
+                .port(replica.port);
+            replicas.push(
+                format!("{}:{}", replica.host, replica.port),
+                database_settings.pool_options().connect_lazy_with(options),
+            );
+        }
+        let postgres_pool = Self {
+            pg_pool,
+            replicas: Arc::new(replicas),
+        };
         Ok(postgres_pool)
     }
 
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        self.pg_pool.acquire().await?.ping().await
+    }
+
+    async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        MIGRATOR.run(&mut self.migration_connection().await?).await
+    }
+
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>, sqlx::migrate::MigrateError> {
+        let mut conn = self.pg_pool.acquire().await?;
+        migration_status(&MIGRATOR, &mut *conn).await
+    }
+
+    async fn revert_migrations(&self, target: i64) -> Result<(), sqlx::migrate::MigrateError> {
+        MIGRATOR
+            .undo(&mut self.migration_connection().await?, target)
+            .await
+    }
+
     #[allow(refining_impl_trait)]
     fn connect_option_without_db(database_settings: &DatabaseSettings) -> PgConnectOptions {
-        let ssl_mod = if database_settings.require_ssl {
-            PgSslMode::Require
-        } else {
-            // 암호화된 커넥션을 시도한다.
-            // 실패하면 암호화되지 않은 커넥션을 사용한다.
-            PgSslMode::Prefer
+        let ssl_mode = match database_settings.ssl_mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Allow => PgSslMode::Allow,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
         };
-        PgConnectOptions::new()
+        let mut options = PgConnectOptions::new()
             .host(&database_settings.host)
             .username(&database_settings.username)
             .password(database_settings.password.expose_secret())
             .port(database_settings.port)
-            .ssl_mode(ssl_mod)
+            .ssl_mode(ssl_mode)
+            .application_name(&database_settings.application_name)
+            // 0이면 Postgres도 시간 제한을 두지 않는다.
+            .options([(
+                "statement_timeout",
+                database_settings.statement_timeout_milliseconds.to_string(),
+            )])
+            .options(&database_settings.options);
+        // 파일은 구성을 읽을 때 있는지 확인했다.
+        if let Some(ssl_root_cert) = &database_settings.ssl_root_cert {
+            options = options.ssl_root_cert(ssl_root_cert);
+        }
+        if let Some(ssl_client_cert) = &database_settings.ssl_client_cert {
+            options = options.ssl_client_cert(ssl_client_cert);
+        }
+        if let Some(ssl_client_key) = &database_settings.ssl_client_key {
+            options = options.ssl_client_key(ssl_client_key);
+        }
+        options
     }
 
     #[allow(refining_impl_trait)]
@@ -51,15 +148,436 @@ impl Zero2ProdDatabase for PostgresPool {
         // ``.log_statements`은 대한 부분은 저자의 예시 코드에도 보이지 않는다.
         // 노이즈를 줄이려고 INFO를 TRACE로 변경하는 것이 이해가 되지 않는다.
     }
+}
+
+impl Zero2ProdDatabase for PostgresPool {
+    type Transaction = PostgresTransaction;
+
+    async fn begin(&self) -> Result<Self::Transaction, StorageError> {
+        SharedTransaction::begin(&self.pg_pool)
+            .await
+            .map(|transaction| PostgresTransaction { transaction })
+            .map_err(StorageError::from)
+    }
 
     async fn insert_subscriptions(
         &self,
         id: uuid::Uuid,
-        email: &str,
+        email: &SubscriberEmail,
         name: &str,
+        attributes: &SubscriberAttributes,
         subscribed_at: chrono::DateTime<chrono::Utc>,
-    ) -> Result<PgQueryResult, sqlx::Error> {
-        pg_insert_subscriptions(&self.pg_pool, id, email, name, subscribed_at).await
+    ) -> Result<(), StorageError> {
+        pg_insert_subscriptions(&self.pg_pool, id, email, name, attributes, subscribed_at)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscribers(
+        &self,
+        search: Option<&str>,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        pg_fetch_subscribers(&mut *self.read_connection().await?, search)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_page(
+        &self,
+        filter: &SubscriberFilter,
+        limit: i64,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        pg_fetch_subscriber_page(&mut *self.read_connection().await?, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber(&self, id: uuid::Uuid) -> Result<Option<Subscriber>, StorageError> {
+        pg_fetch_subscriber(&mut *self.read_connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn confirm_subscriber(&self, id: uuid::Uuid) -> Result<u64, StorageError> {
+        pg_confirm_subscriber(&self.pg_pool, id)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_delete_subscriber(&self.pg_pool, id, deleted_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn restore_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_after: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_restore_subscriber(&self.pg_pool, id, deleted_after)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn purge_deleted_subscribers(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_purge_deleted_subscribers(&self.pg_pool, deleted_before)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_history(
+        &self,
+        id: uuid::Uuid,
+    ) -> Result<Vec<SubscriberHistoryEntry>, StorageError> {
+        pg_fetch_subscriber_history(&mut *self.read_connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_delete_unconfirmed_subscribers(&self.pg_pool, subscribed_before, deleted_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn anonymise_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_anonymise_unconfirmed_subscribers(&self.pg_pool, subscribed_before)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn import_subscribers(
+        &self,
+        subscribers: &[NewSubscriber],
+    ) -> Result<ImportCounts, StorageError> {
+        let transaction = self.begin().await?;
+        let counts = transaction.import_subscribers(subscribers).await?;
+        transaction.commit().await?;
+        Ok(counts)
+    }
+
+    async fn fetch_subscriber_tags(&self, id: uuid::Uuid) -> Result<Vec<String>, StorageError> {
+        pg_fetch_subscriber_tags(&mut *self.read_connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        id: uuid::Uuid,
+        tags: &[SubscriberTag],
+    ) -> Result<(), StorageError> {
+        let transaction = self.begin().await?;
+        transaction.set_subscriber_tags(id, tags).await?;
+        transaction.commit().await
+    }
+
+    async fn fetch_segments(&self) -> Result<Vec<Segment>, StorageError> {
+        pg_fetch_segments(&self.pg_pool)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_segment(&self, name: &str) -> Result<Option<Segment>, StorageError> {
+        pg_fetch_segment(&self.pg_pool, name)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn save_segment(
+        &self,
+        name: &str,
+        expression: &str,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        pg_save_segment(&self.pg_pool, name, expression, updated_at)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_segment(&self, name: &str) -> Result<u64, StorageError> {
+        pg_delete_segment(&self.pg_pool, name)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn count_segment(
+        &self,
+        expression: &SegmentExpression,
+    ) -> Result<SegmentSize, StorageError> {
+        pg_count_segment(&mut *self.read_connection().await?, expression)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_attribute_schema(&self) -> Result<AttributeSchema, StorageError> {
+        pg_fetch_attribute_schema(&self.pg_pool)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_attribute_schema(&self, schema: &AttributeSchema) -> Result<(), StorageError> {
+        let transaction = self.begin().await?;
+        transaction.set_attribute_schema(schema).await?;
+        transaction.commit().await
+    }
+
+    async fn insert_user(
+        &self,
+        user_id: uuid::Uuid,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        pg_insert_user(
+            &self.pg_pool,
+            user_id,
+            username,
+            password_hash,
+            role,
+            created_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<u64, StorageError> {
+        pg_set_user_disabled(&self.pg_pool, username, disabled)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_user_credentials(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserCredentials>, StorageError> {
+        pg_fetch_user_credentials(&self.pg_pool, username)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: uuid::Uuid,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        second_factor_pending: bool,
+    ) -> Result<(), StorageError> {
+        pg_insert_session(
+            &self.pg_pool,
+            token_hash,
+            user_id,
+            created_at,
+            expires_at,
+            second_factor_pending,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_session_user(
+        &self,
+        token_hash: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<SessionUser>, StorageError> {
+        pg_fetch_session_user(&self.pg_pool, token_hash, now)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn complete_second_factor(
+        &self,
+        token_hash: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_complete_second_factor(&self.pg_pool, token_hash, expires_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> Result<u64, StorageError> {
+        pg_delete_session(&self.pg_pool, token_hash)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_totp_state(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<TotpState>, StorageError> {
+        pg_fetch_totp_state(&self.pg_pool, user_id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_pending_totp_secret(
+        &self,
+        user_id: uuid::Uuid,
+        secret: &str,
+    ) -> Result<u64, StorageError> {
+        pg_set_pending_totp_secret(&self.pg_pool, user_id, secret)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn enable_totp(
+        &self,
+        user_id: uuid::Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), StorageError> {
+        // 복구 코드가 교체되지 않은 채로 활성화되지 않도록 트랜잭션을 사용한다.
+        let mut transaction = self.pg_pool.begin().await?;
+        pg_enable_totp(&mut *transaction, user_id).await?;
+        pg_delete_recovery_codes(&mut *transaction, user_id).await?;
+        pg_insert_recovery_codes(&mut *transaction, user_id, recovery_code_hashes).await?;
+        Ok(transaction.commit().await?)
+    }
+
+    async fn disable_totp(&self, user_id: uuid::Uuid) -> Result<(), StorageError> {
+        let mut transaction = self.pg_pool.begin().await?;
+        pg_disable_totp(&mut *transaction, user_id).await?;
+        pg_delete_recovery_codes(&mut *transaction, user_id).await?;
+        Ok(transaction.commit().await?)
+    }
+
+    async fn record_totp_step(&self, user_id: uuid::Uuid, step: i64) -> Result<u64, StorageError> {
+        pg_record_totp_step(&self.pg_pool, user_id, step)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: uuid::Uuid,
+        code_hash: &str,
+        used_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_use_recovery_code(&self.pg_pool, user_id, code_hash, used_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_require_two_factor(&self) -> Result<bool, StorageError> {
+        pg_fetch_require_two_factor(&self.pg_pool)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_require_two_factor(&self, required: bool) -> Result<(), StorageError> {
+        pg_set_require_two_factor(&self.pg_pool, required)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> Result<Option<LoginThrottle>, StorageError> {
+        pg_fetch_login_throttle(&self.pg_pool, scope, subject)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn lock_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        pg_lock_login_throttle(&self.pg_pool, scope, subject, now)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn record_login_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+        window_start: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        pg_record_login_failure(&self.pg_pool, scope, subject, now, window_start)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn lock_login(
+        &self,
+        scope: &str,
+        subject: &str,
+        locked_until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_lock_login(&self.pg_pool, scope, subject, locked_until)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn forgive_login_failure(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        pg_forgive_login_failure(&self.pg_pool, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn clear_login_failures(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        pg_clear_login_failures(&self.pg_pool, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_audit_entry(&self, entry: &NewAuditEntry) -> Result<(), StorageError> {
+        pg_insert_audit_entry(&self.pg_pool, entry)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_audit_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, StorageError> {
+        pg_fetch_audit_entries(&mut *self.read_connection().await?, filter, limit)
+            .await
+            .map_err(StorageError::from)
     }
 }
 
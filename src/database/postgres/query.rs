@@ -1,4 +1,18 @@
-use sqlx::{postgres::PgQueryResult, PgExecutor};
+use secrecy::Secret;
+use sqlx::{postgres::PgQueryResult, PgConnection, PgExecutor, Postgres, QueryBuilder};
+
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    database::basic::{
+        ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser, Subscriber,
+        SubscriberFilter, SubscriberHistoryEntry, TotpState, UserCredentials,
+    },
+    domain::{
+        AttributeDefinition, AttributeSchema, AttributeType, SubscriberAttributes, SubscriberEmail,
+        SubscriberTag,
+    },
+    segment::SegmentExpression,
+};
 
 // 구독자를 DB에 추가한다.
 
@@ -6,20 +20,1135 @@ use sqlx::{postgres::PgQueryResult, PgExecutor};
 pub async fn pg_insert_subscriptions(
     executor: impl PgExecutor<'_>,
     id: uuid::Uuid,
-    email: &str,
+    email: &SubscriberEmail,
     name: &str,
+    attributes: &SubscriberAttributes,
     subscribed_at: chrono::DateTime<chrono::Utc>,
 ) -> Result<PgQueryResult, sqlx::Error> {
     sqlx::query!(
         r#"
-        INSERT INTO subscriptions (id, email, name, subscribed_at)
-        VALUES ($1, $2, $3, $4);
+        INSERT INTO subscriptions (
+            id, email, canonical_email, name, attributes, subscribed_at, status
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, 'pending_confirmation');
         "#,
         id,
-        email,
+        email.as_str(),
+        email.canonical(),
         name,
+        attributes.to_value(),
         subscribed_at
     )
     .execute(executor)
     .await
 }
+
+#[tracing::instrument(name = "Fetching subscribers from the database.", skip(executor))]
+pub async fn pg_fetch_subscribers(
+    executor: impl PgExecutor<'_>,
+    search: Option<&str>,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    // `search`가 `NULL`이면 모든 구독자를 가져온다.
+    // `%`, `_`가 와일드카드로 해석되지 않도록 `LIKE` 대신 `strpos`로 찾는다.
+    sqlx::query_as!(
+        Subscriber,
+        r#"
+        SELECT id, email, name, status, subscribed_at, attributes
+        FROM subscriptions
+        WHERE deleted_at IS NULL
+            AND ($1::TEXT IS NULL
+                OR strpos(lower(email), lower($1)) > 0
+                OR strpos(lower(name), lower($1)) > 0)
+        ORDER BY subscribed_at, id;
+        "#,
+        search
+    )
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Fetching a page of subscribers from the database.",
+    skip(executor)
+)]
+pub async fn pg_fetch_subscriber_page(
+    executor: impl PgExecutor<'_>,
+    filter: &SubscriberFilter,
+    limit: i64,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    // `NULL`인 조건은 무시한다.
+    // 커서보다 앞선 행을 (subscribed_at, id) 순서로 비교해서 다음 페이지를 가져온다.
+    sqlx::query_as!(
+        Subscriber,
+        r#"
+        SELECT id, email, name, status, subscribed_at, attributes
+        FROM subscriptions
+        WHERE deleted_at IS NULL
+            AND ($1::TEXT IS NULL OR status = $1)
+            AND ($2::TEXT IS NULL OR strpos(lower(email), lower($2)) > 0)
+            AND ($3::TIMESTAMPTZ IS NULL OR subscribed_at >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR subscribed_at < $4)
+            AND ($5::TIMESTAMPTZ IS NULL OR (subscribed_at, id) < ($5, $6::UUID))
+            AND ($8::JSONB IS NULL OR attributes @> $8)
+        ORDER BY subscribed_at DESC, id DESC
+        LIMIT $7;
+        "#,
+        filter.status,
+        filter.email,
+        filter.since,
+        filter.until,
+        filter.cursor.map(|cursor| cursor.subscribed_at),
+        filter.cursor.map(|cursor| cursor.id),
+        limit,
+        filter
+            .attributes
+            .as_ref()
+            .map(SubscriberAttributes::to_value)
+    )
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching a subscriber from the database.", skip(executor))]
+pub async fn pg_fetch_subscriber(
+    executor: impl PgExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    sqlx::query_as!(
+        Subscriber,
+        r#"
+        SELECT id, email, name, status, subscribed_at, attributes
+        FROM subscriptions
+        WHERE id = $1 AND deleted_at IS NULL;
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Confirming a subscriber in the database.", skip(executor))]
+pub async fn pg_confirm_subscriber(
+    executor: impl PgExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET status = 'confirmed'
+        WHERE id = $1 AND deleted_at IS NULL;
+        "#,
+        id
+    )
+    .execute(executor)
+    .await
+}
+
+// 유예 기간 동안 복원할 수 있도록 삭제 표시만 한다.
+#[tracing::instrument(name = "Deleting a subscriber from the database.", skip(executor))]
+pub async fn pg_delete_subscriber(
+    executor: impl PgExecutor<'_>,
+    id: uuid::Uuid,
+    deleted_at: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET deleted_at = $2
+        WHERE id = $1 AND deleted_at IS NULL;
+        "#,
+        id,
+        deleted_at
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Restoring a subscriber in the database.", skip(executor))]
+pub async fn pg_restore_subscriber(
+    executor: impl PgExecutor<'_>,
+    id: uuid::Uuid,
+    deleted_after: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET deleted_at = NULL
+        WHERE id = $1 AND deleted_at >= $2;
+        "#,
+        id,
+        deleted_after
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Purging deleted subscribers.", skip(executor))]
+pub async fn pg_purge_deleted_subscribers(
+    executor: impl PgExecutor<'_>,
+    deleted_before: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM subscriptions
+        WHERE deleted_at < $1;
+        "#,
+        deleted_before
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Fetching the history of a subscriber from the database.",
+    skip(executor)
+)]
+pub async fn pg_fetch_subscriber_history(
+    executor: impl PgExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<Vec<SubscriberHistoryEntry>, sqlx::Error> {
+    sqlx::query_as!(
+        SubscriberHistoryEntry,
+        r#"
+        SELECT occurred_at, operation, email, name, status, attributes
+        FROM subscription_history
+        WHERE subscriber_id = $1
+        ORDER BY id;
+        "#,
+        id
+    )
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting unconfirmed subscribers.", skip(executor))]
+pub async fn pg_delete_unconfirmed_subscribers(
+    executor: impl PgExecutor<'_>,
+    subscribed_before: chrono::DateTime<chrono::Utc>,
+    deleted_at: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    // 변경 이력이 남도록 삭제 표시만 하고, 유예 기간이 지나면 이력과 함께 영구히 삭제한다.
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET deleted_at = $2
+        WHERE status = 'pending_confirmation' AND subscribed_at < $1 AND deleted_at IS NULL;
+        "#,
+        subscribed_before,
+        deleted_at
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Anonymising unconfirmed subscribers.", skip(executor))]
+pub async fn pg_anonymise_unconfirmed_subscribers(
+    executor: impl PgExecutor<'_>,
+    subscribed_before: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    // `.invalid`는 실제로 존재할 수 없는 최상위 도메인이다.
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET email = id::TEXT || '@anonymised.invalid',
+            canonical_email = id::TEXT || '@anonymised.invalid',
+            name = '',
+            attributes = '{}',
+            status = 'anonymised'
+        WHERE status = 'pending_confirmation' AND subscribed_at < $1;
+        "#,
+        subscribed_before
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Trying an advisory lock.", skip(executor))]
+pub async fn pg_try_advisory_lock(
+    executor: impl PgExecutor<'_>,
+    key: i64,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(r#"SELECT pg_try_advisory_xact_lock($1) AS "locked!""#, key)
+        .fetch_one(executor)
+        .await
+}
+
+/// `COPY`로 한 번에 보낼 행의 수
+const IMPORT_COPY_CHUNK: usize = 1000;
+
+// 임시 테이블은 컴파일할 때 존재하지 않으므로 `query!` 매크로를 사용할 수 없다.
+#[tracing::instrument(
+    name = "Importing subscribers into the database.",
+    skip_all,
+    fields(count = subscribers.len())
+)]
+pub async fn pg_import_subscribers(
+    connection: &mut PgConnection,
+    subscribers: &[NewSubscriber],
+) -> Result<ImportCounts, sqlx::Error> {
+    pg_begin_import(&mut *connection).await?;
+    pg_stage_import(&mut *connection, subscribers).await?;
+    pg_finish_import(connection).await
+}
+
+pub async fn pg_begin_import(connection: &mut PgConnection) -> Result<(), sqlx::Error> {
+    // `ON COMMIT DROP`은 바깥 트랜잭션이 끝날 때까지 남아 있으므로 직접 지운다.
+    sqlx::query(
+        r#"
+        CREATE TEMPORARY TABLE subscriptions_import (
+            id UUID NOT NULL,
+            email TEXT NOT NULL,
+            canonical_email TEXT NOT NULL,
+            name TEXT NOT NULL,
+            subscribed_at TIMESTAMPTZ NOT NULL
+        );
+        "#,
+    )
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Staging subscribers for the import.",
+    skip_all,
+    fields(count = subscribers.len())
+)]
+pub async fn pg_stage_import(
+    connection: &mut PgConnection,
+    subscribers: &[NewSubscriber],
+) -> Result<(), sqlx::Error> {
+    let mut copy = connection
+        .copy_in_raw(
+            "COPY subscriptions_import (id, email, canonical_email, name, subscribed_at) \
+             FROM STDIN WITH (FORMAT csv)",
+        )
+        .await?;
+    for chunk in subscribers.chunks(IMPORT_COPY_CHUNK) {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for subscriber in chunk {
+            writer
+                .write_record([
+                    subscriber.id.to_string().as_str(),
+                    subscriber.email.as_str(),
+                    subscriber.email.canonical(),
+                    subscriber.name.as_str(),
+                    subscriber.subscribed_at.to_rfc3339().as_str(),
+                ])
+                .map_err(|e| sqlx::Error::Io(e.into()))?;
+        }
+        let data = writer
+            .into_inner()
+            .map_err(|e| sqlx::Error::Io(e.into_error()))?;
+        copy.send(data).await?;
+    }
+    copy.finish().await?;
+    Ok(())
+}
+
+pub async fn pg_finish_import(connection: &mut PgConnection) -> Result<ImportCounts, sqlx::Error> {
+    // 먼저 기존 구독자를 갱신해야 새로 추가한 구독자가 갱신한 것으로 세어지지 않는다.
+    let updated = sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET name = import.name
+        FROM subscriptions_import AS import
+        WHERE subscriptions.canonical_email = import.canonical_email
+            AND subscriptions.deleted_at IS NULL
+            AND subscriptions.name <> import.name;
+        "#,
+    )
+    .execute(&mut *connection)
+    .await?
+    .rows_affected();
+    // 이미 구독 중인 독자를 옮겨오는 것이므로 확인된 구독자로 추가한다.
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO subscriptions (id, email, canonical_email, name, subscribed_at, status)
+        SELECT id, email, canonical_email, name, subscribed_at, 'confirmed'
+        FROM subscriptions_import
+        ON CONFLICT DO NOTHING;
+        "#,
+    )
+    .execute(&mut *connection)
+    .await?
+    .rows_affected();
+
+    sqlx::query("DROP TABLE subscriptions_import;")
+        .execute(&mut *connection)
+        .await?;
+    Ok(ImportCounts { inserted, updated })
+}
+
+pub async fn pg_fetch_attribute_schema(
+    executor: impl PgExecutor<'_>,
+) -> Result<AttributeSchema, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT name, type
+        FROM subscriber_attributes
+        ORDER BY name;
+        "#
+    )
+    .fetch_all(executor)
+    .await?;
+    let definitions = rows
+        .into_iter()
+        .map(|row| {
+            Ok(AttributeDefinition {
+                r#type: AttributeType::try_from(row.r#type.as_str())?,
+                name: row.name,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| sqlx::Error::Decode(e.into()))?;
+    AttributeSchema::parse(definitions).map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+#[tracing::instrument(name = "Replacing the subscriber attribute schema.", skip_all)]
+pub async fn pg_set_attribute_schema(
+    connection: &mut PgConnection,
+    schema: &AttributeSchema,
+) -> Result<(), sqlx::Error> {
+    let (names, types): (Vec<_>, Vec<_>) = schema
+        .definitions()
+        .into_iter()
+        .map(|definition| (definition.name, definition.r#type.as_str().to_string()))
+        .unzip();
+    sqlx::query!("DELETE FROM subscriber_attributes;")
+        .execute(&mut *connection)
+        .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriber_attributes (name, type)
+        SELECT * FROM UNNEST($1::TEXT[], $2::TEXT[]);
+        "#,
+        &names,
+        &types
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+pub async fn pg_fetch_subscriber_tags(
+    executor: impl PgExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT tag
+        FROM subscription_tags
+        WHERE subscriber_id = $1
+        ORDER BY tag;
+        "#,
+        id
+    )
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Replacing the tags of a subscriber.", skip(connection))]
+pub async fn pg_set_subscriber_tags(
+    connection: &mut PgConnection,
+    id: uuid::Uuid,
+    tags: &[SubscriberTag],
+) -> Result<(), sqlx::Error> {
+    let tags: Vec<_> = tags.iter().map(|tag| tag.as_str().to_string()).collect();
+    sqlx::query!(
+        r#"
+        DELETE FROM subscription_tags
+        WHERE subscriber_id = $1;
+        "#,
+        id
+    )
+    .execute(&mut *connection)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO subscription_tags (subscriber_id, tag)
+        SELECT $1, tag FROM UNNEST($2::TEXT[]) AS tag
+        ON CONFLICT DO NOTHING;
+        "#,
+        id,
+        &tags
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+pub async fn pg_fetch_segments(executor: impl PgExecutor<'_>) -> Result<Vec<Segment>, sqlx::Error> {
+    sqlx::query_as!(
+        Segment,
+        r#"
+        SELECT name, expression, updated_at
+        FROM segments
+        ORDER BY name;
+        "#
+    )
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn pg_fetch_segment(
+    executor: impl PgExecutor<'_>,
+    name: &str,
+) -> Result<Option<Segment>, sqlx::Error> {
+    sqlx::query_as!(
+        Segment,
+        r#"
+        SELECT name, expression, updated_at
+        FROM segments
+        WHERE name = $1;
+        "#,
+        name
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Saving a segment in the database.", skip(executor))]
+pub async fn pg_save_segment(
+    executor: impl PgExecutor<'_>,
+    name: &str,
+    expression: &str,
+    updated_at: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO segments (name, expression, updated_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (name) DO UPDATE
+        SET expression = EXCLUDED.expression, updated_at = EXCLUDED.updated_at;
+        "#,
+        name,
+        expression,
+        updated_at
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting a segment from the database.", skip(executor))]
+pub async fn pg_delete_segment(
+    executor: impl PgExecutor<'_>,
+    name: &str,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM segments
+        WHERE name = $1;
+        "#,
+        name
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Counting the subscribers of a segment.", skip(executor))]
+pub async fn pg_count_segment(
+    executor: impl PgExecutor<'_>,
+    expression: &SegmentExpression,
+) -> Result<SegmentSize, sqlx::Error> {
+    // 식의 구조가 세그먼트마다 다르므로 `query!` 대신 쿼리를 조립한다.
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE status = 'confirmed') AS confirmed
+        FROM subscriptions
+        WHERE deleted_at IS NULL AND "#,
+    );
+    push_segment_condition(&mut builder, expression);
+    builder
+        .build_query_as::<SegmentSize>()
+        .fetch_one(executor)
+        .await
+}
+
+/// 세그먼트 식을 `subscriptions` 행에 대한 조건으로 변환한다.
+fn push_segment_condition(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    expression: &SegmentExpression,
+) {
+    match expression {
+        SegmentExpression::Tag(tag) => {
+            builder.push(
+                "EXISTS (SELECT 1 FROM subscription_tags \
+                 WHERE subscriber_id = subscriptions.id AND tag = ",
+            );
+            builder.push_bind(tag.as_str().to_string());
+            builder.push(")");
+        }
+        SegmentExpression::Status(status) => {
+            builder.push("status = ");
+            builder.push_bind(status.clone());
+        }
+        SegmentExpression::Attribute { name, value, list } => {
+            let value = if *list {
+                serde_json::json!([value])
+            } else {
+                value.clone()
+            };
+            builder.push("attributes @> ");
+            builder.push_bind(serde_json::json!({ name: value }));
+        }
+        SegmentExpression::SubscribedAt(comparison, timestamp) => {
+            builder.push("subscribed_at ");
+            builder.push(comparison.as_sql());
+            builder.push(" ");
+            builder.push_bind(*timestamp);
+        }
+        SegmentExpression::Not(inner) => {
+            builder.push("NOT (");
+            push_segment_condition(builder, inner);
+            builder.push(")");
+        }
+        SegmentExpression::And(left, right) => {
+            builder.push("(");
+            push_segment_condition(builder, left);
+            builder.push(") AND (");
+            push_segment_condition(builder, right);
+            builder.push(")");
+        }
+        SegmentExpression::Or(left, right) => {
+            builder.push("(");
+            push_segment_condition(builder, left);
+            builder.push(") OR (");
+            push_segment_condition(builder, right);
+            builder.push(")");
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Saving new user in the database.",
+    skip(executor, password_hash)
+)]
+pub async fn pg_insert_user(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+    username: &str,
+    password_hash: &str,
+    role: &str,
+    created_at: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO users (user_id, username, password_hash, role, created_at)
+        VALUES ($1, $2, $3, $4, $5);
+        "#,
+        user_id,
+        username,
+        password_hash,
+        role,
+        created_at
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Changing user's disabled flag in the database.",
+    skip(executor)
+)]
+pub async fn pg_set_user_disabled(
+    executor: impl PgExecutor<'_>,
+    username: &str,
+    disabled: bool,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET disabled = $2
+        WHERE username = $1;
+        "#,
+        username,
+        disabled
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching user credentials from the database.", skip(executor))]
+pub async fn pg_fetch_user_credentials(
+    executor: impl PgExecutor<'_>,
+    username: &str,
+) -> Result<Option<UserCredentials>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT user_id, password_hash, disabled, role, totp_enabled
+        FROM users
+        WHERE username = $1;
+        "#,
+        username
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(row.map(|row| UserCredentials {
+        user_id: row.user_id,
+        password_hash: Secret::new(row.password_hash),
+        disabled: row.disabled,
+        role: row.role,
+        totp_enabled: row.totp_enabled,
+    }))
+}
+
+#[tracing::instrument(
+    name = "Saving new session in the database.",
+    skip(executor, token_hash)
+)]
+pub async fn pg_insert_session(
+    executor: impl PgExecutor<'_>,
+    token_hash: &str,
+    user_id: uuid::Uuid,
+    created_at: chrono::DateTime<chrono::Utc>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    second_factor_pending: bool,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO admin_sessions
+            (token_hash, user_id, created_at, expires_at, second_factor_pending)
+        VALUES ($1, $2, $3, $4, $5);
+        "#,
+        token_hash,
+        user_id,
+        created_at,
+        expires_at,
+        second_factor_pending
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Fetching session from the database.",
+    skip(executor, token_hash)
+)]
+pub async fn pg_fetch_session_user(
+    executor: impl PgExecutor<'_>,
+    token_hash: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<Option<SessionUser>, sqlx::Error> {
+    sqlx::query_as!(
+        SessionUser,
+        r#"
+        SELECT u.user_id, u.username, u.role, u.totp_enabled, s.second_factor_pending
+        FROM admin_sessions s
+        JOIN users u ON u.user_id = s.user_id
+        WHERE s.token_hash = $1
+            AND s.expires_at > $2
+            AND NOT u.disabled;
+        "#,
+        token_hash,
+        now
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Completing second factor of session.",
+    skip(executor, token_hash)
+)]
+pub async fn pg_complete_second_factor(
+    executor: impl PgExecutor<'_>,
+    token_hash: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE admin_sessions
+        SET second_factor_pending = FALSE, expires_at = $2
+        WHERE token_hash = $1 AND second_factor_pending;
+        "#,
+        token_hash,
+        expires_at
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting session from the database.", skip_all)]
+pub async fn pg_delete_session(
+    executor: impl PgExecutor<'_>,
+    token_hash: &str,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM admin_sessions
+        WHERE token_hash = $1;
+        "#,
+        token_hash
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching TOTP state from the database.", skip(executor))]
+pub async fn pg_fetch_totp_state(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<Option<TotpState>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT username, totp_secret, totp_enabled
+        FROM users
+        WHERE user_id = $1;
+        "#,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(row.map(|row| TotpState {
+        username: row.username,
+        secret: row.totp_secret.map(Secret::new),
+        enabled: row.totp_enabled,
+    }))
+}
+
+#[tracing::instrument(name = "Saving pending TOTP secret.", skip(executor, secret))]
+pub async fn pg_set_pending_totp_secret(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+    secret: &str,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET totp_secret = $2, totp_last_step = NULL
+        WHERE user_id = $1 AND NOT totp_enabled;
+        "#,
+        user_id,
+        secret
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Enabling TOTP.", skip(executor))]
+pub async fn pg_enable_totp(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET totp_enabled = TRUE
+        WHERE user_id = $1 AND totp_secret IS NOT NULL;
+        "#,
+        user_id
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Disabling TOTP.", skip(executor))]
+pub async fn pg_disable_totp(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET totp_enabled = FALSE, totp_secret = NULL, totp_last_step = NULL
+        WHERE user_id = $1;
+        "#,
+        user_id
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting recovery codes.", skip(executor))]
+pub async fn pg_delete_recovery_codes(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM recovery_codes
+        WHERE user_id = $1;
+        "#,
+        user_id
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Saving recovery codes.", skip(executor, code_hashes))]
+pub async fn pg_insert_recovery_codes(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+    code_hashes: &[String],
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO recovery_codes (user_id, code_hash)
+        SELECT $1, code_hash FROM UNNEST($2::TEXT[]) AS code_hash;
+        "#,
+        user_id,
+        code_hashes
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Recording used TOTP step.", skip(executor))]
+pub async fn pg_record_totp_step(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+    step: i64,
+) -> Result<PgQueryResult, sqlx::Error> {
+    // 조건부 갱신으로 동시에 같은 코드를 사용하는 경우에도 한 번만 성공한다.
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET totp_last_step = $2
+        WHERE user_id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2);
+        "#,
+        user_id,
+        step
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Using recovery code.", skip(executor, code_hash))]
+pub async fn pg_use_recovery_code(
+    executor: impl PgExecutor<'_>,
+    user_id: uuid::Uuid,
+    code_hash: &str,
+    used_at: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE recovery_codes
+        SET used_at = $3
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL;
+        "#,
+        user_id,
+        code_hash,
+        used_at
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching two-factor requirement.", skip(executor))]
+pub async fn pg_fetch_require_two_factor(
+    executor: impl PgExecutor<'_>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT require_two_factor
+        FROM security_settings;
+        "#
+    )
+    .fetch_one(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Changing two-factor requirement.", skip(executor))]
+pub async fn pg_set_require_two_factor(
+    executor: impl PgExecutor<'_>,
+    required: bool,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE security_settings
+        SET require_two_factor = $1;
+        "#,
+        required
+    )
+    .execute(executor)
+    .await
+}
+
+pub async fn pg_fetch_login_throttle(
+    executor: impl PgExecutor<'_>,
+    scope: &str,
+    subject: &str,
+) -> Result<Option<LoginThrottle>, sqlx::Error> {
+    sqlx::query_as!(
+        LoginThrottle,
+        r#"
+        SELECT failures, last_failure_at, locked_until
+        FROM login_attempts
+        WHERE scope = $1 AND subject = $2;
+        "#,
+        scope,
+        subject
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn pg_lock_login_throttle(
+    executor: impl PgExecutor<'_>,
+    scope: &str,
+    subject: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<LoginThrottle, sqlx::Error> {
+    // 기록이 없어도 행을 만들어서 잠가야 동시에 시작한 시도가 모두 기다린다.
+    sqlx::query_as!(
+        LoginThrottle,
+        r#"
+        INSERT INTO login_attempts (scope, subject, failures, last_failure_at)
+        VALUES ($1, $2, 0, $3)
+        ON CONFLICT (scope, subject) DO UPDATE
+        SET failures = login_attempts.failures
+        RETURNING failures, last_failure_at, locked_until;
+        "#,
+        scope,
+        subject,
+        now
+    )
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn pg_record_login_failure(
+    executor: impl PgExecutor<'_>,
+    scope: &str,
+    subject: &str,
+    now: chrono::DateTime<chrono::Utc>,
+    window_start: chrono::DateTime<chrono::Utc>,
+) -> Result<LoginThrottle, sqlx::Error> {
+    // 여러 인스턴스에서 동시에 실패해도 횟수가 누락되지 않도록 한 번의 쿼리로 갱신한다.
+    sqlx::query_as!(
+        LoginThrottle,
+        r#"
+        INSERT INTO login_attempts (scope, subject, failures, last_failure_at)
+        VALUES ($1, $2, 1, $3)
+        ON CONFLICT (scope, subject) DO UPDATE
+        SET failures = CASE
+                WHEN login_attempts.last_failure_at < $4 OR login_attempts.locked_until <= $3
+                THEN 1
+                ELSE login_attempts.failures + 1
+            END,
+            last_failure_at = $3,
+            locked_until = CASE
+                WHEN login_attempts.locked_until <= $3 THEN NULL
+                ELSE login_attempts.locked_until
+            END
+        RETURNING failures, last_failure_at, locked_until;
+        "#,
+        scope,
+        subject,
+        now,
+        window_start
+    )
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn pg_lock_login(
+    executor: impl PgExecutor<'_>,
+    scope: &str,
+    subject: &str,
+    locked_until: chrono::DateTime<chrono::Utc>,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE login_attempts
+        SET locked_until = $3
+        WHERE scope = $1 AND subject = $2;
+        "#,
+        scope,
+        subject,
+        locked_until
+    )
+    .execute(executor)
+    .await
+}
+
+pub async fn pg_forgive_login_failure(
+    executor: impl PgExecutor<'_>,
+    scope: &str,
+    subject: &str,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE login_attempts
+        SET failures = failures - 1
+        WHERE scope = $1 AND subject = $2 AND failures > 0;
+        "#,
+        scope,
+        subject
+    )
+    .execute(executor)
+    .await
+}
+
+pub async fn pg_clear_login_failures(
+    executor: impl PgExecutor<'_>,
+    scope: &str,
+    subject: &str,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM login_attempts
+        WHERE scope = $1 AND subject = $2;
+        "#,
+        scope,
+        subject
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Saving audit entry in the database.", skip(executor))]
+pub async fn pg_insert_audit_entry(
+    executor: impl PgExecutor<'_>,
+    entry: &NewAuditEntry,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (occurred_at, actor, action, target, request_id, ip, diff)
+        VALUES ($1, $2, $3, $4, $5, $6, $7);
+        "#,
+        entry.occurred_at,
+        entry.actor,
+        entry.action.as_str(),
+        entry.target,
+        entry.request_id,
+        entry.ip,
+        entry.diff
+    )
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching audit entries from the database.", skip(executor))]
+pub async fn pg_fetch_audit_entries(
+    executor: impl PgExecutor<'_>,
+    filter: &AuditFilter,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    // `NULL`인 조건은 무시한다.
+    sqlx::query_as!(
+        AuditEntry,
+        r#"
+        SELECT id, occurred_at, actor, action, target, request_id, ip, diff
+        FROM audit_log
+        WHERE ($1::TEXT IS NULL OR actor = $1)
+            AND ($2::TEXT IS NULL OR action = $2)
+            AND ($3::TEXT IS NULL OR target = $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR occurred_at >= $4)
+            AND ($5::TIMESTAMPTZ IS NULL OR occurred_at < $5)
+            AND ($6::BIGINT IS NULL OR id < $6)
+        ORDER BY id DESC
+        LIMIT $7;
+        "#,
+        filter.actor,
+        filter.action,
+        filter.target,
+        filter.since,
+        filter.until,
+        filter.before,
+        limit
+    )
+    .fetch_all(executor)
+    .await
+}
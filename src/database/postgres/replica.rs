@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+use chrono::{Duration, Utc};
+use sqlx::{pool::PoolConnection, PgPool, Postgres};
+
+/// 커넥션을 얻지 못한 복제본은 이 시간 동안 사용하지 않는다.
+const UNHEALTHY_DURATION: Duration = Duration::seconds(30);
+
+struct Replica {
+    name: String,
+    pool: PgPool,
+    /// 이 시각(유닉스 초)까지는 사용하지 않는다.
+    unhealthy_until: AtomicI64,
+}
+
+/// 읽기 전용 복제본 목록
+///
+/// 복제본을 번갈아 사용하고, 커넥션을 얻지 못한 복제본은 잠시 건너뛴다.
+#[derive(Default)]
+pub(super) struct ReplicaSet {
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+}
+
+impl ReplicaSet {
+    pub(super) fn push(&mut self, name: String, pool: PgPool) {
+        self.replicas.push(Replica {
+            name,
+            pool,
+            unhealthy_until: AtomicI64::new(0),
+        });
+    }
+
+    /// 사용할 수 있는 복제본의 커넥션을 가져온다.
+    /// 모든 복제본을 사용할 수 없으면 `None`을 반환한다.
+    pub(super) async fn acquire(&self) -> Option<PoolConnection<Postgres>> {
+        if self.replicas.is_empty() {
+            return None;
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.replicas.len() {
+            let replica = &self.replicas[(start + offset) % self.replicas.len()];
+            let now = Utc::now();
+            if replica.unhealthy_until.load(Ordering::Relaxed) > now.timestamp() {
+                continue;
+            }
+            match replica.pool.acquire().await {
+                Ok(connection) => return Some(connection),
+                Err(e) => {
+                    tracing::warn!(
+                        replica = %replica.name,
+                        "Failed to connect to the replica: {:?}",
+                        e
+                    );
+                    replica
+                        .unhealthy_until
+                        .store((now + UNHEALTHY_DURATION).timestamp(), Ordering::Relaxed);
+                }
+            }
+        }
+        None
+    }
+}
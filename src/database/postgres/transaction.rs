@@ -0,0 +1,548 @@
+use sqlx::{Connection, Postgres};
+
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    database::{
+        basic::{
+            ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser,
+            Subscriber, SubscriberFilter, SubscriberHistoryEntry, TotpState, UserCredentials,
+            Zero2ProdDatabase, Zero2ProdTransaction,
+        },
+        error::StorageError,
+        transaction::SharedTransaction,
+    },
+    domain::{AttributeSchema, SubscriberAttributes, SubscriberEmail, SubscriberTag},
+    segment::SegmentExpression,
+};
+
+use super::*;
+
+/// 진행 중인 Postgres 트랜잭션
+///
+/// 모든 쿼리는 트랜잭션의 커넥션에서 실행된다.
+pub struct PostgresTransaction {
+    pub(super) transaction: SharedTransaction<Postgres>,
+}
+
+impl Zero2ProdTransaction for PostgresTransaction {
+    async fn commit(self) -> Result<(), StorageError> {
+        self.transaction.commit().await.map_err(StorageError::from)
+    }
+
+    async fn rollback(self) -> Result<(), StorageError> {
+        self.transaction
+            .rollback()
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn try_advisory_lock(&self, key: i64) -> Result<bool, StorageError> {
+        pg_try_advisory_lock(&mut *self.transaction.connection().await?, key)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn begin_import(&self) -> Result<(), StorageError> {
+        pg_begin_import(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn stage_import(&self, subscribers: &[NewSubscriber]) -> Result<(), StorageError> {
+        pg_stage_import(&mut *self.transaction.connection().await?, subscribers)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn finish_import(&self) -> Result<ImportCounts, StorageError> {
+        pg_finish_import(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+}
+
+impl Zero2ProdDatabase for PostgresTransaction {
+    type Transaction = Self;
+
+    async fn begin(&self) -> Result<Self::Transaction, StorageError> {
+        self.transaction
+            .begin_nested()
+            .await
+            .map(|transaction| Self { transaction })
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_subscriptions(
+        &self,
+        id: uuid::Uuid,
+        email: &SubscriberEmail,
+        name: &str,
+        attributes: &SubscriberAttributes,
+        subscribed_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        pg_insert_subscriptions(
+            &mut *self.transaction.connection().await?,
+            id,
+            email,
+            name,
+            attributes,
+            subscribed_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscribers(
+        &self,
+        search: Option<&str>,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        pg_fetch_subscribers(&mut *self.transaction.connection().await?, search)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_page(
+        &self,
+        filter: &SubscriberFilter,
+        limit: i64,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        pg_fetch_subscriber_page(&mut *self.transaction.connection().await?, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber(&self, id: uuid::Uuid) -> Result<Option<Subscriber>, StorageError> {
+        pg_fetch_subscriber(&mut *self.transaction.connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn confirm_subscriber(&self, id: uuid::Uuid) -> Result<u64, StorageError> {
+        pg_confirm_subscriber(&mut *self.transaction.connection().await?, id)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_delete_subscriber(&mut *self.transaction.connection().await?, id, deleted_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn restore_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_after: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_restore_subscriber(
+            &mut *self.transaction.connection().await?,
+            id,
+            deleted_after,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn purge_deleted_subscribers(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_purge_deleted_subscribers(&mut *self.transaction.connection().await?, deleted_before)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_history(
+        &self,
+        id: uuid::Uuid,
+    ) -> Result<Vec<SubscriberHistoryEntry>, StorageError> {
+        pg_fetch_subscriber_history(&mut *self.transaction.connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_delete_unconfirmed_subscribers(
+            &mut *self.transaction.connection().await?,
+            subscribed_before,
+            deleted_at,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn anonymise_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_anonymise_unconfirmed_subscribers(
+            &mut *self.transaction.connection().await?,
+            subscribed_before,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn import_subscribers(
+        &self,
+        subscribers: &[NewSubscriber],
+    ) -> Result<ImportCounts, StorageError> {
+        pg_import_subscribers(&mut *self.transaction.connection().await?, subscribers)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_tags(&self, id: uuid::Uuid) -> Result<Vec<String>, StorageError> {
+        pg_fetch_subscriber_tags(&mut *self.transaction.connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        id: uuid::Uuid,
+        tags: &[SubscriberTag],
+    ) -> Result<(), StorageError> {
+        pg_set_subscriber_tags(&mut *self.transaction.connection().await?, id, tags)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_segments(&self) -> Result<Vec<Segment>, StorageError> {
+        pg_fetch_segments(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_segment(&self, name: &str) -> Result<Option<Segment>, StorageError> {
+        pg_fetch_segment(&mut *self.transaction.connection().await?, name)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn save_segment(
+        &self,
+        name: &str,
+        expression: &str,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        pg_save_segment(
+            &mut *self.transaction.connection().await?,
+            name,
+            expression,
+            updated_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn delete_segment(&self, name: &str) -> Result<u64, StorageError> {
+        pg_delete_segment(&mut *self.transaction.connection().await?, name)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn count_segment(
+        &self,
+        expression: &SegmentExpression,
+    ) -> Result<SegmentSize, StorageError> {
+        pg_count_segment(&mut *self.transaction.connection().await?, expression)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_attribute_schema(&self) -> Result<AttributeSchema, StorageError> {
+        pg_fetch_attribute_schema(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_attribute_schema(&self, schema: &AttributeSchema) -> Result<(), StorageError> {
+        pg_set_attribute_schema(&mut *self.transaction.connection().await?, schema)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_user(
+        &self,
+        user_id: uuid::Uuid,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        pg_insert_user(
+            &mut *self.transaction.connection().await?,
+            user_id,
+            username,
+            password_hash,
+            role,
+            created_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<u64, StorageError> {
+        pg_set_user_disabled(
+            &mut *self.transaction.connection().await?,
+            username,
+            disabled,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_user_credentials(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserCredentials>, StorageError> {
+        pg_fetch_user_credentials(&mut *self.transaction.connection().await?, username)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: uuid::Uuid,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        second_factor_pending: bool,
+    ) -> Result<(), StorageError> {
+        pg_insert_session(
+            &mut *self.transaction.connection().await?,
+            token_hash,
+            user_id,
+            created_at,
+            expires_at,
+            second_factor_pending,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_session_user(
+        &self,
+        token_hash: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<SessionUser>, StorageError> {
+        pg_fetch_session_user(&mut *self.transaction.connection().await?, token_hash, now)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn complete_second_factor(
+        &self,
+        token_hash: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_complete_second_factor(
+            &mut *self.transaction.connection().await?,
+            token_hash,
+            expires_at,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> Result<u64, StorageError> {
+        pg_delete_session(&mut *self.transaction.connection().await?, token_hash)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_totp_state(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<TotpState>, StorageError> {
+        pg_fetch_totp_state(&mut *self.transaction.connection().await?, user_id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_pending_totp_secret(
+        &self,
+        user_id: uuid::Uuid,
+        secret: &str,
+    ) -> Result<u64, StorageError> {
+        pg_set_pending_totp_secret(&mut *self.transaction.connection().await?, user_id, secret)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn enable_totp(
+        &self,
+        user_id: uuid::Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), StorageError> {
+        let mut connection = self.transaction.connection().await?;
+        // 진행 중인 트랜잭션 안에서는 세이브포인트를 만든다.
+        let mut transaction = connection.begin().await?;
+        pg_enable_totp(&mut *transaction, user_id).await?;
+        pg_delete_recovery_codes(&mut *transaction, user_id).await?;
+        pg_insert_recovery_codes(&mut *transaction, user_id, recovery_code_hashes).await?;
+        transaction.commit().await.map_err(StorageError::from)
+    }
+
+    async fn disable_totp(&self, user_id: uuid::Uuid) -> Result<(), StorageError> {
+        let mut connection = self.transaction.connection().await?;
+        // 진행 중인 트랜잭션 안에서는 세이브포인트를 만든다.
+        let mut transaction = connection.begin().await?;
+        pg_disable_totp(&mut *transaction, user_id).await?;
+        pg_delete_recovery_codes(&mut *transaction, user_id).await?;
+        transaction.commit().await.map_err(StorageError::from)
+    }
+
+    async fn record_totp_step(&self, user_id: uuid::Uuid, step: i64) -> Result<u64, StorageError> {
+        pg_record_totp_step(&mut *self.transaction.connection().await?, user_id, step)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: uuid::Uuid,
+        code_hash: &str,
+        used_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_use_recovery_code(
+            &mut *self.transaction.connection().await?,
+            user_id,
+            code_hash,
+            used_at,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_require_two_factor(&self) -> Result<bool, StorageError> {
+        pg_fetch_require_two_factor(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_require_two_factor(&self, required: bool) -> Result<(), StorageError> {
+        pg_set_require_two_factor(&mut *self.transaction.connection().await?, required)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> Result<Option<LoginThrottle>, StorageError> {
+        pg_fetch_login_throttle(&mut *self.transaction.connection().await?, scope, subject)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn lock_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        pg_lock_login_throttle(
+            &mut *self.transaction.connection().await?,
+            scope,
+            subject,
+            now,
+        )
+        .await
+        .map_err(StorageError::from)
+    }
+
+    async fn record_login_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+        window_start: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        pg_record_login_failure(
+            &mut *self.transaction.connection().await?,
+            scope,
+            subject,
+            now,
+            window_start,
+        )
+        .await
+        .map_err(StorageError::from)
+    }
+
+    async fn lock_login(
+        &self,
+        scope: &str,
+        subject: &str,
+        locked_until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        pg_lock_login(
+            &mut *self.transaction.connection().await?,
+            scope,
+            subject,
+            locked_until,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn forgive_login_failure(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        pg_forgive_login_failure(&mut *self.transaction.connection().await?, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn clear_login_failures(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        pg_clear_login_failures(&mut *self.transaction.connection().await?, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_audit_entry(&self, entry: &NewAuditEntry) -> Result<(), StorageError> {
+        pg_insert_audit_entry(&mut *self.transaction.connection().await?, entry)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_audit_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, StorageError> {
+        pg_fetch_audit_entries(&mut *self.transaction.connection().await?, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use super::basic::SqlxDatabase;
+
+/// 구동할 때 DB 연결을 재시도하는 정책
+#[derive(Debug, Clone)]
+pub struct ConnectRetry {
+    /// 첫 번째 실패 후의 지연 시간
+    /// 실패할 때마다 두 배로 늘어난다.
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// 첫 시도부터 이 시간이 지나면 더 이상 재시도하지 않는다.
+    pub deadline: Duration,
+}
+
+/// DB가 응답할 때까지 재시도한다.
+/// 제한 시간 안에 연결하지 못하면 마지막 오류를 반환한다.
+#[tracing::instrument(name = "Wait until the database is available", skip(pool))]
+pub async fn wait_until_available(
+    pool: &impl SqlxDatabase,
+    retry: &ConnectRetry,
+) -> Result<(), sqlx::Error> {
+    let started = Instant::now();
+    let mut delay = retry.initial_delay;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        // 응답하지 않는 서버를 기다리느라 제한 시간을 넘기지 않도록
+        // 시도마다 남은 시간만큼만 기다린다.
+        let remaining = retry.deadline.saturating_sub(started.elapsed());
+        let e = match tokio::time::timeout(remaining, pool.ping()).await {
+            Ok(Ok(())) => {
+                tracing::info!(attempt, "Connected to the database.");
+                return Ok(());
+            }
+            Ok(Err(e)) => e,
+            Err(_) => sqlx::Error::PoolTimedOut,
+        };
+        if started.elapsed() + delay > retry.deadline {
+            tracing::error!(attempt, "Failed to connect to the database: {:?}", e);
+            return Err(e);
+        }
+        tracing::warn!(
+            attempt,
+            "Failed to connect to the database: {:?}. Retrying in {:?}.",
+            e,
+            delay
+        );
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(retry.max_delay);
+    }
+}
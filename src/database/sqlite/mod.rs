@@ -0,0 +1,5 @@
+pub mod pool;
+mod query;
+pub mod transaction;
+
+pub use query::*;
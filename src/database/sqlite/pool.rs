@@ -0,0 +1,588 @@
+use std::{ops::Deref, path::PathBuf, str::FromStr, sync::Arc};
+
+use sqlx::{
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    Connection, Sqlite,
+};
+
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    configuration::DatabaseSettings,
+    database::{
+        basic::{
+            ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser,
+            SqlxDatabase, Subscriber, SubscriberFilter, SubscriberHistoryEntry, TotpState,
+            UserCredentials, Zero2ProdDatabase, Zero2ProdTransaction,
+        },
+        error::StorageError,
+        migration::{migration_status, MigrationStatus},
+        transaction::SharedTransaction,
+    },
+    domain::{AttributeSchema, SubscriberAttributes, SubscriberEmail, SubscriberTag},
+    segment::SegmentExpression,
+};
+
+use super::transaction::SqliteTransaction;
+use super::*;
+
+/// SQLite용 스키마는 별도의 디렉터리에서 관리한다.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+/// 별도의 DB 서버 없이 파일 하나에 저장하는 SQLite 백엔드
+///
+/// `database_name`을 DB 파일의 경로로 사용한다.
+/// 호스트, 포트, 계정 설정은 사용하지 않는다.
+#[derive(Clone)]
+pub struct SqlitePool {
+    sqlite_pool: sqlx::SqlitePool,
+    /// `temporary`로 만든 경우 마지막 복제본이 사라질 때 지울 임시 파일
+    _scratch: Option<Arc<ScratchFile>>,
+}
+
+/// 풀이 사라지면 함께 지우는 임시 DB 파일
+struct ScratchFile(PathBuf);
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl SqlitePool {
+    /// 마이그레이션을 적용한 일회용 DB를 만든다.
+    ///
+    /// 서버 없이 스키마의 제약 조건을 그대로 사용할 수 있어 테스트에 적합하다.
+    /// `DefaultDBPool`과 무관하게 사용할 수 있으므로 Postgres 테스트와 같은 빌드에서 실행된다.
+    ///
+    /// SQLite의 메모리 DB는 쓰는 동안 다른 커넥션의 읽기를 막으므로
+    /// 임시 디렉터리의 WAL 모드 파일을 사용한다.
+    /// 트랜잭션을 진행하는 동안에도 풀의 다른 커넥션으로 읽을 수 있고,
+    /// 풀의 마지막 복제본이 사라지면 파일을 지운다.
+    pub async fn temporary() -> Result<Self, sqlx::migrate::MigrateError> {
+        let path = std::env::temp_dir().join(format!("zero2prod-{}.db", uuid::Uuid::new_v4()));
+        let scratch = Arc::new(ScratchFile(path));
+        let sqlite_pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(
+                SqliteConnectOptions::new()
+                    .filename(&scratch.0)
+                    .create_if_missing(true)
+                    .journal_mode(SqliteJournalMode::Wal)
+                    .foreign_keys(true),
+            )
+            .await?;
+        let pool = Self {
+            sqlite_pool,
+            _scratch: Some(scratch),
+        };
+        pool.migrate().await?;
+        Ok(pool)
+    }
+}
+
+impl SqlxDatabase for SqlitePool {
+    type DB = Sqlite;
+
+    async fn connect(database_settings: &DatabaseSettings) -> Result<Self, sqlx::Error> {
+        if !database_settings.replicas.is_empty() {
+            tracing::warn!("SQLite does not support replicas. They are ignored.");
+        }
+        if database_settings.ssl_client_cert.is_some() || database_settings.ssl_root_cert.is_some()
+        {
+            tracing::warn!("SQLite does not use TLS. The certificates are ignored.");
+        }
+        let sqlite_pool = database_settings
+            .pool_options()
+            .connect_lazy_with(Self::connect_option_with_db(database_settings));
+        Ok(Self {
+            sqlite_pool,
+            _scratch: None,
+        })
+    }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        self.sqlite_pool.acquire().await?.ping().await
+    }
+
+    async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        MIGRATOR.run(&self.sqlite_pool).await
+    }
+
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>, sqlx::migrate::MigrateError> {
+        let mut conn = self.sqlite_pool.acquire().await?;
+        migration_status(&MIGRATOR, &mut *conn).await
+    }
+
+    async fn revert_migrations(&self, target: i64) -> Result<(), sqlx::migrate::MigrateError> {
+        MIGRATOR.undo(&self.sqlite_pool, target).await
+    }
+
+    /// SQLite에는 DB 서버가 없으므로 메모리 DB에 연결한다.
+    #[allow(refining_impl_trait)]
+    fn connect_option_without_db(_database_settings: &DatabaseSettings) -> SqliteConnectOptions {
+        SqliteConnectOptions::from_str("sqlite::memory:")
+            .expect("Failed to parse the in-memory SQLite URL.")
+    }
+
+    #[allow(refining_impl_trait)]
+    fn connect_option_with_db(database_settings: &DatabaseSettings) -> SqliteConnectOptions {
+        SqliteConnectOptions::new()
+            .filename(&database_settings.database_name)
+            .create_if_missing(true)
+            // 읽기와 쓰기가 서로를 막지 않도록 WAL 모드를 사용한다.
+            .journal_mode(SqliteJournalMode::Wal)
+            .foreign_keys(true)
+    }
+}
+
+impl Zero2ProdDatabase for SqlitePool {
+    type Transaction = SqliteTransaction;
+
+    async fn begin(&self) -> Result<Self::Transaction, StorageError> {
+        SharedTransaction::begin(&self.sqlite_pool)
+            .await
+            .map(|transaction| SqliteTransaction { transaction })
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_subscriptions(
+        &self,
+        id: uuid::Uuid,
+        email: &SubscriberEmail,
+        name: &str,
+        attributes: &SubscriberAttributes,
+        subscribed_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        sqlite_insert_subscriptions(
+            &self.sqlite_pool,
+            id,
+            email,
+            name,
+            attributes,
+            subscribed_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscribers(
+        &self,
+        search: Option<&str>,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        sqlite_fetch_subscribers(&self.sqlite_pool, search)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_page(
+        &self,
+        filter: &SubscriberFilter,
+        limit: i64,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        sqlite_fetch_subscriber_page(&self.sqlite_pool, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber(&self, id: uuid::Uuid) -> Result<Option<Subscriber>, StorageError> {
+        sqlite_fetch_subscriber(&self.sqlite_pool, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn confirm_subscriber(&self, id: uuid::Uuid) -> Result<u64, StorageError> {
+        sqlite_confirm_subscriber(&self.sqlite_pool, id)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_delete_subscriber(&self.sqlite_pool, id, deleted_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn restore_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_after: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_restore_subscriber(&self.sqlite_pool, id, deleted_after)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn purge_deleted_subscribers(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_purge_deleted_subscribers(&self.sqlite_pool, deleted_before)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_history(
+        &self,
+        id: uuid::Uuid,
+    ) -> Result<Vec<SubscriberHistoryEntry>, StorageError> {
+        sqlite_fetch_subscriber_history(&self.sqlite_pool, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_delete_unconfirmed_subscribers(&self.sqlite_pool, subscribed_before, deleted_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn anonymise_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_anonymise_unconfirmed_subscribers(&self.sqlite_pool, subscribed_before)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn import_subscribers(
+        &self,
+        subscribers: &[NewSubscriber],
+    ) -> Result<ImportCounts, StorageError> {
+        let transaction = self.begin().await?;
+        let counts = transaction.import_subscribers(subscribers).await?;
+        transaction.commit().await?;
+        Ok(counts)
+    }
+
+    async fn fetch_subscriber_tags(&self, id: uuid::Uuid) -> Result<Vec<String>, StorageError> {
+        sqlite_fetch_subscriber_tags(&self.sqlite_pool, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        id: uuid::Uuid,
+        tags: &[SubscriberTag],
+    ) -> Result<(), StorageError> {
+        let transaction = self.begin().await?;
+        transaction.set_subscriber_tags(id, tags).await?;
+        transaction.commit().await
+    }
+
+    async fn fetch_segments(&self) -> Result<Vec<Segment>, StorageError> {
+        sqlite_fetch_segments(&self.sqlite_pool)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_segment(&self, name: &str) -> Result<Option<Segment>, StorageError> {
+        sqlite_fetch_segment(&self.sqlite_pool, name)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn save_segment(
+        &self,
+        name: &str,
+        expression: &str,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        sqlite_save_segment(&self.sqlite_pool, name, expression, updated_at)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_segment(&self, name: &str) -> Result<u64, StorageError> {
+        sqlite_delete_segment(&self.sqlite_pool, name)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn count_segment(
+        &self,
+        expression: &SegmentExpression,
+    ) -> Result<SegmentSize, StorageError> {
+        sqlite_count_segment(&self.sqlite_pool, expression)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_attribute_schema(&self) -> Result<AttributeSchema, StorageError> {
+        sqlite_fetch_attribute_schema(&self.sqlite_pool)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_attribute_schema(&self, schema: &AttributeSchema) -> Result<(), StorageError> {
+        let transaction = self.begin().await?;
+        transaction.set_attribute_schema(schema).await?;
+        transaction.commit().await
+    }
+
+    async fn insert_user(
+        &self,
+        user_id: uuid::Uuid,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        sqlite_insert_user(
+            &self.sqlite_pool,
+            user_id,
+            username,
+            password_hash,
+            role,
+            created_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<u64, StorageError> {
+        sqlite_set_user_disabled(&self.sqlite_pool, username, disabled)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_user_credentials(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserCredentials>, StorageError> {
+        sqlite_fetch_user_credentials(&self.sqlite_pool, username)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: uuid::Uuid,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        second_factor_pending: bool,
+    ) -> Result<(), StorageError> {
+        sqlite_insert_session(
+            &self.sqlite_pool,
+            token_hash,
+            user_id,
+            created_at,
+            expires_at,
+            second_factor_pending,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_session_user(
+        &self,
+        token_hash: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<SessionUser>, StorageError> {
+        sqlite_fetch_session_user(&self.sqlite_pool, token_hash, now)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn complete_second_factor(
+        &self,
+        token_hash: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_complete_second_factor(&self.sqlite_pool, token_hash, expires_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> Result<u64, StorageError> {
+        sqlite_delete_session(&self.sqlite_pool, token_hash)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_totp_state(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<TotpState>, StorageError> {
+        sqlite_fetch_totp_state(&self.sqlite_pool, user_id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_pending_totp_secret(
+        &self,
+        user_id: uuid::Uuid,
+        secret: &str,
+    ) -> Result<u64, StorageError> {
+        sqlite_set_pending_totp_secret(&self.sqlite_pool, user_id, secret)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn enable_totp(
+        &self,
+        user_id: uuid::Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), StorageError> {
+        // 복구 코드가 교체되지 않은 채로 활성화되지 않도록 트랜잭션을 사용한다.
+        let mut transaction = self.sqlite_pool.begin().await?;
+        sqlite_enable_totp(&mut *transaction, user_id).await?;
+        sqlite_delete_recovery_codes(&mut *transaction, user_id).await?;
+        for code_hash in recovery_code_hashes {
+            sqlite_insert_recovery_code(&mut *transaction, user_id, code_hash).await?;
+        }
+        Ok(transaction.commit().await?)
+    }
+
+    async fn disable_totp(&self, user_id: uuid::Uuid) -> Result<(), StorageError> {
+        let mut transaction = self.sqlite_pool.begin().await?;
+        sqlite_disable_totp(&mut *transaction, user_id).await?;
+        sqlite_delete_recovery_codes(&mut *transaction, user_id).await?;
+        Ok(transaction.commit().await?)
+    }
+
+    async fn record_totp_step(&self, user_id: uuid::Uuid, step: i64) -> Result<u64, StorageError> {
+        sqlite_record_totp_step(&self.sqlite_pool, user_id, step)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: uuid::Uuid,
+        code_hash: &str,
+        used_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_use_recovery_code(&self.sqlite_pool, user_id, code_hash, used_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_require_two_factor(&self) -> Result<bool, StorageError> {
+        sqlite_fetch_require_two_factor(&self.sqlite_pool)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_require_two_factor(&self, required: bool) -> Result<(), StorageError> {
+        sqlite_set_require_two_factor(&self.sqlite_pool, required)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> Result<Option<LoginThrottle>, StorageError> {
+        sqlite_fetch_login_throttle(&self.sqlite_pool, scope, subject)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn lock_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        sqlite_lock_login_throttle(&self.sqlite_pool, scope, subject, now)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn record_login_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+        window_start: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        sqlite_record_login_failure(&self.sqlite_pool, scope, subject, now, window_start)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn lock_login(
+        &self,
+        scope: &str,
+        subject: &str,
+        locked_until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_lock_login(&self.sqlite_pool, scope, subject, locked_until)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn forgive_login_failure(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        sqlite_forgive_login_failure(&self.sqlite_pool, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn clear_login_failures(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        sqlite_clear_login_failures(&self.sqlite_pool, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_audit_entry(&self, entry: &NewAuditEntry) -> Result<(), StorageError> {
+        sqlite_insert_audit_entry(&self.sqlite_pool, entry)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_audit_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, StorageError> {
+        sqlite_fetch_audit_entries(&self.sqlite_pool, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+}
+
+impl Deref for SqlitePool {
+    type Target = sqlx::SqlitePool;
+    fn deref(&self) -> &Self::Target {
+        &self.sqlite_pool
+    }
+}
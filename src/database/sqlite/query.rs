@@ -0,0 +1,1162 @@
+use secrecy::Secret;
+use serde_json::Value;
+use sqlx::{
+    sqlite::SqliteQueryResult, types::Json, QueryBuilder, Row, Sqlite, SqliteConnection,
+    SqliteExecutor,
+};
+
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    database::basic::{
+        ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser, Subscriber,
+        SubscriberFilter, SubscriberHistoryEntry, TotpState, UserCredentials,
+    },
+    domain::{
+        AttributeDefinition, AttributeSchema, AttributeType, SubscriberAttributes, SubscriberEmail,
+        SubscriberTag,
+    },
+    segment::SegmentExpression,
+};
+
+// SQLite는 컴파일 시점에 확인할 DB가 없으므로 `query!` 매크로 대신 런타임 쿼리를 사용한다.
+// 쿼리의 의미는 `postgres::query`와 같게 유지한다.
+
+#[tracing::instrument(name = "Saving new subscriber details in the database.", skip_all)]
+pub async fn sqlite_insert_subscriptions(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+    email: &SubscriberEmail,
+    name: &str,
+    attributes: &SubscriberAttributes,
+    subscribed_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO subscriptions (
+            id, email, canonical_email, name, attributes, subscribed_at, status
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'pending_confirmation');
+        "#,
+    )
+    .bind(id)
+    .bind(email.as_str())
+    .bind(email.canonical())
+    .bind(name)
+    .bind(Json(attributes))
+    .bind(subscribed_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching subscribers from the database.", skip(executor))]
+pub async fn sqlite_fetch_subscribers(
+    executor: impl SqliteExecutor<'_>,
+    search: Option<&str>,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    // `%`, `_`가 와일드카드로 해석되지 않도록 `LIKE` 대신 `instr`로 찾는다.
+    // SQLite의 `lower`는 ASCII 문자만 바꾼다.
+    sqlx::query_as(
+        r#"
+        SELECT id, email, name, status, subscribed_at, attributes
+        FROM subscriptions
+        WHERE deleted_at IS NULL
+            AND (?1 IS NULL
+                OR instr(lower(email), lower(?1)) > 0
+                OR instr(lower(name), lower(?1)) > 0)
+        ORDER BY subscribed_at, id;
+        "#,
+    )
+    .bind(search)
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Fetching a page of subscribers from the database.",
+    skip(executor)
+)]
+pub async fn sqlite_fetch_subscriber_page(
+    executor: impl SqliteExecutor<'_>,
+    filter: &SubscriberFilter,
+    limit: i64,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    // 시각은 UTC의 RFC 3339 문자열로 저장되므로 문자열 비교로 순서를 비교할 수 있다.
+    // SQLite에는 `@>`가 없으므로 조건의 속성 중 일치하지 않는 것이 없는지 확인한다.
+    // 목록 조건은 값이 하나인 배열이며 구독자의 목록에 그 값이 있어야 한다.
+    // 속성 이름은 스키마로 검증했으므로 경로에 그대로 넣을 수 있다.
+    sqlx::query_as(
+        r#"
+        SELECT id, email, name, status, subscribed_at, attributes
+        FROM subscriptions
+        WHERE deleted_at IS NULL
+            AND (?1 IS NULL OR status = ?1)
+            AND (?2 IS NULL OR instr(lower(email), lower(?2)) > 0)
+            AND (?3 IS NULL OR subscribed_at >= ?3)
+            AND (?4 IS NULL OR subscribed_at < ?4)
+            AND (?5 IS NULL OR (subscribed_at, id) < (?5, ?6))
+            AND (?8 IS NULL OR NOT EXISTS (
+                SELECT 1
+                FROM json_each(?8) AS wanted
+                WHERE CASE wanted.type
+                    WHEN 'array' THEN NOT EXISTS (
+                        SELECT 1
+                        FROM json_each(subscriptions.attributes, '$.' || wanted.key) AS item
+                        WHERE item.value = wanted.value ->> 0
+                    )
+                    ELSE subscriptions.attributes ->> wanted.key IS NOT wanted.value
+                END
+            ))
+        ORDER BY subscribed_at DESC, id DESC
+        LIMIT ?7;
+        "#,
+    )
+    .bind(&filter.status)
+    .bind(&filter.email)
+    .bind(filter.since)
+    .bind(filter.until)
+    .bind(filter.cursor.map(|cursor| cursor.subscribed_at))
+    .bind(filter.cursor.map(|cursor| cursor.id))
+    .bind(limit)
+    .bind(filter.attributes.as_ref().map(Json))
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching a subscriber from the database.", skip(executor))]
+pub async fn sqlite_fetch_subscriber(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, email, name, status, subscribed_at, attributes
+        FROM subscriptions
+        WHERE id = ?1 AND deleted_at IS NULL;
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Confirming a subscriber in the database.", skip(executor))]
+pub async fn sqlite_confirm_subscriber(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET status = 'confirmed'
+        WHERE id = ?1 AND deleted_at IS NULL;
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting a subscriber from the database.", skip(executor))]
+pub async fn sqlite_delete_subscriber(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+    deleted_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET deleted_at = ?2
+        WHERE id = ?1 AND deleted_at IS NULL;
+        "#,
+    )
+    .bind(id)
+    .bind(deleted_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Restoring a subscriber in the database.", skip(executor))]
+pub async fn sqlite_restore_subscriber(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+    deleted_after: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET deleted_at = NULL
+        WHERE id = ?1 AND deleted_at >= ?2;
+        "#,
+    )
+    .bind(id)
+    .bind(deleted_after)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Purging deleted subscribers.", skip(executor))]
+pub async fn sqlite_purge_deleted_subscribers(
+    executor: impl SqliteExecutor<'_>,
+    deleted_before: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM subscriptions
+        WHERE deleted_at < ?1;
+        "#,
+    )
+    .bind(deleted_before)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Fetching the history of a subscriber from the database.",
+    skip(executor)
+)]
+pub async fn sqlite_fetch_subscriber_history(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<Vec<SubscriberHistoryEntry>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT occurred_at, operation, email, name, status, attributes
+        FROM subscription_history
+        WHERE subscriber_id = ?1
+        ORDER BY id;
+        "#,
+    )
+    .bind(id)
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting unconfirmed subscribers.", skip(executor))]
+pub async fn sqlite_delete_unconfirmed_subscribers(
+    executor: impl SqliteExecutor<'_>,
+    subscribed_before: chrono::DateTime<chrono::Utc>,
+    deleted_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET deleted_at = ?2
+        WHERE status = 'pending_confirmation' AND subscribed_at < ?1 AND deleted_at IS NULL;
+        "#,
+    )
+    .bind(subscribed_before)
+    .bind(deleted_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Anonymising unconfirmed subscribers.", skip(executor))]
+pub async fn sqlite_anonymise_unconfirmed_subscribers(
+    executor: impl SqliteExecutor<'_>,
+    subscribed_before: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    // id는 BLOB이므로 16진수 문자열을 Postgres의 `uuid::TEXT`와 같은 형식으로 바꿔서 주소를 만든다.
+    sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET email = placeholder.address,
+            canonical_email = placeholder.address,
+            name = '',
+            attributes = '{}',
+            status = 'anonymised'
+        FROM (
+            SELECT id,
+                lower(
+                    substr(hex(id), 1, 8) || '-' || substr(hex(id), 9, 4) || '-' ||
+                    substr(hex(id), 13, 4) || '-' || substr(hex(id), 17, 4) || '-' ||
+                    substr(hex(id), 21)
+                ) || '@anonymised.invalid' AS address
+            FROM subscriptions
+        ) AS placeholder
+        WHERE subscriptions.id = placeholder.id
+            AND status = 'pending_confirmation'
+            AND subscribed_at < ?1;
+        "#,
+    )
+    .bind(subscribed_before)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Importing subscribers into the database.",
+    skip_all,
+    fields(count = subscribers.len())
+)]
+pub async fn sqlite_import_subscribers(
+    connection: &mut SqliteConnection,
+    subscribers: &[NewSubscriber],
+) -> Result<ImportCounts, sqlx::Error> {
+    sqlite_begin_import(&mut *connection).await?;
+    sqlite_stage_import(&mut *connection, subscribers).await?;
+    sqlite_finish_import(connection).await
+}
+
+pub async fn sqlite_begin_import(connection: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TEMPORARY TABLE subscriptions_import (
+            id BLOB NOT NULL,
+            email TEXT NOT NULL,
+            canonical_email TEXT NOT NULL,
+            name TEXT NOT NULL,
+            subscribed_at TEXT NOT NULL
+        );
+        "#,
+    )
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+// SQLite에는 `COPY`가 없으므로 임시 테이블에 한 행씩 추가한다.
+#[tracing::instrument(
+    name = "Staging subscribers for the import.",
+    skip_all,
+    fields(count = subscribers.len())
+)]
+pub async fn sqlite_stage_import(
+    connection: &mut SqliteConnection,
+    subscribers: &[NewSubscriber],
+) -> Result<(), sqlx::Error> {
+    for subscriber in subscribers {
+        sqlx::query(
+            r#"
+            INSERT INTO subscriptions_import (id, email, canonical_email, name, subscribed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5);
+            "#,
+        )
+        .bind(subscriber.id)
+        .bind(subscriber.email.as_str())
+        .bind(subscriber.email.canonical())
+        .bind(subscriber.name.as_str())
+        .bind(subscriber.subscribed_at)
+        .execute(&mut *connection)
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn sqlite_finish_import(
+    connection: &mut SqliteConnection,
+) -> Result<ImportCounts, sqlx::Error> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE subscriptions
+        SET name = import.name
+        FROM subscriptions_import AS import
+        WHERE subscriptions.canonical_email = import.canonical_email
+            AND subscriptions.deleted_at IS NULL
+            AND subscriptions.name <> import.name;
+        "#,
+    )
+    .execute(&mut *connection)
+    .await?
+    .rows_affected();
+    // `INSERT ... SELECT`에 `ON CONFLICT`를 붙이려면 `WHERE` 절이 필요하다.
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO subscriptions (id, email, canonical_email, name, subscribed_at, status)
+        SELECT id, email, canonical_email, name, subscribed_at, 'confirmed'
+        FROM subscriptions_import
+        WHERE true
+        ON CONFLICT DO NOTHING;
+        "#,
+    )
+    .execute(&mut *connection)
+    .await?
+    .rows_affected();
+
+    sqlx::query("DROP TABLE subscriptions_import;")
+        .execute(&mut *connection)
+        .await?;
+    Ok(ImportCounts { inserted, updated })
+}
+
+pub async fn sqlite_fetch_attribute_schema(
+    executor: impl SqliteExecutor<'_>,
+) -> Result<AttributeSchema, sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT name, type
+        FROM subscriber_attributes
+        ORDER BY name;
+        "#,
+    )
+    .fetch_all(executor)
+    .await?;
+    let definitions = rows
+        .into_iter()
+        .map(|(name, r#type)| {
+            Ok(AttributeDefinition {
+                r#type: AttributeType::try_from(r#type.as_str())?,
+                name,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| sqlx::Error::Decode(e.into()))?;
+    AttributeSchema::parse(definitions).map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+#[tracing::instrument(name = "Replacing the subscriber attribute schema.", skip_all)]
+pub async fn sqlite_set_attribute_schema(
+    connection: &mut SqliteConnection,
+    schema: &AttributeSchema,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM subscriber_attributes;")
+        .execute(&mut *connection)
+        .await?;
+    for definition in schema.definitions() {
+        sqlx::query(
+            r#"
+            INSERT INTO subscriber_attributes (name, type)
+            VALUES (?1, ?2);
+            "#,
+        )
+        .bind(&definition.name)
+        .bind(definition.r#type.as_str())
+        .execute(&mut *connection)
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn sqlite_fetch_subscriber_tags(
+    executor: impl SqliteExecutor<'_>,
+    id: uuid::Uuid,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT tag
+        FROM subscription_tags
+        WHERE subscriber_id = ?1
+        ORDER BY tag;
+        "#,
+    )
+    .bind(id)
+    .fetch_all(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Replacing the tags of a subscriber.", skip(connection))]
+pub async fn sqlite_set_subscriber_tags(
+    connection: &mut SqliteConnection,
+    id: uuid::Uuid,
+    tags: &[SubscriberTag],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM subscription_tags
+        WHERE subscriber_id = ?1;
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *connection)
+    .await?;
+    for tag in tags {
+        sqlx::query(
+            r#"
+            INSERT INTO subscription_tags (subscriber_id, tag)
+            VALUES (?1, ?2)
+            ON CONFLICT DO NOTHING;
+            "#,
+        )
+        .bind(id)
+        .bind(tag.as_str())
+        .execute(&mut *connection)
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn sqlite_fetch_segments(
+    executor: impl SqliteExecutor<'_>,
+) -> Result<Vec<Segment>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT name, expression, updated_at
+        FROM segments
+        ORDER BY name;
+        "#,
+    )
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn sqlite_fetch_segment(
+    executor: impl SqliteExecutor<'_>,
+    name: &str,
+) -> Result<Option<Segment>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT name, expression, updated_at
+        FROM segments
+        WHERE name = ?1;
+        "#,
+    )
+    .bind(name)
+    .fetch_optional(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Saving a segment in the database.", skip(executor))]
+pub async fn sqlite_save_segment(
+    executor: impl SqliteExecutor<'_>,
+    name: &str,
+    expression: &str,
+    updated_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO segments (name, expression, updated_at)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT (name) DO UPDATE
+        SET expression = excluded.expression, updated_at = excluded.updated_at;
+        "#,
+    )
+    .bind(name)
+    .bind(expression)
+    .bind(updated_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting a segment from the database.", skip(executor))]
+pub async fn sqlite_delete_segment(
+    executor: impl SqliteExecutor<'_>,
+    name: &str,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM segments
+        WHERE name = ?1;
+        "#,
+    )
+    .bind(name)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Counting the subscribers of a segment.", skip(executor))]
+pub async fn sqlite_count_segment(
+    executor: impl SqliteExecutor<'_>,
+    expression: &SegmentExpression,
+) -> Result<SegmentSize, sqlx::Error> {
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE status = 'confirmed') AS confirmed
+        FROM subscriptions
+        WHERE deleted_at IS NULL AND "#,
+    );
+    push_segment_condition(&mut builder, expression);
+    builder
+        .build_query_as::<SegmentSize>()
+        .fetch_one(executor)
+        .await
+}
+
+/// 세그먼트 식을 `subscriptions` 행에 대한 조건으로 변환한다.
+/// 속성이 없는 구독자는 Postgres의 `@>`처럼 조건에 맞지 않는 것으로 취급한다.
+fn push_segment_condition(builder: &mut QueryBuilder<'_, Sqlite>, expression: &SegmentExpression) {
+    match expression {
+        SegmentExpression::Tag(tag) => {
+            builder.push(
+                "EXISTS (SELECT 1 FROM subscription_tags \
+                 WHERE subscriber_id = subscriptions.id AND tag = ",
+            );
+            builder.push_bind(tag.as_str().to_string());
+            builder.push(")");
+        }
+        SegmentExpression::Status(status) => {
+            builder.push("status = ");
+            builder.push_bind(status.clone());
+        }
+        SegmentExpression::Attribute { name, value, list } => {
+            if *list {
+                builder.push("EXISTS (SELECT 1 FROM json_each(subscriptions.attributes, '$.' || ");
+                builder.push_bind(name.clone());
+                builder.push(") WHERE value = ");
+            } else {
+                builder.push("(subscriptions.attributes ->> ");
+                builder.push_bind(name.clone());
+                builder.push(") IS ");
+            }
+            match value {
+                Value::Bool(value) => builder.push_bind(*value),
+                Value::Number(number) => match number.as_i64() {
+                    Some(value) => builder.push_bind(value),
+                    None => builder.push_bind(number.as_f64()),
+                },
+                value => builder.push_bind(value.as_str().map(str::to_string)),
+            };
+            if *list {
+                builder.push(")");
+            }
+        }
+        SegmentExpression::SubscribedAt(comparison, timestamp) => {
+            builder.push("subscribed_at ");
+            builder.push(comparison.as_sql());
+            builder.push(" ");
+            builder.push_bind(*timestamp);
+        }
+        SegmentExpression::Not(inner) => {
+            builder.push("NOT (");
+            push_segment_condition(builder, inner);
+            builder.push(")");
+        }
+        SegmentExpression::And(left, right) => {
+            builder.push("(");
+            push_segment_condition(builder, left);
+            builder.push(") AND (");
+            push_segment_condition(builder, right);
+            builder.push(")");
+        }
+        SegmentExpression::Or(left, right) => {
+            builder.push("(");
+            push_segment_condition(builder, left);
+            builder.push(") OR (");
+            push_segment_condition(builder, right);
+            builder.push(")");
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Saving new user in the database.",
+    skip(executor, password_hash)
+)]
+pub async fn sqlite_insert_user(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+    username: &str,
+    password_hash: &str,
+    role: &str,
+    created_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO users (user_id, username, password_hash, role, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5);
+        "#,
+    )
+    .bind(user_id)
+    .bind(username)
+    .bind(password_hash)
+    .bind(role)
+    .bind(created_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Changing user's disabled flag in the database.",
+    skip(executor)
+)]
+pub async fn sqlite_set_user_disabled(
+    executor: impl SqliteExecutor<'_>,
+    username: &str,
+    disabled: bool,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET disabled = ?2
+        WHERE username = ?1;
+        "#,
+    )
+    .bind(username)
+    .bind(disabled)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching user credentials from the database.", skip(executor))]
+pub async fn sqlite_fetch_user_credentials(
+    executor: impl SqliteExecutor<'_>,
+    username: &str,
+) -> Result<Option<UserCredentials>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT user_id, password_hash, disabled, role, totp_enabled
+        FROM users
+        WHERE username = ?1;
+        "#,
+    )
+    .bind(username)
+    .fetch_optional(executor)
+    .await?;
+    row.map(|row| {
+        Ok(UserCredentials {
+            user_id: row.try_get("user_id")?,
+            password_hash: Secret::new(row.try_get("password_hash")?),
+            disabled: row.try_get("disabled")?,
+            role: row.try_get("role")?,
+            totp_enabled: row.try_get("totp_enabled")?,
+        })
+    })
+    .transpose()
+}
+
+#[tracing::instrument(
+    name = "Saving new session in the database.",
+    skip(executor, token_hash)
+)]
+pub async fn sqlite_insert_session(
+    executor: impl SqliteExecutor<'_>,
+    token_hash: &str,
+    user_id: uuid::Uuid,
+    created_at: chrono::DateTime<chrono::Utc>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    second_factor_pending: bool,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO admin_sessions
+            (token_hash, user_id, created_at, expires_at, second_factor_pending)
+        VALUES (?1, ?2, ?3, ?4, ?5);
+        "#,
+    )
+    .bind(token_hash)
+    .bind(user_id)
+    .bind(created_at)
+    .bind(expires_at)
+    .bind(second_factor_pending)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Fetching session from the database.",
+    skip(executor, token_hash)
+)]
+pub async fn sqlite_fetch_session_user(
+    executor: impl SqliteExecutor<'_>,
+    token_hash: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<Option<SessionUser>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT u.user_id, u.username, u.role, u.totp_enabled, s.second_factor_pending
+        FROM admin_sessions s
+        JOIN users u ON u.user_id = s.user_id
+        WHERE s.token_hash = ?1
+            AND s.expires_at > ?2
+            AND NOT u.disabled;
+        "#,
+    )
+    .bind(token_hash)
+    .bind(now)
+    .fetch_optional(executor)
+    .await
+}
+
+#[tracing::instrument(
+    name = "Completing second factor of session.",
+    skip(executor, token_hash)
+)]
+pub async fn sqlite_complete_second_factor(
+    executor: impl SqliteExecutor<'_>,
+    token_hash: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE admin_sessions
+        SET second_factor_pending = FALSE, expires_at = ?2
+        WHERE token_hash = ?1 AND second_factor_pending;
+        "#,
+    )
+    .bind(token_hash)
+    .bind(expires_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting session from the database.", skip_all)]
+pub async fn sqlite_delete_session(
+    executor: impl SqliteExecutor<'_>,
+    token_hash: &str,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM admin_sessions
+        WHERE token_hash = ?1;
+        "#,
+    )
+    .bind(token_hash)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching TOTP state from the database.", skip(executor))]
+pub async fn sqlite_fetch_totp_state(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<Option<TotpState>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT username, totp_secret, totp_enabled
+        FROM users
+        WHERE user_id = ?1;
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+    row.map(|row| {
+        Ok(TotpState {
+            username: row.try_get("username")?,
+            secret: row
+                .try_get::<Option<String>, _>("totp_secret")?
+                .map(Secret::new),
+            enabled: row.try_get("totp_enabled")?,
+        })
+    })
+    .transpose()
+}
+
+#[tracing::instrument(name = "Saving pending TOTP secret.", skip(executor, secret))]
+pub async fn sqlite_set_pending_totp_secret(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+    secret: &str,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_secret = ?2, totp_last_step = NULL
+        WHERE user_id = ?1 AND NOT totp_enabled;
+        "#,
+    )
+    .bind(user_id)
+    .bind(secret)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Enabling TOTP.", skip(executor))]
+pub async fn sqlite_enable_totp(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_enabled = TRUE
+        WHERE user_id = ?1 AND totp_secret IS NOT NULL;
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Disabling TOTP.", skip(executor))]
+pub async fn sqlite_disable_totp(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_enabled = FALSE, totp_secret = NULL, totp_last_step = NULL
+        WHERE user_id = ?1;
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Deleting recovery codes.", skip(executor))]
+pub async fn sqlite_delete_recovery_codes(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM recovery_codes
+        WHERE user_id = ?1;
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await
+}
+
+/// SQLite에는 배열 타입이 없으므로 복구 코드를 하나씩 추가한다.
+#[tracing::instrument(name = "Saving recovery code.", skip(executor, code_hash))]
+pub async fn sqlite_insert_recovery_code(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+    code_hash: &str,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO recovery_codes (user_id, code_hash)
+        VALUES (?1, ?2);
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Recording used TOTP step.", skip(executor))]
+pub async fn sqlite_record_totp_step(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+    step: i64,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    // 조건부 갱신으로 동시에 같은 코드를 사용하는 경우에도 한 번만 성공한다.
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_last_step = ?2
+        WHERE user_id = ?1 AND (totp_last_step IS NULL OR totp_last_step < ?2);
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Using recovery code.", skip(executor, code_hash))]
+pub async fn sqlite_use_recovery_code(
+    executor: impl SqliteExecutor<'_>,
+    user_id: uuid::Uuid,
+    code_hash: &str,
+    used_at: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE recovery_codes
+        SET used_at = ?3
+        WHERE user_id = ?1 AND code_hash = ?2 AND used_at IS NULL;
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .bind(used_at)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching two-factor requirement.", skip(executor))]
+pub async fn sqlite_fetch_require_two_factor(
+    executor: impl SqliteExecutor<'_>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT require_two_factor
+        FROM security_settings;
+        "#,
+    )
+    .fetch_one(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Changing two-factor requirement.", skip(executor))]
+pub async fn sqlite_set_require_two_factor(
+    executor: impl SqliteExecutor<'_>,
+    required: bool,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE security_settings
+        SET require_two_factor = ?1;
+        "#,
+    )
+    .bind(required)
+    .execute(executor)
+    .await
+}
+
+pub async fn sqlite_fetch_login_throttle(
+    executor: impl SqliteExecutor<'_>,
+    scope: &str,
+    subject: &str,
+) -> Result<Option<LoginThrottle>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT failures, last_failure_at, locked_until
+        FROM login_attempts
+        WHERE scope = ?1 AND subject = ?2;
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn sqlite_lock_login_throttle(
+    executor: impl SqliteExecutor<'_>,
+    scope: &str,
+    subject: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<LoginThrottle, sqlx::Error> {
+    // 쓰기로 시작해야 트랜잭션이 끝날 때까지 다른 쓰기가 기다린다.
+    sqlx::query_as(
+        r#"
+        INSERT INTO login_attempts (scope, subject, failures, last_failure_at)
+        VALUES (?1, ?2, 0, ?3)
+        ON CONFLICT (scope, subject) DO UPDATE
+        SET failures = login_attempts.failures
+        RETURNING failures, last_failure_at, locked_until;
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .bind(now)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn sqlite_record_login_failure(
+    executor: impl SqliteExecutor<'_>,
+    scope: &str,
+    subject: &str,
+    now: chrono::DateTime<chrono::Utc>,
+    window_start: chrono::DateTime<chrono::Utc>,
+) -> Result<LoginThrottle, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO login_attempts (scope, subject, failures, last_failure_at)
+        VALUES (?1, ?2, 1, ?3)
+        ON CONFLICT (scope, subject) DO UPDATE
+        SET failures = CASE
+                WHEN login_attempts.last_failure_at < ?4 OR login_attempts.locked_until <= ?3
+                THEN 1
+                ELSE login_attempts.failures + 1
+            END,
+            last_failure_at = ?3,
+            locked_until = CASE
+                WHEN login_attempts.locked_until <= ?3 THEN NULL
+                ELSE login_attempts.locked_until
+            END
+        RETURNING failures, last_failure_at, locked_until;
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .bind(now)
+    .bind(window_start)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn sqlite_lock_login(
+    executor: impl SqliteExecutor<'_>,
+    scope: &str,
+    subject: &str,
+    locked_until: chrono::DateTime<chrono::Utc>,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE login_attempts
+        SET locked_until = ?3
+        WHERE scope = ?1 AND subject = ?2;
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .bind(locked_until)
+    .execute(executor)
+    .await
+}
+
+pub async fn sqlite_forgive_login_failure(
+    executor: impl SqliteExecutor<'_>,
+    scope: &str,
+    subject: &str,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE login_attempts
+        SET failures = failures - 1
+        WHERE scope = ?1 AND subject = ?2 AND failures > 0;
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .execute(executor)
+    .await
+}
+
+pub async fn sqlite_clear_login_failures(
+    executor: impl SqliteExecutor<'_>,
+    scope: &str,
+    subject: &str,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM login_attempts
+        WHERE scope = ?1 AND subject = ?2;
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Saving audit entry in the database.", skip(executor))]
+pub async fn sqlite_insert_audit_entry(
+    executor: impl SqliteExecutor<'_>,
+    entry: &NewAuditEntry,
+) -> Result<SqliteQueryResult, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (occurred_at, actor, action, target, request_id, ip, diff)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);
+        "#,
+    )
+    .bind(entry.occurred_at)
+    .bind(&entry.actor)
+    .bind(entry.action.as_str())
+    .bind(&entry.target)
+    .bind(&entry.request_id)
+    .bind(&entry.ip)
+    .bind(Json(&entry.diff))
+    .execute(executor)
+    .await
+}
+
+#[tracing::instrument(name = "Fetching audit entries from the database.", skip(executor))]
+pub async fn sqlite_fetch_audit_entries(
+    executor: impl SqliteExecutor<'_>,
+    filter: &AuditFilter,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, occurred_at, actor, action, target, request_id, ip, diff
+        FROM audit_log
+        WHERE (?1 IS NULL OR actor = ?1)
+            AND (?2 IS NULL OR action = ?2)
+            AND (?3 IS NULL OR target = ?3)
+            AND (?4 IS NULL OR occurred_at >= ?4)
+            AND (?5 IS NULL OR occurred_at < ?5)
+            AND (?6 IS NULL OR id < ?6)
+        ORDER BY id DESC
+        LIMIT ?7;
+        "#,
+    )
+    .bind(&filter.actor)
+    .bind(&filter.action)
+    .bind(&filter.target)
+    .bind(filter.since)
+    .bind(filter.until)
+    .bind(filter.before)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,550 @@
+use sqlx::{Connection, Sqlite};
+
+use crate::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    database::{
+        basic::{
+            ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser,
+            Subscriber, SubscriberFilter, SubscriberHistoryEntry, TotpState, UserCredentials,
+            Zero2ProdDatabase, Zero2ProdTransaction,
+        },
+        error::StorageError,
+        transaction::SharedTransaction,
+    },
+    domain::{AttributeSchema, SubscriberAttributes, SubscriberEmail, SubscriberTag},
+    segment::SegmentExpression,
+};
+
+use super::*;
+
+/// 진행 중인 SQLite 트랜잭션
+///
+/// 모든 쿼리는 트랜잭션의 커넥션에서 실행된다.
+/// SQLite는 쓰기 트랜잭션을 하나만 허용하므로 트랜잭션이 끝날 때까지 다른 쓰기는 기다린다.
+pub struct SqliteTransaction {
+    pub(super) transaction: SharedTransaction<Sqlite>,
+}
+
+impl Zero2ProdTransaction for SqliteTransaction {
+    async fn commit(self) -> Result<(), StorageError> {
+        self.transaction.commit().await.map_err(StorageError::from)
+    }
+
+    async fn rollback(self) -> Result<(), StorageError> {
+        self.transaction
+            .rollback()
+            .await
+            .map_err(StorageError::from)
+    }
+
+    // SQLite는 프로세스 하나에서만 사용하므로 경쟁할 다른 인스턴스가 없다.
+    async fn try_advisory_lock(&self, _key: i64) -> Result<bool, StorageError> {
+        Ok(true)
+    }
+
+    async fn begin_import(&self) -> Result<(), StorageError> {
+        sqlite_begin_import(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn stage_import(&self, subscribers: &[NewSubscriber]) -> Result<(), StorageError> {
+        sqlite_stage_import(&mut *self.transaction.connection().await?, subscribers)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn finish_import(&self) -> Result<ImportCounts, StorageError> {
+        sqlite_finish_import(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+}
+
+impl Zero2ProdDatabase for SqliteTransaction {
+    type Transaction = Self;
+
+    async fn begin(&self) -> Result<Self::Transaction, StorageError> {
+        self.transaction
+            .begin_nested()
+            .await
+            .map(|transaction| Self { transaction })
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_subscriptions(
+        &self,
+        id: uuid::Uuid,
+        email: &SubscriberEmail,
+        name: &str,
+        attributes: &SubscriberAttributes,
+        subscribed_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        sqlite_insert_subscriptions(
+            &mut *self.transaction.connection().await?,
+            id,
+            email,
+            name,
+            attributes,
+            subscribed_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscribers(
+        &self,
+        search: Option<&str>,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        sqlite_fetch_subscribers(&mut *self.transaction.connection().await?, search)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_page(
+        &self,
+        filter: &SubscriberFilter,
+        limit: i64,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        sqlite_fetch_subscriber_page(&mut *self.transaction.connection().await?, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber(&self, id: uuid::Uuid) -> Result<Option<Subscriber>, StorageError> {
+        sqlite_fetch_subscriber(&mut *self.transaction.connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn confirm_subscriber(&self, id: uuid::Uuid) -> Result<u64, StorageError> {
+        sqlite_confirm_subscriber(&mut *self.transaction.connection().await?, id)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_delete_subscriber(&mut *self.transaction.connection().await?, id, deleted_at)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn restore_subscriber(
+        &self,
+        id: uuid::Uuid,
+        deleted_after: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_restore_subscriber(
+            &mut *self.transaction.connection().await?,
+            id,
+            deleted_after,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn purge_deleted_subscribers(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_purge_deleted_subscribers(&mut *self.transaction.connection().await?, deleted_before)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_history(
+        &self,
+        id: uuid::Uuid,
+    ) -> Result<Vec<SubscriberHistoryEntry>, StorageError> {
+        sqlite_fetch_subscriber_history(&mut *self.transaction.connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn delete_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_delete_unconfirmed_subscribers(
+            &mut *self.transaction.connection().await?,
+            subscribed_before,
+            deleted_at,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn anonymise_unconfirmed_subscribers(
+        &self,
+        subscribed_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_anonymise_unconfirmed_subscribers(
+            &mut *self.transaction.connection().await?,
+            subscribed_before,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn import_subscribers(
+        &self,
+        subscribers: &[NewSubscriber],
+    ) -> Result<ImportCounts, StorageError> {
+        sqlite_import_subscribers(&mut *self.transaction.connection().await?, subscribers)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_subscriber_tags(&self, id: uuid::Uuid) -> Result<Vec<String>, StorageError> {
+        sqlite_fetch_subscriber_tags(&mut *self.transaction.connection().await?, id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        id: uuid::Uuid,
+        tags: &[SubscriberTag],
+    ) -> Result<(), StorageError> {
+        sqlite_set_subscriber_tags(&mut *self.transaction.connection().await?, id, tags)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_segments(&self) -> Result<Vec<Segment>, StorageError> {
+        sqlite_fetch_segments(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_segment(&self, name: &str) -> Result<Option<Segment>, StorageError> {
+        sqlite_fetch_segment(&mut *self.transaction.connection().await?, name)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn save_segment(
+        &self,
+        name: &str,
+        expression: &str,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        sqlite_save_segment(
+            &mut *self.transaction.connection().await?,
+            name,
+            expression,
+            updated_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn delete_segment(&self, name: &str) -> Result<u64, StorageError> {
+        sqlite_delete_segment(&mut *self.transaction.connection().await?, name)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn count_segment(
+        &self,
+        expression: &SegmentExpression,
+    ) -> Result<SegmentSize, StorageError> {
+        sqlite_count_segment(&mut *self.transaction.connection().await?, expression)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_attribute_schema(&self) -> Result<AttributeSchema, StorageError> {
+        sqlite_fetch_attribute_schema(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_attribute_schema(&self, schema: &AttributeSchema) -> Result<(), StorageError> {
+        sqlite_set_attribute_schema(&mut *self.transaction.connection().await?, schema)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_user(
+        &self,
+        user_id: uuid::Uuid,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), StorageError> {
+        sqlite_insert_user(
+            &mut *self.transaction.connection().await?,
+            user_id,
+            username,
+            password_hash,
+            role,
+            created_at,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<u64, StorageError> {
+        sqlite_set_user_disabled(
+            &mut *self.transaction.connection().await?,
+            username,
+            disabled,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_user_credentials(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserCredentials>, StorageError> {
+        sqlite_fetch_user_credentials(&mut *self.transaction.connection().await?, username)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: uuid::Uuid,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        second_factor_pending: bool,
+    ) -> Result<(), StorageError> {
+        sqlite_insert_session(
+            &mut *self.transaction.connection().await?,
+            token_hash,
+            user_id,
+            created_at,
+            expires_at,
+            second_factor_pending,
+        )
+        .await
+        .map(|_| ())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_session_user(
+        &self,
+        token_hash: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<SessionUser>, StorageError> {
+        sqlite_fetch_session_user(&mut *self.transaction.connection().await?, token_hash, now)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn complete_second_factor(
+        &self,
+        token_hash: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_complete_second_factor(
+            &mut *self.transaction.connection().await?,
+            token_hash,
+            expires_at,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> Result<u64, StorageError> {
+        sqlite_delete_session(&mut *self.transaction.connection().await?, token_hash)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_totp_state(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<TotpState>, StorageError> {
+        sqlite_fetch_totp_state(&mut *self.transaction.connection().await?, user_id)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_pending_totp_secret(
+        &self,
+        user_id: uuid::Uuid,
+        secret: &str,
+    ) -> Result<u64, StorageError> {
+        sqlite_set_pending_totp_secret(&mut *self.transaction.connection().await?, user_id, secret)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn enable_totp(
+        &self,
+        user_id: uuid::Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), StorageError> {
+        let mut connection = self.transaction.connection().await?;
+        // 진행 중인 트랜잭션 안에서는 세이브포인트를 만든다.
+        let mut transaction = connection.begin().await?;
+        sqlite_enable_totp(&mut *transaction, user_id).await?;
+        sqlite_delete_recovery_codes(&mut *transaction, user_id).await?;
+        for code_hash in recovery_code_hashes {
+            sqlite_insert_recovery_code(&mut *transaction, user_id, code_hash).await?;
+        }
+        transaction.commit().await.map_err(StorageError::from)
+    }
+
+    async fn disable_totp(&self, user_id: uuid::Uuid) -> Result<(), StorageError> {
+        let mut connection = self.transaction.connection().await?;
+        // 진행 중인 트랜잭션 안에서는 세이브포인트를 만든다.
+        let mut transaction = connection.begin().await?;
+        sqlite_disable_totp(&mut *transaction, user_id).await?;
+        sqlite_delete_recovery_codes(&mut *transaction, user_id).await?;
+        transaction.commit().await.map_err(StorageError::from)
+    }
+
+    async fn record_totp_step(&self, user_id: uuid::Uuid, step: i64) -> Result<u64, StorageError> {
+        sqlite_record_totp_step(&mut *self.transaction.connection().await?, user_id, step)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: uuid::Uuid,
+        code_hash: &str,
+        used_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_use_recovery_code(
+            &mut *self.transaction.connection().await?,
+            user_id,
+            code_hash,
+            used_at,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn fetch_require_two_factor(&self) -> Result<bool, StorageError> {
+        sqlite_fetch_require_two_factor(&mut *self.transaction.connection().await?)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn set_require_two_factor(&self, required: bool) -> Result<(), StorageError> {
+        sqlite_set_require_two_factor(&mut *self.transaction.connection().await?, required)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> Result<Option<LoginThrottle>, StorageError> {
+        sqlite_fetch_login_throttle(&mut *self.transaction.connection().await?, scope, subject)
+            .await
+            .map_err(StorageError::from)
+    }
+
+    async fn lock_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        sqlite_lock_login_throttle(
+            &mut *self.transaction.connection().await?,
+            scope,
+            subject,
+            now,
+        )
+        .await
+        .map_err(StorageError::from)
+    }
+
+    async fn record_login_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: chrono::DateTime<chrono::Utc>,
+        window_start: chrono::DateTime<chrono::Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        sqlite_record_login_failure(
+            &mut *self.transaction.connection().await?,
+            scope,
+            subject,
+            now,
+            window_start,
+        )
+        .await
+        .map_err(StorageError::from)
+    }
+
+    async fn lock_login(
+        &self,
+        scope: &str,
+        subject: &str,
+        locked_until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, StorageError> {
+        sqlite_lock_login(
+            &mut *self.transaction.connection().await?,
+            scope,
+            subject,
+            locked_until,
+        )
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(StorageError::from)
+    }
+
+    async fn forgive_login_failure(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        sqlite_forgive_login_failure(&mut *self.transaction.connection().await?, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn clear_login_failures(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        sqlite_clear_login_failures(&mut *self.transaction.connection().await?, scope, subject)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(StorageError::from)
+    }
+
+    async fn insert_audit_entry(&self, entry: &NewAuditEntry) -> Result<(), StorageError> {
+        sqlite_insert_audit_entry(&mut *self.transaction.connection().await?, entry)
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    async fn fetch_audit_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, StorageError> {
+        sqlite_fetch_audit_entries(&mut *self.transaction.connection().await?, filter, limit)
+            .await
+            .map_err(StorageError::from)
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use sqlx::{pool::PoolConnection, Database, Pool, TransactionManager};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+
+struct TransactionState<DB: Database> {
+    connection: PoolConnection<DB>,
+    /// 열려 있는 트랜잭션과 세이브포인트의 수
+    depth: usize,
+}
+
+impl<DB: Database> TransactionState<DB> {
+    /// `depth`와 그 안쪽의 트랜잭션을 되돌린다.
+    /// 롤백은 커넥션을 다음에 사용하거나 풀에 반환할 때 실행된다.
+    fn start_rollback(&mut self, depth: usize) {
+        while self.depth >= depth && self.depth > 0 {
+            DB::TransactionManager::start_rollback(&mut self.connection);
+            self.depth -= 1;
+        }
+    }
+}
+
+impl<DB: Database> Drop for TransactionState<DB> {
+    /// 모든 핸들이 사라질 때까지 끝나지 않은 트랜잭션은 되돌린 뒤에 풀에 반환한다.
+    fn drop(&mut self) {
+        self.start_rollback(1);
+    }
+}
+
+struct Shared<DB: Database> {
+    state: Mutex<TransactionState<DB>>,
+    /// 잠금을 얻지 못한 채 버려진 핸들 중 가장 바깥쪽의 깊이
+    /// 다음에 커넥션을 사용할 때 이 깊이까지 되돌린다.
+    abandoned: AtomicUsize,
+}
+
+/// 여러 핸들이 공유하는 트랜잭션 커넥션
+///
+/// `sqlx::Transaction`은 커넥션을 빌려서 세이브포인트를 만들기 때문에
+/// `&self`로 쿼리를 실행하는 `Zero2ProdDatabase`에서 사용할 수 없다.
+/// 커넥션을 `Mutex`로 감싸고 핸들마다 자신의 깊이를 기억해서
+/// 가장 안쪽의 트랜잭션만 사용할 수 있도록 한다.
+///
+/// `commit`이나 `rollback`을 호출하지 않고 버리면 롤백한다.
+pub struct SharedTransaction<DB: Database> {
+    shared: Arc<Shared<DB>>,
+    depth: usize,
+    open: bool,
+}
+
+impl<DB: Database> SharedTransaction<DB> {
+    /// 풀에서 커넥션을 가져와 트랜잭션을 시작한다.
+    pub async fn begin(pool: &Pool<DB>) -> Result<Self, sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        DB::TransactionManager::begin(&mut connection).await?;
+        Ok(Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(TransactionState {
+                    connection,
+                    depth: 1,
+                }),
+                abandoned: AtomicUsize::new(usize::MAX),
+            }),
+            depth: 1,
+            open: true,
+        })
+    }
+
+    /// 이 트랜잭션 안에 세이브포인트를 만든다.
+    /// 세이브포인트를 끝낼 때까지 이 핸들은 사용할 수 없다.
+    pub async fn begin_nested(&self) -> Result<Self, sqlx::Error> {
+        let mut state = self.active_state().await?;
+        DB::TransactionManager::begin(&mut state.connection).await?;
+        state.depth += 1;
+        Ok(Self {
+            shared: self.shared.clone(),
+            depth: state.depth,
+            open: true,
+        })
+    }
+
+    /// 쿼리를 실행할 커넥션을 가져온다.
+    pub async fn connection(&self) -> Result<MappedMutexGuard<'_, DB::Connection>, sqlx::Error> {
+        let state = self.active_state().await?;
+        Ok(MutexGuard::map(state, |state| &mut *state.connection))
+    }
+
+    pub async fn commit(mut self) -> Result<(), sqlx::Error> {
+        let mut state = self.active_state().await?;
+        DB::TransactionManager::commit(&mut state.connection).await?;
+        state.depth -= 1;
+        drop(state);
+        self.open = false;
+        Ok(())
+    }
+
+    pub async fn rollback(mut self) -> Result<(), sqlx::Error> {
+        let mut state = self.active_state().await?;
+        DB::TransactionManager::rollback(&mut state.connection).await?;
+        state.depth -= 1;
+        drop(state);
+        self.open = false;
+        Ok(())
+    }
+
+    async fn active_state(&self) -> Result<MutexGuard<'_, TransactionState<DB>>, sqlx::Error> {
+        let mut state = self.shared.state.lock().await;
+        let abandoned = self.shared.abandoned.swap(usize::MAX, Ordering::AcqRel);
+        state.start_rollback(abandoned);
+        if !self.open || state.depth != self.depth {
+            return Err(sqlx::Error::Protocol(
+                "The transaction has finished or a nested transaction is still open.".into(),
+            ));
+        }
+        Ok(state)
+    }
+}
+
+impl<DB: Database> Drop for SharedTransaction<DB> {
+    fn drop(&mut self) {
+        if !self.open {
+            return;
+        }
+        // 안쪽의 세이브포인트까지 함께 되돌린다.
+        // 다른 핸들이 커넥션을 사용하고 있으면 다음에 잠금을 얻는 쪽이 되돌린다.
+        match self.shared.state.try_lock() {
+            Ok(mut state) => state.start_rollback(self.depth),
+            Err(_) => {
+                self.shared
+                    .abandoned
+                    .fetch_min(self.depth, Ordering::AcqRel);
+                // 기록하는 사이에 잠금이 풀렸을 수 있다.
+                if let Ok(mut state) = self.shared.state.try_lock() {
+                    let abandoned = self.shared.abandoned.swap(usize::MAX, Ordering::AcqRel);
+                    state.start_rollback(abandoned);
+                }
+            }
+        }
+    }
+}
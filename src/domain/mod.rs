@@ -0,0 +1,9 @@
+mod subscriber_attributes;
+mod subscriber_email;
+mod subscriber_name;
+mod subscriber_tag;
+
+pub use subscriber_attributes::*;
+pub use subscriber_email::*;
+pub use subscriber_name::*;
+pub use subscriber_tag::*;
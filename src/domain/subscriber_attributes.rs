@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{Map, Value};
+
+/// 구독자 속성 값의 형식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeType {
+    String,
+    Number,
+    Boolean,
+    /// 문자열 목록. 양식에서는 쉼표로 구분한다.
+    List,
+}
+
+impl AttributeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeType::String => "string",
+            AttributeType::Number => "number",
+            AttributeType::Boolean => "boolean",
+            AttributeType::List => "list",
+        }
+    }
+}
+
+impl TryFrom<&str> for AttributeType {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "string" => Ok(Self::String),
+            "number" => Ok(Self::Number),
+            "boolean" => Ok(Self::Boolean),
+            "list" => Ok(Self::List),
+            other => Err(format!("{} is not a supported attribute type.", other)),
+        }
+    }
+}
+
+/// 관리자가 정의한 구독자 속성 하나
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AttributeDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: AttributeType,
+}
+
+/// 구독 양식에서 받을 수 있는 속성의 이름과 형식
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeSchema(BTreeMap<String, AttributeType>);
+
+/// 목록 속성이 가질 수 있는 최대 항목 수
+const MAX_LIST_ITEMS: usize = 32;
+
+impl AttributeSchema {
+    /// 속성 이름은 소문자로 시작하고 소문자, 숫자, `_`로만 이루어진 64자 이하의 문자열이다.
+    /// 구독 양식의 `email`과 `name`은 속성으로 정의할 수 없다.
+    pub fn parse(definitions: Vec<AttributeDefinition>) -> Result<Self, String> {
+        let mut schema = BTreeMap::new();
+        for definition in definitions {
+            let name = definition.name;
+            let valid = name.len() <= 64
+                && name.starts_with(|c: char| c.is_ascii_lowercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(format!("{} is not a valid attribute name.", name));
+            }
+            if name == "email" || name == "name" {
+                return Err(format!("{} is reserved for the subscription form.", name));
+            }
+            if schema.insert(name.clone(), definition.r#type).is_some() {
+                return Err(format!("{} is defined more than once.", name));
+            }
+        }
+        Ok(Self(schema))
+    }
+
+    pub fn definitions(&self) -> Vec<AttributeDefinition> {
+        self.0
+            .iter()
+            .map(|(name, r#type)| AttributeDefinition {
+                name: name.clone(),
+                r#type: *r#type,
+            })
+            .collect()
+    }
+
+    fn get(&self, name: &str) -> Result<AttributeType, String> {
+        self.0
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("{} is not a known attribute.", name))
+    }
+
+    /// 구독 양식의 필드를 속성으로 변환한다.
+    /// 빈 필드는 지정하지 않은 것으로 취급하고, 정의하지 않은 필드는 거부한다.
+    pub fn parse_form(
+        &self,
+        fields: &HashMap<String, String>,
+    ) -> Result<SubscriberAttributes, String> {
+        let mut attributes = Map::new();
+        for (name, raw) in fields {
+            let r#type = self.get(name)?;
+            let raw = raw.trim();
+            if raw.is_empty() {
+                continue;
+            }
+            let value = match r#type {
+                AttributeType::List => {
+                    let mut items = Vec::new();
+                    for item in raw
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                    {
+                        let item = Value::String(parse_text(name, item)?);
+                        if !items.contains(&item) {
+                            items.push(item);
+                        }
+                    }
+                    if items.len() > MAX_LIST_ITEMS {
+                        return Err(format!("{} has more than {} items.", name, MAX_LIST_ITEMS));
+                    }
+                    Value::Array(items)
+                }
+                scalar => parse_scalar(name, scalar, raw)?,
+            };
+            attributes.insert(name.clone(), value);
+        }
+        Ok(SubscriberAttributes(attributes))
+    }
+
+    /// 세그먼트 식의 속성 조건 값을 변환한다.
+    /// 목록 속성에는 포함되어야 하는 항목 하나를 지정한다.
+    pub fn parse_condition(&self, name: &str, raw: &str) -> Result<(AttributeType, Value), String> {
+        let r#type = self.get(name)?;
+        let value = match r#type {
+            AttributeType::List => Value::String(parse_text(name, raw)?),
+            scalar => parse_scalar(name, scalar, raw)?,
+        };
+        Ok((r#type, value))
+    }
+
+    /// 관리용 API의 속성 조건을 검증한다.
+    /// 조건은 속성 이름과 값의 JSON 객체이며, 목록 속성에는 포함되어야 하는 문자열 하나를 지정한다.
+    /// 반환하는 속성은 조건에 맞는 구독자의 속성에 포함된다.
+    pub fn parse_filter(&self, filter: &str) -> Result<SubscriberAttributes, String> {
+        let invalid = || format!("{} is not a valid attribute filter.", filter);
+        let Value::Object(conditions) = serde_json::from_str(filter).map_err(|_| invalid())? else {
+            return Err(invalid());
+        };
+        let mut attributes = Map::new();
+        for (name, value) in conditions {
+            let value = match (self.get(&name)?, value) {
+                (AttributeType::String, value @ Value::String(_))
+                | (AttributeType::Number, value @ Value::Number(_))
+                | (AttributeType::Boolean, value @ Value::Bool(_)) => value,
+                (AttributeType::List, value @ Value::String(_)) => Value::Array(vec![value]),
+                (r#type, _) => {
+                    return Err(format!(
+                        "The filter for {} must be a {}.",
+                        name,
+                        match r#type {
+                            AttributeType::List => "string",
+                            other => other.as_str(),
+                        }
+                    ))
+                }
+            };
+            attributes.insert(name, value);
+        }
+        Ok(SubscriberAttributes(attributes))
+    }
+}
+
+/// 목록이 아닌 속성 값을 변환한다.
+fn parse_scalar(name: &str, r#type: AttributeType, raw: &str) -> Result<Value, String> {
+    let invalid = || format!("{} is not a valid {} for {}.", raw, r#type.as_str(), name);
+    Ok(match r#type {
+        AttributeType::String | AttributeType::List => Value::String(parse_text(name, raw)?),
+        AttributeType::Number => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .ok()
+            .or_else(|| {
+                raw.parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+            })
+            .ok_or_else(invalid)?,
+        // HTML 체크박스는 기본으로 `on`을 보낸다.
+        AttributeType::Boolean => match raw {
+            "true" | "on" => Value::Bool(true),
+            "false" | "off" => Value::Bool(false),
+            _ => return Err(invalid()),
+        },
+    })
+}
+
+fn parse_text(name: &str, raw: &str) -> Result<String, String> {
+    if raw.chars().count() > 256 {
+        return Err(format!("{} is longer than 256 characters.", name));
+    }
+    if raw.chars().any(char::is_control) {
+        return Err(format!("{} contains control characters.", name));
+    }
+    Ok(raw.to_string())
+}
+
+/// 검증된 구독자 속성
+/// `AttributeSchema`로만 만들 수 있다.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+#[serde(transparent)]
+pub struct SubscriberAttributes(Map<String, Value>);
+
+impl SubscriberAttributes {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.0.clone())
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+/// 정규화된 구독자 이메일 주소
+///
+/// 앞뒤 공백을 제거하고 도메인은 소문자 ASCII(퓨니코드)로 바꾼다.
+/// 로컬 파트의 대소문자는 유지한다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriberEmail {
+    address: String,
+    /// 중복 검사에 사용하는 주소
+    canonical: String,
+}
+
+impl SubscriberEmail {
+    pub fn parse(email: &str) -> Result<Self, String> {
+        let email = email.trim();
+        let invalid = || format!("{} is not a valid email address.", email);
+        let (local, domain) = email.rsplit_once('@').ok_or_else(invalid)?;
+        if local.is_empty()
+            || local.len() > 64
+            || local.contains('@')
+            || local.chars().any(|c| c.is_whitespace() || c.is_control())
+        {
+            return Err(invalid());
+        }
+        let domain = idna::domain_to_ascii(domain).map_err(|_| invalid())?;
+        if !domain.contains('.') || domain.split('.').any(str::is_empty) {
+            return Err(invalid());
+        }
+        let address = format!("{}@{}", local, domain);
+        if address.len() > 254 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            canonical: address.to_lowercase(),
+            address,
+        })
+    }
+
+    /// 제공자별 규칙을 적용해서 중복 검사에 사용할 주소를 정한다.
+    /// 규칙이 없으면 주소 전체를 소문자로 바꾼 값을 사용한다.
+    pub fn canonicalize(mut self, rules: &EmailRules) -> Self {
+        let canonical = self.address.to_lowercase();
+        let (local, domain) = canonical
+            .rsplit_once('@')
+            .expect("A parsed email address has a domain.");
+        let Some(rule) = rules.find(domain) else {
+            self.canonical = canonical;
+            return self;
+        };
+        let mut canonical_local = local;
+        if rule.strip_plus_tags {
+            canonical_local = canonical_local.split('+').next().unwrap_or_default();
+        }
+        let mut canonical_local = canonical_local.to_string();
+        if rule.ignore_dots {
+            canonical_local.retain(|c| c != '.');
+        }
+        // `+tag@gmail.com`처럼 남는 것이 없으면 규칙을 적용하지 않는다.
+        if canonical_local.is_empty() {
+            canonical_local = local.to_string();
+        }
+        let canonical_domain = rule
+            .canonical_domain
+            .as_deref()
+            .map_or(domain.to_string(), str::to_lowercase);
+        self.canonical = format!("{}@{}", canonical_local, canonical_domain);
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.address
+    }
+
+    pub fn canonical(&self) -> &str {
+        &self.canonical
+    }
+}
+
+impl AsRef<str> for SubscriberEmail {
+    fn as_ref(&self) -> &str {
+        &self.address
+    }
+}
+
+impl Display for SubscriberEmail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.address)
+    }
+}
+
+/// 한 제공자에 대한 정규화 규칙
+///
+/// 예를 들어 Gmail은 로컬 파트의 `+` 뒤와 `.`을 무시하고
+/// `googlemail.com`을 `gmail.com`과 같은 도메인으로 취급한다.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct EmailRule {
+    /// 규칙을 적용할 도메인
+    pub domains: Vec<String>,
+    /// 중복 검사에서 `domains` 대신 사용할 도메인
+    #[serde(default)]
+    pub canonical_domain: Option<String>,
+    /// 로컬 파트에서 `+` 뒤를 무시한다.
+    #[serde(default)]
+    pub strip_plus_tags: bool,
+    /// 로컬 파트의 `.`을 무시한다.
+    #[serde(default)]
+    pub ignore_dots: bool,
+}
+
+/// 중복 검사에 사용할 제공자별 정규화 규칙의 목록
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct EmailRules(pub Vec<EmailRule>);
+
+impl EmailRules {
+    fn find(&self, domain: &str) -> Option<&EmailRule> {
+        self.0.iter().find(|rule| {
+            rule.domains
+                .iter()
+                .any(|rule_domain| rule_domain.eq_ignore_ascii_case(domain))
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::Display;
+
+/// 검증된 구독자 이름
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriberName(String);
+
+impl SubscriberName {
+    /// 앞뒤 공백을 제거한다.
+    /// 비어 있거나 256자보다 길거나 제어 문자를 포함하면 거부한다.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("The name is empty.".into());
+        }
+        if name.chars().count() > 256 {
+            return Err("The name is longer than 256 characters.".into());
+        }
+        if name.chars().any(char::is_control) {
+            return Err("The name contains control characters.".into());
+        }
+        Ok(Self(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SubscriberName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for SubscriberName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+/// 검증된 구독자 태그
+/// 대소문자를 구분하지 않도록 소문자로 저장한다.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(transparent)]
+pub struct SubscriberTag(String);
+
+impl SubscriberTag {
+    /// 앞뒤 공백을 제거하고 소문자로 바꾼다.
+    /// 64자 이하의 영문자, 숫자, `-`, `_`만 허용한다.
+    pub fn parse(tag: &str) -> Result<Self, String> {
+        let tag = tag.trim().to_lowercase();
+        let valid = !tag.is_empty()
+            && tag.len() <= 64
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("{} is not a valid tag.", tag));
+        }
+        Ok(Self(tag))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SubscriberTag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for SubscriberTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    database::basic::{ImportCounts, NewSubscriber},
+    domain::{EmailRules, SubscriberEmail, SubscriberName},
+};
+
+/// CSV 한 행의 원래 값
+#[derive(serde::Deserialize)]
+struct ImportRecord {
+    email: String,
+    name: String,
+    #[serde(default)]
+    subscribed_at: Option<String>,
+    #[serde(default)]
+    list: Option<String>,
+}
+
+/// 가져올 수 없는 CSV 파일
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("The CSV header must contain the `{0}` column.")]
+    MissingColumn(&'static str),
+    #[error("Failed to read the CSV header.")]
+    Header(#[source] csv::Error),
+}
+
+/// 가져오지 못한 행
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportRowError {
+    /// 헤더를 포함한 CSV 파일의 줄 번호
+    pub line: u64,
+    pub email: Option<String>,
+    pub error: String,
+}
+
+/// 검증을 통과한 구독자와 통과하지 못한 행
+pub struct ParsedImport {
+    pub subscribers: Vec<NewSubscriber>,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// 구독자 CSV를 읽고 각 행을 검증한다.
+///
+/// 헤더에는 `email`, `name` 열이 있어야 하고 `subscribed_at`(RFC 3339), `list` 열은 선택이다.
+/// 잘못된 행은 건너뛰고 오류로 기록한다.
+pub fn parse_import(
+    reader: impl std::io::Read,
+    rules: &EmailRules,
+) -> Result<ParsedImport, ImportError> {
+    let mut subscribers = Vec::new();
+    let mut errors = Vec::new();
+    for row in read_import(reader, rules)? {
+        match row {
+            Ok(subscriber) => subscribers.push(subscriber),
+            Err(e) => errors.push(e),
+        }
+    }
+    Ok(ParsedImport {
+        subscribers,
+        errors,
+    })
+}
+
+/// 구독자 CSV의 헤더를 확인하고 나머지 행을 읽는 대로 검증하는 반복자를 반환한다.
+///
+/// 파일 전체를 메모리에 올리지 않고 가져올 때 사용한다.
+pub fn read_import<R: std::io::Read>(
+    reader: R,
+    rules: &EmailRules,
+) -> Result<ImportRows<'_, R>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader.headers().map_err(ImportError::Header)?.clone();
+    for column in ["email", "name"] {
+        if !headers.iter().any(|header| header == column) {
+            return Err(ImportError::MissingColumn(column));
+        }
+    }
+    Ok(ImportRows {
+        records: reader.into_records(),
+        headers,
+        rules,
+        now: Utc::now(),
+        seen: HashMap::new(),
+    })
+}
+
+/// 구독자 CSV의 행을 하나씩 검증하는 반복자
+pub struct ImportRows<'a, R> {
+    records: csv::StringRecordsIntoIter<R>,
+    headers: csv::StringRecord,
+    rules: &'a EmailRules,
+    now: DateTime<Utc>,
+    /// 정규화된 주소와 그 주소가 처음 나온 줄 번호
+    seen: HashMap<String, u64>,
+}
+
+impl<R: std::io::Read> Iterator for ImportRows<'_, R> {
+    type Item = Result<NewSubscriber, ImportRowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => {
+                return Some(Err(ImportRowError {
+                    line: e.position().map_or(0, |position| position.line()),
+                    email: None,
+                    error: e.to_string(),
+                }));
+            }
+        };
+        Some(self.validate(record))
+    }
+}
+
+impl<R> ImportRows<'_, R> {
+    fn validate(&mut self, record: csv::StringRecord) -> Result<NewSubscriber, ImportRowError> {
+        let line = record.position().map_or(0, |position| position.line());
+        let record: ImportRecord =
+            record
+                .deserialize(Some(&self.headers))
+                .map_err(|e| ImportRowError {
+                    line,
+                    email: None,
+                    error: e.to_string(),
+                })?;
+        let reject = |error: String| ImportRowError {
+            line,
+            email: Some(record.email.clone()),
+            error,
+        };
+        let email = SubscriberEmail::parse(&record.email)
+            .map_err(reject)?
+            .canonicalize(self.rules);
+        let name = SubscriberName::parse(&record.name).map_err(reject)?;
+        let subscribed_at = match record.subscribed_at.as_deref().filter(|s| !s.is_empty()) {
+            None => self.now,
+            Some(subscribed_at) => DateTime::parse_from_rfc3339(subscribed_at)
+                .map_err(|_| reject(format!("{} is not an RFC 3339 timestamp.", subscribed_at)))?
+                .with_timezone(&Utc),
+        };
+        // 구독 목록은 아직 없으므로 빠뜨리지 않도록 거부한다.
+        if record.list.as_deref().is_some_and(|list| !list.is_empty()) {
+            return Err(reject("Lists are not supported yet.".into()));
+        }
+        if let Some(first) = self.seen.get(email.canonical()) {
+            return Err(reject(format!("Duplicate of line {}.", first)));
+        }
+        self.seen.insert(email.canonical().to_string(), line);
+        Ok(NewSubscriber {
+            id: Uuid::new_v4(),
+            email,
+            name,
+            subscribed_at,
+        })
+    }
+}
+
+/// 가져오기 결과 보고서
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportReport {
+    pub inserted: u64,
+    pub updated: u64,
+    /// 이미 같은 이름으로 구독 중이거나 다른 제약 조건에 걸려서 건너뛴 행의 수
+    pub skipped: u64,
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ImportReport {
+    /// `staged`는 검증을 통과해서 저장소로 보낸 행의 수다.
+    pub fn new(staged: usize, errors: Vec<ImportRowError>, counts: ImportCounts) -> Self {
+        Self {
+            inserted: counts.inserted,
+            updated: counts.updated,
+            skipped: staged as u64 - counts.inserted - counts.updated,
+            errors,
+        }
+    }
+
+    /// 감사 로그에 기록할 요약
+    pub fn summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "inserted": self.inserted,
+            "updated": self.updated,
+            "skipped": self.skipped,
+            "errors": self.errors.len(),
+        })
+    }
+}
@@ -1,5 +1,12 @@
+pub mod audit;
+pub mod authentication;
+pub mod cli;
 pub mod configuration;
 pub mod database;
+pub mod domain;
+pub mod import;
+pub mod maintenance;
 pub mod routes;
+pub mod segment;
 pub mod startup;
 pub mod telemetry;
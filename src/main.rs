@@ -1,17 +1,45 @@
+use std::path::Path;
+
 use anyhow::Context;
+use clap::Parser;
 use zero2prod::{
+    cli::Cli,
     configuration::Settings,
+    database::migration::prepare_schema,
+    maintenance::run_retention_periodically,
     startup::new_server,
     telemetry::{get_tracing_subscriber, init_tracing_subscriber},
 };
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+    match cli.command {
+        None => serve(cli.config_dir.as_deref()).await,
+        Some(command) => {
+            // 관리용 명령의 출력과 섞이지 않도록 로그는 stderr로 출력한다.
+            let tracing_subscriber =
+                get_tracing_subscriber("zero2prod".into(), "warn".into(), std::io::stderr);
+            init_tracing_subscriber(tracing_subscriber);
+            let configuration = Settings::get_configuration_from(cli.config_dir.as_deref())
+                .context("Failed to read configuration.")?;
+            let pool = configuration
+                .database
+                .connect()
+                .await
+                .context("Failed to connect to the database.")?;
+            command.run(&pool, &configuration).await
+        }
+    }
+}
+
+async fn serve(config_dir: Option<&Path>) -> Result<(), anyhow::Error> {
     let tracing_subscriber =
         get_tracing_subscriber("zero2prod".into(), "info".into(), std::io::stdout);
     init_tracing_subscriber(tracing_subscriber);
-    // 구성을 읽을 수 없으면 패닉에 빠진다.
-    let configuration = Settings::get_configuration().expect("Failed to read configuration.");
+    // 구성에 문제가 있으면 모두 보고하고 구동하지 않는다.
+    let configuration =
+        Settings::get_configuration_from(config_dir).context("Failed to read configuration.")?;
     let listener = configuration
         .application
         .get_listener()
@@ -21,7 +49,21 @@ async fn main() -> Result<(), anyhow::Error> {
         .database
         .connect()
         .await
-        .context("Failed to connect to Postgres.")?;
-    let server = new_server(listener, pool).context("Failed to make new server.")?;
+        .context("Failed to connect to the database.")?;
+    // 스키마가 바이너리와 맞지 않으면 요청을 처리하지 않는다.
+    prepare_schema(&pool, configuration.database.run_migrations_on_startup).await?;
+    if configuration.retention.enabled {
+        tokio::spawn(run_retention_periodically(
+            pool.clone(),
+            configuration.retention.clone(),
+        ));
+    }
+    let server = new_server(
+        listener,
+        pool,
+        configuration.application.email_rules,
+        configuration.retention,
+    )
+    .context("Failed to make new server.")?;
     server.await.context("Failed to run server.")
 }
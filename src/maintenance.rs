@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    configuration::{RetentionAction, RetentionSettings},
+    database::basic::{Zero2ProdDatabase, Zero2ProdTransaction},
+};
+
+/// 정리 작업의 잠금 키
+/// 여러 인스턴스가 같은 DB를 사용하면 하나만 작업을 실행한다.
+pub const RETENTION_LOCK_KEY: i64 = 0x7a32_7072_6f64_0001;
+
+/// 정리 작업의 결과
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct RetentionReport {
+    pub deleted: u64,
+    pub anonymised: u64,
+    /// 유예 기간이 지나 영구히 삭제한 구독자의 수
+    pub purged: u64,
+}
+
+/// 확인하지 않은 구독자와 유예 기간이 지난 삭제된 구독자를 한 번 정리한다.
+///
+/// 다른 인스턴스가 작업 중이면 아무것도 하지 않고 `None`을 반환한다.
+/// 정리한 구독자가 있으면 감사 로그에 기록한다.
+/// 정리한 구독자의 수는 span의 `deleted`, `anonymised`, `purged` 필드에 기록한다.
+#[tracing::instrument(
+    name = "Running the retention task",
+    skip_all,
+    fields(
+        deleted = tracing::field::Empty,
+        anonymised = tracing::field::Empty,
+        purged = tracing::field::Empty,
+    )
+)]
+pub async fn run_retention<D: Zero2ProdDatabase>(
+    pool: &D,
+    settings: &RetentionSettings,
+    now: DateTime<Utc>,
+) -> Result<Option<RetentionReport>, anyhow::Error> {
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    if !transaction
+        .try_advisory_lock(RETENTION_LOCK_KEY)
+        .await
+        .context("Failed to acquire the retention lock.")?
+    {
+        tracing::info!("Another instance is running the retention task.");
+        return Ok(None);
+    }
+
+    let subscribed_before =
+        now - chrono::Duration::days(settings.unconfirmed_subscriber_days.into());
+    let mut report = RetentionReport::default();
+    match settings.action {
+        RetentionAction::Delete => {
+            report.deleted = transaction
+                .delete_unconfirmed_subscribers(subscribed_before, now)
+                .await
+                .context("Failed to delete unconfirmed subscribers.")?;
+        }
+        RetentionAction::Anonymise => {
+            report.anonymised = transaction
+                .anonymise_unconfirmed_subscribers(subscribed_before)
+                .await
+                .context("Failed to anonymise unconfirmed subscribers.")?;
+        }
+    }
+    let deleted_before = settings.restorable_after(now);
+    report.purged = transaction
+        .purge_deleted_subscribers(deleted_before)
+        .await
+        .context("Failed to purge deleted subscribers.")?;
+    if report != RetentionReport::default() {
+        let entry = AuditContext::system("retention").entry(
+            AuditAction::SubscriberPurge,
+            "subscriptions",
+            serde_json::json!({
+                "subscribed_before": subscribed_before,
+                "deleted": report.deleted,
+                "anonymised": report.anonymised,
+                "deleted_before": deleted_before,
+                "purged": report.purged,
+            }),
+        );
+        transaction
+            .insert_audit_entry(&entry)
+            .await
+            .context("Failed to record the audit entry.")?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    let span = tracing::Span::current();
+    span.record("deleted", report.deleted);
+    span.record("anonymised", report.anonymised);
+    span.record("purged", report.purged);
+    // `monotonic_counter.` 접두사는 `tracing-opentelemetry`의 `MetricsLayer`가 카운터로 집계하는 필드이다.
+    // 메트릭을 내보내는 계층은 아직 없으며, 추가하면 이 이벤트를 그대로 사용한다.
+    tracing::info!(
+        monotonic_counter.retention_deleted_subscribers = report.deleted,
+        monotonic_counter.retention_anonymised_subscribers = report.anonymised,
+        monotonic_counter.retention_purged_subscribers = report.purged,
+        "The retention task has finished."
+    );
+    Ok(Some(report))
+}
+
+/// 서버가 종료될 때까지 `interval_seconds`마다 정리 작업을 실행한다.
+/// 실패는 기록만 하고 다음 주기에 다시 시도한다.
+pub async fn run_retention_periodically<D: Zero2ProdDatabase>(
+    pool: D,
+    settings: RetentionSettings,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(settings.interval_seconds));
+    // 오래 멈춰 있었어도 밀린 실행을 한꺼번에 하지 않는다.
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if let Err(e) = run_retention(&pool, &settings, Utc::now()).await {
+            tracing::error!("The retention task failed: {:?}", e);
+        }
+    }
+}
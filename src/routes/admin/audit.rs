@@ -0,0 +1,46 @@
+use actix_web::{web, HttpResponse};
+
+use crate::{
+    audit::{AuditEntry, AuditFilter},
+    authentication::AdminUser,
+    database::basic::Zero2ProdDatabase,
+};
+
+use super::PageQuery;
+
+#[derive(serde::Serialize)]
+struct AuditPage {
+    entries: Vec<AuditEntry>,
+    /// 다음 페이지를 가져올 때 `before`로 전달할 값
+    /// 마지막 페이지이면 `null`이다.
+    next_cursor: Option<i64>,
+}
+
+// `GET /admin/audit?actor=...&action=...&since=...&before=...&limit=...`
+#[tracing::instrument(name = "Fetching audit log", skip_all, fields(username = %admin.username))]
+pub async fn audit_log<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    filter: web::Query<AuditFilter>,
+    page: web::Query<PageQuery>,
+    pool: web::Data<D>,
+) -> HttpResponse {
+    let limit = page.limit();
+    match pool.fetch_audit_entries(&filter, limit).await {
+        Err(e) => {
+            tracing::error!("Failed to fetch audit entries: {:?}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+        Ok(entries) => {
+            // 한 페이지를 가득 채웠다면 다음 페이지가 있을 수 있다.
+            let next_cursor = if entries.len() as i64 == limit {
+                entries.last().map(|entry| entry.id)
+            } else {
+                None
+            };
+            HttpResponse::Ok().json(AuditPage {
+                entries,
+                next_cursor,
+            })
+        }
+    }
+}
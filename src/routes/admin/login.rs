@@ -0,0 +1,156 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use tracing_actix_web::RequestId;
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    authentication::{
+        basic_authentication, bearer_token, begin_login_attempt, check_recovery_code,
+        check_totp_code, finish_successful_login, generate_session_token, hash_token,
+        validate_credentials, AuthError, AuthenticatedUser, LoginSource, UserRole,
+        PENDING_SESSION_LIFETIME, SESSION_LIFETIME,
+    },
+    database::basic::{Zero2ProdDatabase, Zero2ProdTransaction},
+};
+
+#[derive(serde::Serialize)]
+struct LoginResponse {
+    /// 이후 요청에 `Authorization: Bearer <token>`으로 전달한다.
+    token: String,
+    expires_at: DateTime<Utc>,
+    /// 참이면 `POST /admin/login/two-factor`로 2단계 인증을 마쳐야 토큰을 사용할 수 있다.
+    second_factor_required: bool,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SecondFactorData {
+    /// TOTP 코드 또는 복구 코드
+    pub code: String,
+}
+
+// `POST /admin/login`
+// Basic 인증으로 자격 증명을 확인하고 세션 토큰을 발급한다.
+#[tracing::instrument(name = "Admin login", skip_all)]
+pub async fn login<D: Zero2ProdDatabase>(
+    request: HttpRequest,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let credentials =
+        basic_authentication(request.headers()).map_err(AuthError::InvalidCredentials)?;
+    let user = validate_credentials(
+        credentials,
+        &LoginSource::from_request(&request),
+        pool.get_ref(),
+    )
+    .await?;
+
+    let token = generate_session_token();
+    let now = Utc::now();
+    let second_factor_required = user.two_factor_enabled;
+    let expires_at = if second_factor_required {
+        now + PENDING_SESSION_LIFETIME
+    } else {
+        now + SESSION_LIFETIME
+    };
+    pool.insert_session(
+        &hash_token(&token),
+        user.user_id,
+        now,
+        expires_at,
+        second_factor_required,
+    )
+    .await
+    .context("Failed to store the session.")?;
+
+    Ok(HttpResponse::Ok().json(LoginResponse {
+        token: token.expose_secret().clone(),
+        expires_at,
+        second_factor_required,
+    }))
+}
+
+// `POST /admin/login/two-factor`
+// 2단계 인증을 기다리는 세션 토큰과 TOTP 코드 또는 복구 코드를 받는다.
+#[tracing::instrument(name = "Admin login second factor", skip_all)]
+pub async fn login_two_factor<D: Zero2ProdDatabase>(
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<SecondFactorData>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let token = bearer_token(request.headers()).map_err(AuthError::InvalidCredentials)?;
+    let token_hash = hash_token(&token);
+    let session = pool
+        .fetch_session_user(&token_hash, Utc::now())
+        .await
+        .context("Failed to fetch the session.")?
+        .filter(|session| session.second_factor_pending)
+        .ok_or_else(|| anyhow::anyhow!("No session is waiting for a second factor."))
+        .map_err(AuthError::InvalidCredentials)?;
+    let user = AuthenticatedUser {
+        user_id: session.user_id,
+        username: session.username,
+        role: UserRole::parse(&session.role)?,
+        two_factor_enabled: session.totp_enabled,
+    };
+
+    let source = LoginSource::from_request(&request);
+    begin_login_attempt(pool.get_ref(), &user.username, &source).await?;
+
+    let state = pool
+        .fetch_totp_state(user.user_id)
+        .await
+        .context("Failed to fetch the TOTP state.")?
+        .context("The user does not exist.")?;
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    if !check_totp_code(&transaction, user.user_id, &state, &body.code).await? {
+        if !check_recovery_code(&transaction, user.user_id, &body.code).await? {
+            return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+                "Invalid second factor."
+            )));
+        }
+        // 남은 복구 코드가 줄어들었음을 기록한다.
+        let entry = AuditContext::http(&user, &request_id, &request).entry(
+            AuditAction::RecoveryCodeUse,
+            &user.username,
+            serde_json::json!({}),
+        );
+        transaction
+            .insert_audit_entry(&entry)
+            .await
+            .context("Failed to record the audit entry.")?;
+    }
+
+    let expires_at = Utc::now() + SESSION_LIFETIME;
+    transaction
+        .complete_second_factor(&token_hash, expires_at)
+        .await
+        .context("Failed to update the session.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    finish_successful_login(pool.get_ref(), &user.username, &source).await?;
+    Ok(HttpResponse::Ok().json(LoginResponse {
+        token: token.expose_secret().clone(),
+        expires_at,
+        second_factor_required: false,
+    }))
+}
+
+// `POST /admin/logout`
+pub async fn logout<D: Zero2ProdDatabase>(
+    request: HttpRequest,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let token = bearer_token(request.headers()).map_err(AuthError::InvalidCredentials)?;
+    pool.delete_session(&hash_token(&token))
+        .await
+        .context("Failed to delete the session.")?;
+    Ok(HttpResponse::NoContent().finish())
+}
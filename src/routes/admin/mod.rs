@@ -0,0 +1,32 @@
+mod audit;
+mod login;
+mod segments;
+mod settings;
+mod subscribers;
+mod two_factor;
+
+pub use audit::*;
+pub use login::*;
+pub use segments::*;
+pub use settings::*;
+pub use subscribers::*;
+pub use two_factor::*;
+
+/// 한 페이지의 기본 항목 수
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// 한 페이지의 최대 항목 수
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(serde::Deserialize)]
+pub struct PageQuery {
+    limit: Option<i64>,
+}
+
+impl PageQuery {
+    /// 허용 범위로 제한한 페이지 크기
+    fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
@@ -0,0 +1,167 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use chrono::Utc;
+use tracing_actix_web::RequestId;
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    authentication::{AdminUser, AuthError},
+    database::basic::{Segment, Zero2ProdDatabase, Zero2ProdTransaction},
+    segment::{validate_segment_name, SegmentExpression},
+};
+
+#[derive(serde::Serialize)]
+struct SegmentList {
+    segments: Vec<Segment>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SegmentBody {
+    /// 세그먼트 식
+    /// 예: `tag = vip and not attributes.country = KR`
+    expression: String,
+}
+
+// `GET /admin/segments`
+pub async fn segments<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let segments = pool
+        .fetch_segments()
+        .await
+        .context("Failed to fetch segments.")?;
+    Ok(HttpResponse::Ok().json(SegmentList { segments }))
+}
+
+// `PUT /admin/segments/{name}`
+// 같은 이름의 세그먼트가 있으면 식을 교체한다.
+#[tracing::instrument(name = "Saving segment", skip_all, fields(username = %admin.username))]
+pub async fn save_segment<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    name: web::Path<String>,
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<SegmentBody>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    if let Err(e) = validate_segment_name(&name) {
+        return Ok(HttpResponse::BadRequest().body(e));
+    }
+    let expression = body.into_inner().expression;
+    let schema = pool
+        .fetch_attribute_schema()
+        .await
+        .context("Failed to fetch the attribute schema.")?;
+    if let Err(e) = SegmentExpression::parse(&expression, &schema) {
+        return Ok(HttpResponse::BadRequest().body(e));
+    }
+    // 변경과 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    let previous = transaction
+        .fetch_segment(&name)
+        .await
+        .context("Failed to fetch the segment.")?;
+    let updated_at = Utc::now();
+    transaction
+        .save_segment(&name, &expression, updated_at)
+        .await
+        .context("Failed to save the segment.")?;
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SegmentUpdate,
+        &*name,
+        serde_json::json!({
+            "expression": {
+                "from": previous.map(|segment| segment.expression),
+                "to": expression
+            }
+        }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::Ok().json(Segment {
+        name: name.into_inner(),
+        expression,
+        updated_at,
+    }))
+}
+
+// `DELETE /admin/segments/{name}`
+#[tracing::instrument(name = "Deleting segment", skip_all, fields(username = %admin.username))]
+pub async fn delete_segment<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    name: web::Path<String>,
+    request: HttpRequest,
+    request_id: RequestId,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    // 삭제와 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    let Some(segment) = transaction
+        .fetch_segment(&name)
+        .await
+        .context("Failed to fetch the segment.")?
+    else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    transaction
+        .delete_segment(&name)
+        .await
+        .context("Failed to delete the segment.")?;
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SegmentDelete,
+        &*name,
+        serde_json::json!({ "before": segment }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// `GET /admin/segments/{name}/preview`
+// 세그먼트에 속한 구독자의 수를 센다. 확인된 구독자만 발송 대상이 된다.
+pub async fn preview_segment<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    name: web::Path<String>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let Some(segment) = pool
+        .fetch_segment(&name)
+        .await
+        .context("Failed to fetch the segment.")?
+    else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let schema = pool
+        .fetch_attribute_schema()
+        .await
+        .context("Failed to fetch the attribute schema.")?;
+    // 저장한 뒤에 식이 참조하는 속성의 정의가 바뀌었을 수 있다.
+    let expression = match SegmentExpression::parse(&segment.expression, &schema) {
+        Ok(expression) => expression,
+        Err(e) => return Ok(HttpResponse::Conflict().body(e)),
+    };
+    let size = pool
+        .count_segment(&expression)
+        .await
+        .context("Failed to count the segment.")?;
+    Ok(HttpResponse::Ok().json(size))
+}
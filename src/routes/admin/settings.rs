@@ -0,0 +1,128 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use tracing_actix_web::RequestId;
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    authentication::{AdminUser, AuthError, UserRole},
+    database::basic::{Zero2ProdDatabase, Zero2ProdTransaction},
+    domain::{AttributeDefinition, AttributeSchema},
+};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SecuritySettings {
+    /// 참이면 2단계 인증을 활성화하지 않은 관리자는 등록 엔드포인트만 사용할 수 있다.
+    require_two_factor: bool,
+}
+
+// `GET /admin/settings/security`
+pub async fn security_settings<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let require_two_factor = pool
+        .fetch_require_two_factor()
+        .await
+        .context("Failed to fetch the security settings.")?;
+    Ok(HttpResponse::Ok().json(SecuritySettings { require_two_factor }))
+}
+
+// `PUT /admin/settings/security`
+// owner만 변경할 수 있다.
+#[tracing::instrument(name = "Update security settings", skip_all, fields(username = %admin.username))]
+pub async fn update_security_settings<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<SecuritySettings>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    if admin.role != UserRole::Owner {
+        return Ok(HttpResponse::Forbidden().body("Only owners can change security settings."));
+    }
+    let previous = pool
+        .fetch_require_two_factor()
+        .await
+        .context("Failed to fetch the security settings.")?;
+    pool.set_require_two_factor(body.require_two_factor)
+        .await
+        .context("Failed to update the security settings.")?;
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SecuritySettingsUpdate,
+        "security",
+        serde_json::json!({
+            "require_two_factor": { "from": previous, "to": body.require_two_factor }
+        }),
+    );
+    pool.insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    Ok(HttpResponse::Ok().json(body.into_inner()))
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct AttributeSettings {
+    /// 구독 양식에서 받을 수 있는 속성
+    attributes: Vec<AttributeDefinition>,
+}
+
+// `GET /admin/settings/attributes`
+pub async fn attribute_settings<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let schema = pool
+        .fetch_attribute_schema()
+        .await
+        .context("Failed to fetch the attribute schema.")?;
+    Ok(HttpResponse::Ok().json(AttributeSettings {
+        attributes: schema.definitions(),
+    }))
+}
+
+// `PUT /admin/settings/attributes`
+// 정의를 모두 교체한다.
+#[tracing::instrument(name = "Update attribute settings", skip_all, fields(username = %admin.username))]
+pub async fn update_attribute_settings<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<AttributeSettings>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let schema = match AttributeSchema::parse(body.into_inner().attributes) {
+        Ok(schema) => schema,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+    // 변경과 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    let previous = transaction
+        .fetch_attribute_schema()
+        .await
+        .context("Failed to fetch the attribute schema.")?;
+    transaction
+        .set_attribute_schema(&schema)
+        .await
+        .context("Failed to update the attribute schema.")?;
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::AttributeSettingsUpdate,
+        "attributes",
+        serde_json::json!({
+            "attributes": { "from": previous.definitions(), "to": schema.definitions() }
+        }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::Ok().json(AttributeSettings {
+        attributes: schema.definitions(),
+    }))
+}
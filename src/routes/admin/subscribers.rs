@@ -0,0 +1,440 @@
+use std::collections::BTreeSet;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use chrono::Utc;
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tracing_actix_web::RequestId;
+use uuid::Uuid;
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    authentication::{AdminUser, AuthError},
+    configuration::RetentionSettings,
+    database::{
+        basic::{
+            Subscriber, SubscriberCursor, SubscriberFilter, SubscriberHistoryEntry,
+            Zero2ProdDatabase, Zero2ProdTransaction,
+        },
+        error::StorageError,
+    },
+    domain::{EmailRules, SubscriberTag},
+    import::{read_import, ImportError, ImportReport},
+};
+
+use super::PageQuery;
+
+/// 가져올 CSV 파일의 최대 크기
+/// 본문은 받는 대로 검증해서 저장소로 보내지만 중복 확인과 오류 보고를 위한 기록은
+/// 행 수에 비례해서 남으므로 크기를 제한한다.
+/// 더 큰 파일은 나눠서 가져오거나 `subscribers import` 명령을 사용한다.
+pub const MAX_IMPORT_SIZE: usize = 64 * 1024 * 1024;
+/// 검증한 구독자를 저장소로 보내는 단위
+const IMPORT_BATCH_SIZE: usize = 1000;
+/// 본문 청크와 검증한 구독자를 넘기는 채널의 크기
+const IMPORT_CHANNEL_CAPACITY: usize = 4;
+
+#[derive(serde::Deserialize)]
+pub struct AttributeQuery {
+    /// 속성 조건(JSON 객체)
+    /// 예: `{"country": "KR", "interests": "rust"}`
+    attributes: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct SubscriberPage {
+    subscribers: Vec<Subscriber>,
+    /// 다음 페이지를 가져올 때 `cursor`로 전달할 값
+    /// 마지막 페이지이면 `null`이다.
+    next_cursor: Option<SubscriberCursor>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SubscriberTags {
+    tags: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct SubscriberHistory {
+    entries: Vec<SubscriberHistoryEntry>,
+}
+
+// `GET /admin/subscribers?status=...&email=...&since=...&until=...&attributes=...&cursor=...&limit=...`
+#[tracing::instrument(name = "Fetching subscribers", skip_all, fields(username = %admin.username))]
+pub async fn subscribers<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    filter: web::Query<SubscriberFilter>,
+    attributes: web::Query<AttributeQuery>,
+    page: web::Query<PageQuery>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let mut filter = filter.into_inner();
+    if let Some(attributes) = &attributes.attributes {
+        let schema = pool
+            .fetch_attribute_schema()
+            .await
+            .context("Failed to fetch the attribute schema.")?;
+        match schema.parse_filter(attributes) {
+            Ok(attributes) => filter.attributes = Some(attributes),
+            Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+        }
+    }
+    let limit = page.limit();
+    let subscribers = pool
+        .fetch_subscriber_page(&filter, limit)
+        .await
+        .context("Failed to fetch subscribers.")?;
+    // 한 페이지를 가득 채웠다면 다음 페이지가 있을 수 있다.
+    let next_cursor = if subscribers.len() as i64 == limit {
+        subscribers.last().map(SubscriberCursor::from)
+    } else {
+        None
+    };
+    Ok(HttpResponse::Ok().json(SubscriberPage {
+        subscribers,
+        next_cursor,
+    }))
+}
+
+// `GET /admin/subscribers/{id}`
+pub async fn subscriber<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    id: web::Path<Uuid>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let subscriber = pool
+        .fetch_subscriber(*id)
+        .await
+        .context("Failed to fetch the subscriber.")?;
+    Ok(match subscriber {
+        Some(subscriber) => HttpResponse::Ok().json(subscriber),
+        None => HttpResponse::NotFound().finish(),
+    })
+}
+
+// `DELETE /admin/subscribers/{id}`
+#[tracing::instrument(name = "Deleting subscriber", skip_all, fields(username = %admin.username))]
+pub async fn delete_subscriber<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    id: web::Path<Uuid>,
+    request: HttpRequest,
+    request_id: RequestId,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    // 삭제와 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    let Some(subscriber) = transaction
+        .fetch_subscriber(*id)
+        .await
+        .context("Failed to fetch the subscriber.")?
+    else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let rows_affected = transaction
+        .delete_subscriber(*id, Utc::now())
+        .await
+        .context("Failed to delete the subscriber.")?;
+    // 조회한 뒤에 다른 요청이 먼저 삭제했다면 기록하지 않는다.
+    if rows_affected == 0 {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SubscriberDelete,
+        *id,
+        serde_json::json!({ "before": subscriber }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// `POST /admin/subscribers/{id}/restore`
+#[tracing::instrument(name = "Restoring subscriber", skip_all, fields(username = %admin.username))]
+pub async fn restore_subscriber<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    id: web::Path<Uuid>,
+    request: HttpRequest,
+    request_id: RequestId,
+    pool: web::Data<D>,
+    retention: web::Data<RetentionSettings>,
+) -> Result<HttpResponse, AuthError> {
+    // 복원과 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    let deleted_after = retention.restorable_after(Utc::now());
+    let rows_affected = match transaction.restore_subscriber(*id, deleted_after).await {
+        Ok(rows_affected) => rows_affected,
+        // 삭제한 뒤에 같은 주소로 다시 구독했다.
+        Err(StorageError::Conflict(_)) => {
+            return Ok(HttpResponse::Conflict().finish());
+        }
+        Err(e) => Err(e).context("Failed to restore the subscriber.")?,
+    };
+    // 삭제하지 않았거나 유예 기간이 지난 구독자는 복원할 수 없다.
+    if rows_affected == 0 {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let subscriber = transaction
+        .fetch_subscriber(*id)
+        .await
+        .context("Failed to fetch the subscriber.")?;
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SubscriberRestore,
+        *id,
+        serde_json::json!({ "after": subscriber }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::Ok().json(subscriber))
+}
+
+// `GET /admin/subscribers/{id}/history`
+pub async fn subscriber_history<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    id: web::Path<Uuid>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let entries = pool
+        .fetch_subscriber_history(*id)
+        .await
+        .context("Failed to fetch the subscriber history.")?;
+    // 이력이 없으면 그런 구독자가 없었거나 영구히 삭제되었다.
+    if entries.is_empty() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    Ok(HttpResponse::Ok().json(SubscriberHistory { entries }))
+}
+
+// `GET /admin/subscribers/{id}/tags`
+pub async fn subscriber_tags<D: Zero2ProdDatabase>(
+    _admin: AdminUser<D>,
+    id: web::Path<Uuid>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    if pool
+        .fetch_subscriber(*id)
+        .await
+        .context("Failed to fetch the subscriber.")?
+        .is_none()
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let tags = pool
+        .fetch_subscriber_tags(*id)
+        .await
+        .context("Failed to fetch the subscriber tags.")?;
+    Ok(HttpResponse::Ok().json(SubscriberTags { tags }))
+}
+
+// `PUT /admin/subscribers/{id}/tags`
+// 태그를 모두 교체한다. 태그는 소문자로 바꿔서 저장한다.
+#[tracing::instrument(name = "Updating subscriber tags", skip_all, fields(username = %admin.username))]
+pub async fn update_subscriber_tags<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    id: web::Path<Uuid>,
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<SubscriberTags>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let tags = match body
+        .tags
+        .iter()
+        .map(|tag| SubscriberTag::parse(tag))
+        .collect::<Result<BTreeSet<_>, _>>()
+    {
+        Ok(tags) => tags.into_iter().collect::<Vec<_>>(),
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+    // 변경과 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    if transaction
+        .fetch_subscriber(*id)
+        .await
+        .context("Failed to fetch the subscriber.")?
+        .is_none()
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let previous = transaction
+        .fetch_subscriber_tags(*id)
+        .await
+        .context("Failed to fetch the subscriber tags.")?;
+    transaction
+        .set_subscriber_tags(*id, &tags)
+        .await
+        .context("Failed to update the subscriber tags.")?;
+    let tags: Vec<_> = tags.iter().map(|tag| tag.as_str().to_string()).collect();
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SubscriberTagsUpdate,
+        *id,
+        serde_json::json!({ "tags": { "from": previous, "to": tags } }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::Ok().json(SubscriberTags { tags }))
+}
+
+// `POST /admin/subscribers/import` (CSV 바디)
+// 본문을 받는 대로 검증해서 저장소로 보내므로 파일 전체를 메모리에 읽지 않는다.
+#[tracing::instrument(name = "Importing subscribers", skip_all, fields(username = %admin.username))]
+pub async fn import_subscribers<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    mut payload: web::Payload,
+    request: HttpRequest,
+    request_id: RequestId,
+    pool: web::Data<D>,
+    email_rules: web::Data<EmailRules>,
+) -> Result<HttpResponse, AuthError> {
+    let (chunk_sender, chunk_receiver) = mpsc::channel(IMPORT_CHANNEL_CAPACITY);
+    let (batch_sender, mut batch_receiver) = mpsc::channel(IMPORT_CHANNEL_CAPACITY);
+    // CSV 리더는 동기식이므로 블로킹 스레드에서 검증한다.
+    let parser = tokio::task::spawn_blocking(move || {
+        let mut errors = Vec::new();
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        for row in read_import(ChunkReader::new(chunk_receiver), &email_rules)? {
+            match row {
+                Ok(subscriber) => batch.push(subscriber),
+                Err(e) => errors.push(e),
+            }
+            // 저장에 실패해서 받는 쪽이 없으면 더 읽지 않는다.
+            if batch.len() == IMPORT_BATCH_SIZE
+                && batch_sender
+                    .blocking_send(std::mem::take(&mut batch))
+                    .is_err()
+            {
+                return Ok(errors);
+            }
+        }
+        if !batch.is_empty() {
+            let _ = batch_sender.blocking_send(batch);
+        }
+        Ok::<_, ImportError>(errors)
+    });
+
+    // 가져오기와 감사 로그를 함께 적용한다.
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    transaction
+        .begin_import()
+        .await
+        .context("Failed to begin the import.")?;
+    let receive = async move {
+        let mut size = 0;
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|e| HttpResponse::BadRequest().body(e.to_string()))?;
+            size += chunk.len();
+            if size > MAX_IMPORT_SIZE {
+                return Err(HttpResponse::PayloadTooLarge().body(format!(
+                    "The CSV file must not be larger than {} bytes.",
+                    MAX_IMPORT_SIZE
+                )));
+            }
+            // 헤더가 잘못되어 파싱을 멈췄다.
+            if chunk_sender.send(chunk).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    };
+    let stage = {
+        let transaction = &transaction;
+        async move {
+            let mut staged = 0;
+            while let Some(batch) = batch_receiver.recv().await {
+                transaction.stage_import(&batch).await?;
+                staged += batch.len();
+            }
+            Ok::<_, StorageError>(staged)
+        }
+    };
+    // 두 작업은 채널을 옮겨받으므로 한쪽이 먼저 끝나면 채널이 닫혀서 다른 쪽도 멈춘다.
+    let (received, staged) = tokio::join!(receive, stage);
+    if let Err(response) = received {
+        return Ok(response);
+    }
+    let errors = match parser.await.context("Failed to spawn blocking task.")? {
+        Ok(errors) => errors,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e.to_string())),
+    };
+    let staged = staged.context("Failed to import subscribers.")?;
+    let counts = transaction
+        .finish_import()
+        .await
+        .context("Failed to import subscribers.")?;
+    let report = ImportReport::new(staged, errors, counts);
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::SubscriberImport,
+        "subscriptions",
+        report.summary(),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// 채널로 받은 본문 청크를 `std::io::Read`로 읽는다.
+/// 청크를 기다리는 동안 스레드를 막으므로 블로킹 스레드에서만 사용한다.
+struct ChunkReader {
+    receiver: mpsc::Receiver<web::Bytes>,
+    chunk: web::Bytes,
+}
+
+impl ChunkReader {
+    fn new(receiver: mpsc::Receiver<web::Bytes>) -> Self {
+        Self {
+            receiver,
+            chunk: web::Bytes::new(),
+        }
+    }
+}
+
+impl std::io::Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk,
+                // 본문을 모두 받았다.
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
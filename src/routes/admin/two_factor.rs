@@ -0,0 +1,166 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use secrecy::ExposeSecret;
+use tracing_actix_web::RequestId;
+
+use crate::{
+    audit::{AuditAction, AuditContext},
+    authentication::{
+        begin_login_attempt, check_recovery_code, check_totp_code, finish_successful_login,
+        generate_recovery_codes, generate_totp_secret, hash_token, totp, AdminUser, AuthError,
+        Authenticated, AuthenticatedUser, LoginSource,
+    },
+    database::basic::{TotpState, Zero2ProdDatabase, Zero2ProdTransaction},
+};
+
+use super::SecondFactorData;
+
+#[derive(serde::Serialize)]
+struct EnrollResponse {
+    /// base32로 인코딩된 비밀키
+    secret: String,
+    /// 인증 앱에서 QR 코드로 읽을 수 있는 `otpauth://` URI
+    provisioning_uri: String,
+}
+
+#[derive(serde::Serialize)]
+struct RecoveryCodesResponse {
+    /// 일회용 복구 코드
+    /// 다시 확인할 수 없으므로 안전한 곳에 보관해야 한다.
+    recovery_codes: Vec<String>,
+}
+
+async fn fetch_totp_state(
+    pool: &impl Zero2ProdDatabase,
+    user: &AuthenticatedUser,
+) -> Result<TotpState, AuthError> {
+    Ok(pool
+        .fetch_totp_state(user.user_id)
+        .await
+        .context("Failed to fetch the TOTP state.")?
+        .context("The user does not exist.")?)
+}
+
+// `POST /admin/two-factor/enroll`
+// 새 비밀키를 발급한다.
+// `POST /admin/two-factor/confirm`으로 코드를 확인하기 전까지는 활성화되지 않는다.
+#[tracing::instrument(name = "Enroll TOTP", skip_all, fields(username = %user.username))]
+pub async fn enroll_two_factor<D: Zero2ProdDatabase>(
+    user: Authenticated<D>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let secret = generate_totp_secret();
+    let rows_affected = pool
+        .set_pending_totp_secret(user.user_id, secret.expose_secret())
+        .await
+        .context("Failed to store the TOTP secret.")?;
+    if rows_affected == 0 {
+        return Ok(HttpResponse::Conflict().body("Two-factor authentication is already enabled."));
+    }
+    let provisioning_uri = totp(&secret, &user.username)?.get_url();
+    Ok(HttpResponse::Ok().json(EnrollResponse {
+        secret: secret.expose_secret().clone(),
+        provisioning_uri,
+    }))
+}
+
+// `POST /admin/two-factor/confirm`
+// 인증 앱이 생성한 코드를 확인하고 2단계 인증을 활성화한다.
+#[tracing::instrument(name = "Confirm TOTP", skip_all, fields(username = %user.username))]
+pub async fn confirm_two_factor<D: Zero2ProdDatabase>(
+    user: Authenticated<D>,
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<SecondFactorData>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let state = fetch_totp_state(pool.get_ref(), &user).await?;
+    if state.enabled {
+        return Ok(HttpResponse::Conflict().body("Two-factor authentication is already enabled."));
+    }
+    if state.secret.is_none() {
+        return Ok(HttpResponse::Conflict().body("Two-factor enrollment has not been started."));
+    }
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    if !check_totp_code(&transaction, user.user_id, &state, &body.code).await? {
+        return Ok(HttpResponse::BadRequest().body("Invalid code."));
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    let recovery_code_hashes: Vec<String> = recovery_codes.iter().map(hash_token).collect();
+    transaction
+        .enable_totp(user.user_id, &recovery_code_hashes)
+        .await
+        .context("Failed to enable two-factor authentication.")?;
+    let entry = AuditContext::http(&user, &request_id, &request).entry(
+        AuditAction::TwoFactorEnable,
+        &user.username,
+        serde_json::json!({ "totp_enabled": { "from": false, "to": true } }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse {
+        recovery_codes: recovery_codes
+            .iter()
+            .map(|code| code.expose_secret().clone())
+            .collect(),
+    }))
+}
+
+// `POST /admin/two-factor/disable`
+// 현재 TOTP 코드나 복구 코드를 확인한 뒤 2단계 인증을 해제한다.
+#[tracing::instrument(name = "Disable TOTP", skip_all, fields(username = %admin.username))]
+pub async fn disable_two_factor<D: Zero2ProdDatabase>(
+    admin: AdminUser<D>,
+    request: HttpRequest,
+    request_id: RequestId,
+    body: web::Json<SecondFactorData>,
+    pool: web::Data<D>,
+) -> Result<HttpResponse, AuthError> {
+    let state = fetch_totp_state(pool.get_ref(), &admin).await?;
+    if !state.enabled {
+        return Ok(HttpResponse::Conflict().body("Two-factor authentication is not enabled."));
+    }
+    // 탈취한 세션으로 코드를 추측해서 2단계 인증을 해제하지 못하게 한다.
+    let source = LoginSource::from_request(&request);
+    begin_login_attempt(pool.get_ref(), &admin.username, &source).await?;
+    let transaction = pool
+        .begin()
+        .await
+        .context("Failed to begin a transaction.")?;
+    if !check_totp_code(&transaction, admin.user_id, &state, &body.code).await?
+        && !check_recovery_code(&transaction, admin.user_id, &body.code).await?
+    {
+        return Ok(HttpResponse::BadRequest().body("Invalid code."));
+    }
+
+    transaction
+        .disable_totp(admin.user_id)
+        .await
+        .context("Failed to disable two-factor authentication.")?;
+    let entry = AuditContext::http(&admin, &request_id, &request).entry(
+        AuditAction::TwoFactorDisable,
+        &admin.username,
+        serde_json::json!({ "totp_enabled": { "from": true, "to": false } }),
+    );
+    transaction
+        .insert_audit_entry(&entry)
+        .await
+        .context("Failed to record the audit entry.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction.")?;
+    finish_successful_login(pool.get_ref(), &admin.username, &source).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,7 +1,9 @@
+mod admin;
 mod greet;
 mod health_check;
 mod subscriptions;
 
+pub use admin::*;
 pub use greet::*;
 pub use health_check::*;
 pub use subscriptions::*;
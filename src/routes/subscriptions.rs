@@ -1,13 +1,21 @@
+use std::collections::HashMap;
+
 use actix_web::{web, HttpResponse};
 use chrono::Utc;
 use uuid::Uuid;
 
-use crate::{configuration::DefaultDBPool, database::basic::Zero2ProdDatabase};
+use crate::{
+    database::{basic::Zero2ProdDatabase, error::StorageError},
+    domain::{EmailRules, SubscriberEmail, SubscriberName},
+};
 
 #[derive(serde::Deserialize)]
 pub struct FormData {
     email: String,
     name: String,
+    /// 나머지 필드는 관리자가 정의한 속성이다.
+    #[serde(flatten)]
+    attributes: HashMap<String, String>,
 }
 
 #[tracing::instrument(
@@ -20,18 +28,49 @@ pub struct FormData {
 )]
 // 단순하게 시작하자.
 // 항산 200 OK를 반환한다.
-pub async fn subscribe(
+pub async fn subscribe<D: Zero2ProdDatabase>(
     form: web::Form<FormData>,
     // 애플리케이션 상태에서 커넥션을 꺼낸다.
-    pool: web::Data<DefaultDBPool>,
+    pool: web::Data<D>,
+    email_rules: web::Data<EmailRules>,
 ) -> HttpResponse {
+    let email = match SubscriberEmail::parse(&form.email) {
+        Ok(email) => email.canonicalize(&email_rules),
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    let Ok(name) = SubscriberName::parse(&form.name) else {
+        return HttpResponse::BadRequest().finish();
+    };
+    let schema = match pool.fetch_attribute_schema().await {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("Failed to fetch the attribute schema: {:?}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let attributes = match schema.parse_form(&form.attributes) {
+        Ok(attributes) => attributes,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
     // `Result`는 `Ok`와 `Err`라는 두 개의 변형을 갖는다.
     // 첫번째는 성공, 두 번째는 실패를 의미한다.
     //  `match` 구문을 사용해서 결과에 따라 무엇을 수행할지 선택한다.
     match pool
-        .insert_subscriptions(Uuid::new_v4(), &form.email, &form.name, Utc::now())
+        .insert_subscriptions(
+            Uuid::new_v4(),
+            &email,
+            name.as_str(),
+            &attributes,
+            Utc::now(),
+        )
         .await
     {
+        // 이미 구독한 주소이다.
+        // 주소가 등록되어 있는지 알려주지 않도록 새로 구독한 것과 같게 응답한다.
+        Err(StorageError::Conflict(_)) => {
+            tracing::info!("The subscriber already exists.");
+            HttpResponse::Ok().finish()
+        }
         Err(e) => {
             // 이 오류 로그는 `query_span` 밖으로 떨어진다.
             tracing::error!("Failed to execute query: {:?}", e);
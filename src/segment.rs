@@ -0,0 +1,292 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+
+use crate::domain::{AttributeSchema, AttributeType, SubscriberTag};
+
+/// 세그먼트 식의 최대 길이
+const MAX_EXPRESSION_LENGTH: usize = 4096;
+/// 괄호와 `not`을 중첩할 수 있는 최대 깊이
+const MAX_DEPTH: usize = 32;
+/// 식에 사용할 수 있는 구독 상태
+const STATUSES: [&str; 3] = ["pending_confirmation", "confirmed", "anonymised"];
+
+/// 비교 연산자
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "<>",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+/// 해석한 세그먼트 식
+///
+/// 각 저장소가 자신의 SQL 조건으로 변환한다.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegmentExpression {
+    /// 태그가 붙은 구독자
+    Tag(SubscriberTag),
+    Status(String),
+    /// 속성 값이 같은 구독자
+    /// 목록 속성이면 `value`가 목록에 포함된 구독자
+    Attribute {
+        name: String,
+        value: Value,
+        list: bool,
+    },
+    SubscribedAt(Comparison, DateTime<Utc>),
+    Not(Box<SegmentExpression>),
+    And(Box<SegmentExpression>, Box<SegmentExpression>),
+    Or(Box<SegmentExpression>, Box<SegmentExpression>),
+}
+
+/// 세그먼트 이름은 64자 이하의 소문자, 숫자, `-`, `_`로 이루어진다.
+pub fn validate_segment_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("{} is not a valid segment name.", name));
+    }
+    Ok(())
+}
+
+impl SegmentExpression {
+    /// 세그먼트 식을 해석한다.
+    ///
+    /// 조건은 `필드 연산자 값` 형식이며 `and`, `or`, `not`, 괄호로 조합한다.
+    /// `and`가 `or`보다 먼저 결합한다.
+    /// - `tag = vip`, `status != confirmed`
+    /// - `attributes.country = KR` (목록 속성은 값이 포함되어 있는지 확인한다)
+    /// - `subscribed_at >= 2024-01-01` (RFC 3339 시각 또는 UTC 날짜)
+    ///
+    /// 공백이나 연산자를 포함한 값은 큰따옴표로 감싼다.
+    pub fn parse(expression: &str, schema: &AttributeSchema) -> Result<Self, String> {
+        if expression.len() > MAX_EXPRESSION_LENGTH {
+            return Err(format!(
+                "The expression is longer than {} bytes.",
+                MAX_EXPRESSION_LENGTH
+            ));
+        }
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+            schema,
+        };
+        let parsed = parser.or(0)?;
+        match parser.next() {
+            None => Ok(parsed),
+            Some(token) => Err(format!("Unexpected {}.", token)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Operator(Comparison),
+    Word(String),
+    Quoted(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::Operator(comparison) => write!(f, "`{}`", comparison.as_sql()),
+            Token::Word(word) => write!(f, "`{}`", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Operator(Comparison::Eq),
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Operator(Comparison::Ne),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Operator(Comparison::Le),
+            '<' => Token::Operator(Comparison::Lt),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Operator(Comparison::Ge),
+            '>' => Token::Operator(Comparison::Gt),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => text.push(escaped),
+                            _ => return Err("Only `\\\"` and `\\\\` can be escaped.".into()),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err("A quoted value is not closed.".into()),
+                    }
+                }
+                Token::Quoted(text)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| is_word_char(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            c => return Err(format!("Unexpected character `{}`.", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '+' | '@')
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    schema: &'a AttributeSchema,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// 다음 토큰이 키워드이면 소비한다.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self, depth: usize) -> Result<SegmentExpression, String> {
+        let mut expression = self.and(depth)?;
+        while self.keyword("or") {
+            let right = self.and(depth)?;
+            expression = SegmentExpression::Or(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<SegmentExpression, String> {
+        let mut expression = self.unary(depth)?;
+        while self.keyword("and") {
+            let right = self.unary(depth)?;
+            expression = SegmentExpression::And(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<SegmentExpression, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "The expression is nested more than {} times.",
+                MAX_DEPTH
+            ));
+        }
+        if self.keyword("not") {
+            return Ok(SegmentExpression::Not(Box::new(self.unary(depth + 1)?)));
+        }
+        if self.tokens.get(self.position) == Some(&Token::Open) {
+            self.position += 1;
+            let expression = self.or(depth + 1)?;
+            return match self.next() {
+                Some(Token::Close) => Ok(expression),
+                Some(token) => Err(format!("Expected `)` but found {}.", token)),
+                None => Err("A parenthesis is not closed.".into()),
+            };
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<SegmentExpression, String> {
+        let field = match self.next() {
+            Some(Token::Word(field)) => field,
+            Some(token) => return Err(format!("Expected a field but found {}.", token)),
+            None => return Err("The expression ended unexpectedly.".into()),
+        };
+        let comparison = match self.next() {
+            Some(Token::Operator(comparison)) => comparison,
+            Some(token) => return Err(format!("Expected an operator but found {}.", token)),
+            None => return Err(format!("{} is missing an operator.", field)),
+        };
+        let value = match self.next() {
+            Some(Token::Word(value) | Token::Quoted(value)) => value,
+            Some(token) => return Err(format!("Expected a value but found {}.", token)),
+            None => return Err(format!("{} is missing a value.", field)),
+        };
+
+        let condition = match field.as_str() {
+            "subscribed_at" => {
+                return Ok(SegmentExpression::SubscribedAt(
+                    comparison,
+                    parse_timestamp(&value)?,
+                ))
+            }
+            "tag" => SegmentExpression::Tag(SubscriberTag::parse(&value)?),
+            "status" => {
+                if !STATUSES.contains(&value.as_str()) {
+                    return Err(format!("{} is not a valid status.", value));
+                }
+                SegmentExpression::Status(value)
+            }
+            "list" => return Err("Lists are not supported yet.".into()),
+            field => match field.strip_prefix("attributes.") {
+                Some(name) => {
+                    let (r#type, value) = self.schema.parse_condition(name, &value)?;
+                    SegmentExpression::Attribute {
+                        name: name.to_string(),
+                        value,
+                        list: r#type == AttributeType::List,
+                    }
+                }
+                None => return Err(format!("{} is not a known field.", field)),
+            },
+        };
+        match comparison {
+            Comparison::Eq => Ok(condition),
+            Comparison::Ne => Ok(SegmentExpression::Not(Box::new(condition))),
+            _ => Err(format!("{} can only be compared with `=` or `!=`.", field)),
+        }
+    }
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("{} is not a valid timestamp.", value))
+}
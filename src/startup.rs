@@ -2,19 +2,32 @@ use actix_web::{dev::Server, web, App, HttpServer};
 use tracing_actix_web::TracingLogger;
 
 use crate::{
-    configuration::DefaultDBPool,
-    routes::{greet, health_check, subscribe},
+    configuration::RetentionSettings,
+    database::basic::Zero2ProdDatabase,
+    domain::EmailRules,
+    routes::{
+        attribute_settings, audit_log, confirm_two_factor, delete_segment, delete_subscriber,
+        disable_two_factor, enroll_two_factor, greet, health_check, import_subscribers, login,
+        login_two_factor, logout, preview_segment, restore_subscriber, save_segment,
+        security_settings, segments, subscribe, subscriber, subscriber_history, subscriber_tags,
+        subscribers, update_attribute_settings, update_security_settings, update_subscriber_tags,
+    },
 };
 
 // `run`을 `public`으로 마크해야 한다.
 // 번쩍번쩍 아름다운 새로운 서버
-pub fn new_server(
+// 핸들러는 `Zero2ProdDatabase`를 구현한 어떤 저장소든 사용할 수 있다.
+pub fn new_server<D: Zero2ProdDatabase>(
     listener: tokio::net::TcpListener,
-    pool: DefaultDBPool,
+    pool: D,
+    email_rules: EmailRules,
+    retention: RetentionSettings,
 ) -> Result<Server, std::io::Error> {
     // web::Data로 pool을 감싼다.
     // Arc 스마트 포인터로 요약된다.
     let pool = web::Data::new(pool);
+    let email_rules = web::Data::new(email_rules);
+    let retention = web::Data::new(retention);
     // 주변 환경으로부터 `connection`을 잡아낸다.
     let server = HttpServer::new(move || {
         App::new()
@@ -24,10 +37,76 @@ pub fn new_server(
             .route("/", web::get().to(greet))
             .route("/health_check", web::get().to(health_check))
             // POST /subcriptions 요청에 대한 라우팅 테이블의 새 엔트리 포인트
-            .route("/subscriptions", web::post().to(subscribe))
+            .route("/subscriptions", web::post().to(subscribe::<D>))
+            // 관리용 엔드포인트는 로그인을 제외하고 모두 인증이 필요하다.
+            .service(
+                web::scope("/admin")
+                    .route("/login", web::post().to(login::<D>))
+                    .route("/login/two-factor", web::post().to(login_two_factor::<D>))
+                    .route("/logout", web::post().to(logout::<D>))
+                    .route("/two-factor/enroll", web::post().to(enroll_two_factor::<D>))
+                    .route(
+                        "/two-factor/confirm",
+                        web::post().to(confirm_two_factor::<D>),
+                    )
+                    .route(
+                        "/two-factor/disable",
+                        web::post().to(disable_two_factor::<D>),
+                    )
+                    .route("/settings/security", web::get().to(security_settings::<D>))
+                    .route(
+                        "/settings/security",
+                        web::put().to(update_security_settings::<D>),
+                    )
+                    .route(
+                        "/settings/attributes",
+                        web::get().to(attribute_settings::<D>),
+                    )
+                    .route(
+                        "/settings/attributes",
+                        web::put().to(update_attribute_settings::<D>),
+                    )
+                    .route("/subscribers", web::get().to(subscribers::<D>))
+                    // 본문을 스트림으로 읽으므로 크기 제한은 핸들러에서 확인한다.
+                    .route(
+                        "/subscribers/import",
+                        web::post().to(import_subscribers::<D>),
+                    )
+                    .route("/subscribers/{id}", web::get().to(subscriber::<D>))
+                    .route(
+                        "/subscribers/{id}",
+                        web::delete().to(delete_subscriber::<D>),
+                    )
+                    .route(
+                        "/subscribers/{id}/restore",
+                        web::post().to(restore_subscriber::<D>),
+                    )
+                    .route(
+                        "/subscribers/{id}/history",
+                        web::get().to(subscriber_history::<D>),
+                    )
+                    .route(
+                        "/subscribers/{id}/tags",
+                        web::get().to(subscriber_tags::<D>),
+                    )
+                    .route(
+                        "/subscribers/{id}/tags",
+                        web::put().to(update_subscriber_tags::<D>),
+                    )
+                    .route("/segments", web::get().to(segments::<D>))
+                    .route("/segments/{name}", web::put().to(save_segment::<D>))
+                    .route("/segments/{name}", web::delete().to(delete_segment::<D>))
+                    .route(
+                        "/segments/{name}/preview",
+                        web::get().to(preview_segment::<D>),
+                    )
+                    .route("/audit", web::get().to(audit_log::<D>)),
+            )
             // 커넥션을 애플리케이션 상태의 일부로 등록한다.
             // 포인터 사본을 얻어 애플리케이션 상태에 추가한다.
             .app_data(pool.clone())
+            .app_data(email_rules.clone())
+            .app_data(retention.clone())
     })
     .listen(listener.into_std()?)?
     .run();
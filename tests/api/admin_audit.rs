@@ -0,0 +1,124 @@
+use zero2prod::{
+    audit::{AuditAction, AuditContext},
+    database::basic::Zero2ProdDatabase,
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn audit_log_rejects_requests_without_credentials() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let response = reqwest::Client::new()
+        .get(format!("{}/admin/audit", &app.http_address()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()["WWW-Authenticate"],
+        r#"Basic realm="admin""#
+    );
+}
+
+#[tokio::test]
+async fn audit_log_rejects_an_invalid_password() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let response = reqwest::Client::new()
+        .get(format!("{}/admin/audit", &app.http_address()))
+        .basic_auth(&app.test_user.username, Some("wrong-password"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn audit_log_is_filtered_and_paginated() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let db_pool = app.db_pool();
+    let context = AuditContext::cli();
+    for target in ["a", "b", "c"] {
+        db_pool
+            .insert_audit_entry(&context.entry(
+                AuditAction::SubscriberDelete,
+                target,
+                serde_json::json!({}),
+            ))
+            .await
+            .unwrap();
+    }
+    db_pool
+        .insert_audit_entry(&context.entry(AuditAction::UserCreate, "d", serde_json::json!({})))
+        .await
+        .unwrap();
+
+    // 실행
+    let first_page: serde_json::Value = app
+        .get_admin("/audit?action=subscriber.delete&limit=2")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let cursor = first_page["next_cursor"].as_i64().unwrap();
+    let second_page: serde_json::Value = app
+        .get_admin(&format!(
+            "/audit?action=subscriber.delete&limit=2&before={}",
+            cursor
+        ))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // 확인
+    // 최신 항목부터 반환된다.
+    let targets = |page: &serde_json::Value| -> Vec<String> {
+        page["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["target"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(targets(&first_page), vec!["c", "b"]);
+    assert_eq!(targets(&second_page), vec!["a"]);
+    assert!(second_page["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn audit_log_ignores_forwarded_headers() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let response = reqwest::Client::new()
+        .put(format!("{}/admin/settings/attributes", &app.http_address()))
+        .basic_auth(&app.test_user.username, Some(&app.test_user.password))
+        .header("X-Forwarded-For", "203.0.113.7")
+        .header("Forwarded", "for=203.0.113.7")
+        .json(&serde_json::json!({ "attributes": [] }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let audit: serde_json::Value = app
+        .get_admin("/audit?action=settings.attributes.update")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(audit["entries"][0]["ip"], "127.0.0.1");
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use zero2prod::{
+    database::basic::Zero2ProdDatabase,
+    domain::{AttributeDefinition, AttributeSchema, AttributeType, SubscriberEmail},
+};
+
+use crate::helpers::TestApp;
+
+async fn send_admin(
+    app: &TestApp,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> reqwest::Response {
+    let mut request = reqwest::Client::new()
+        .request(method, format!("{}/admin{}", &app.http_address(), path))
+        .basic_auth(&app.test_user.username, Some(&app.test_user.password));
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+async fn put_segment(app: &TestApp, name: &str, expression: &str) -> reqwest::Response {
+    send_admin(
+        app,
+        reqwest::Method::PUT,
+        &format!("/segments/{}", name),
+        Some(serde_json::json!({ "expression": expression })),
+    )
+    .await
+}
+
+async fn preview(app: &TestApp, expression: &str) -> serde_json::Value {
+    let response = put_segment(app, "preview", expression).await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK, "{}", expression);
+    let response = app.get_admin("/segments/preview/preview").await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK, "{}", expression);
+    response.json().await.unwrap()
+}
+
+fn schema() -> AttributeSchema {
+    AttributeSchema::parse(vec![
+        AttributeDefinition {
+            name: "country".into(),
+            r#type: AttributeType::String,
+        },
+        AttributeDefinition {
+            name: "interests".into(),
+            r#type: AttributeType::List,
+        },
+        AttributeDefinition {
+            name: "score".into(),
+            r#type: AttributeType::Number,
+        },
+    ])
+    .unwrap()
+}
+
+/// 속성과 태그를 지정해서 구독자를 추가한다.
+async fn insert_subscriber(
+    app: &TestApp,
+    email: &str,
+    fields: &[(&str, &str)],
+    tags: &[&str],
+    confirmed: bool,
+    subscribed_at: &str,
+) -> Uuid {
+    let pool = app.db_pool();
+    let fields: HashMap<_, _> = fields
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    let id = Uuid::new_v4();
+    pool.insert_subscriptions(
+        id,
+        &SubscriberEmail::parse(email).unwrap(),
+        "name",
+        &schema().parse_form(&fields).unwrap(),
+        subscribed_at.parse::<DateTime<Utc>>().unwrap(),
+    )
+    .await
+    .unwrap();
+    if confirmed {
+        pool.confirm_subscriber(id).await.unwrap();
+    }
+    let response = send_admin(
+        app,
+        reqwest::Method::PUT,
+        &format!("/subscribers/{}/tags", id),
+        Some(serde_json::json!({ "tags": tags })),
+    )
+    .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    id
+}
+
+#[tokio::test]
+async fn subscriber_tags_are_replaced_and_audited() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = insert_subscriber(
+        &app,
+        "ursula@example.com",
+        &[],
+        &["news"],
+        true,
+        "2024-01-01T00:00:00Z",
+    )
+    .await;
+    let path = format!("/subscribers/{}/tags", id);
+
+    // 실행
+    let response = send_admin(
+        &app,
+        reqwest::Method::PUT,
+        &path,
+        Some(serde_json::json!({ "tags": ["VIP", " beta ", "vip"] })),
+    )
+    .await;
+    let invalid = send_admin(
+        &app,
+        reqwest::Method::PUT,
+        &path,
+        Some(serde_json::json!({ "tags": ["two words"] })),
+    )
+    .await;
+    let missing = send_admin(
+        &app,
+        reqwest::Method::PUT,
+        &format!("/subscribers/{}/tags", Uuid::new_v4()),
+        Some(serde_json::json!({ "tags": ["vip"] })),
+    )
+    .await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(invalid.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    let tags: serde_json::Value = app.get_admin(&path).await.json().await.unwrap();
+    assert_eq!(tags["tags"], serde_json::json!(["beta", "vip"]));
+    let audit: serde_json::Value = app
+        .get_admin("/audit?action=subscriber.tags.update")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(audit["entries"][0]["target"], id.to_string());
+    assert_eq!(
+        audit["entries"][0]["diff"]["tags"],
+        serde_json::json!({ "from": ["news"], "to": ["beta", "vip"] })
+    );
+}
+
+#[tokio::test]
+async fn segments_are_saved_listed_and_deleted() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let created = put_segment(&app, "vips", "tag = vip").await;
+    let updated = put_segment(&app, "vips", "tag = vip and status = confirmed").await;
+    let list: serde_json::Value = app.get_admin("/segments").await.json().await.unwrap();
+    let deleted = send_admin(&app, reqwest::Method::DELETE, "/segments/vips", None).await;
+    let deleted_again = send_admin(&app, reqwest::Method::DELETE, "/segments/vips", None).await;
+
+    // 확인
+    assert_eq!(created.status(), reqwest::StatusCode::OK);
+    assert_eq!(updated.status(), reqwest::StatusCode::OK);
+    assert_eq!(list["segments"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        list["segments"][0]["expression"],
+        "tag = vip and status = confirmed"
+    );
+    assert_eq!(deleted.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(deleted_again.status(), reqwest::StatusCode::NOT_FOUND);
+    let list: serde_json::Value = app.get_admin("/segments").await.json().await.unwrap();
+    assert_eq!(list["segments"], serde_json::json!([]));
+    let audit: serde_json::Value = app
+        .get_admin("/audit?action=segment.update")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        audit["entries"][0]["diff"]["expression"],
+        serde_json::json!({ "from": "tag = vip", "to": "tag = vip and status = confirmed" })
+    );
+    let audit: serde_json::Value = app
+        .get_admin("/audit?action=segment.delete")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(audit["entries"][0]["target"], "vips");
+}
+
+#[tokio::test]
+async fn invalid_segments_are_rejected() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let response = app
+        .put_attribute_settings(&serde_json::json!({
+            "attributes": [{ "name": "country", "type": "string" }]
+        }))
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let test_cases = vec![
+        ("vips", "", "an empty expression"),
+        ("vips", "tag =", "a missing value"),
+        ("vips", "tag = vip and", "a dangling operator"),
+        ("vips", "(tag = vip", "an unclosed parenthesis"),
+        ("vips", "tag > vip", "an ordering on a tag"),
+        ("vips", "status = gone", "an unknown status"),
+        (
+            "vips",
+            "attributes.company = acme",
+            "an undefined attribute",
+        ),
+        ("vips", "subscribed_at > yesterday", "an invalid timestamp"),
+        ("vips", "list = weekly", "a list condition"),
+        ("vips", "colour = red", "an unknown field"),
+        ("Bad%20Name", "tag = vip", "an invalid name"),
+    ];
+
+    for (name, expression, description) in test_cases {
+        // 실행
+        let response = put_segment(&app, name, expression).await;
+
+        // 확인
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "The API did not fail with 400 Bad Request when the segment had {}.",
+            description
+        );
+    }
+    let list: serde_json::Value = app.get_admin("/segments").await.json().await.unwrap();
+    assert_eq!(list["segments"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn a_segment_preview_counts_matching_subscribers() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let response = app
+        .put_attribute_settings(&serde_json::json!({ "attributes": schema().definitions() }))
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    insert_subscriber(
+        &app,
+        "a@example.com",
+        &[("country", "KR"), ("interests", "rust,go"), ("score", "3")],
+        &["vip"],
+        true,
+        "2024-01-01T00:00:00Z",
+    )
+    .await;
+    insert_subscriber(
+        &app,
+        "b@example.com",
+        &[("country", "KR"), ("interests", "go")],
+        &[],
+        false,
+        "2024-06-01T00:00:00Z",
+    )
+    .await;
+    let c = insert_subscriber(
+        &app,
+        "c@example.com",
+        &[("country", "JP"), ("interests", "rust")],
+        &["vip"],
+        true,
+        "2024-06-01T00:00:00Z",
+    )
+    .await;
+    insert_subscriber(
+        &app,
+        "d@example.com",
+        &[],
+        &[],
+        true,
+        "2024-06-01T00:00:00Z",
+    )
+    .await;
+    let test_cases = vec![
+        ("tag = vip", (2, 2)),
+        ("tag = vip and attributes.country = KR", (1, 1)),
+        (
+            "attributes.interests = rust or status = pending_confirmation",
+            (3, 2),
+        ),
+        // 속성이 없는 구독자도 포함된다.
+        ("attributes.country != KR", (2, 2)),
+        ("attributes.score = 3", (1, 1)),
+        ("subscribed_at >= 2024-03-01 and not tag = vip", (2, 1)),
+        (
+            "(tag = vip or attributes.country = KR) and subscribed_at < \"2024-03-01T00:00:00Z\"",
+            (1, 1),
+        ),
+    ];
+
+    for (expression, (total, confirmed)) in test_cases {
+        // 실행
+        let size = preview(&app, expression).await;
+
+        // 확인
+        assert_eq!(
+            size,
+            serde_json::json!({ "total": total, "confirmed": confirmed }),
+            "{}",
+            expression
+        );
+    }
+    // 삭제한 구독자는 제외된다.
+    app.db_pool()
+        .delete_subscriber(c, Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(
+        preview(&app, "tag = vip").await,
+        serde_json::json!({ "total": 1, "confirmed": 1 })
+    );
+}
+
+#[tokio::test]
+async fn a_segment_preview_fails_for_missing_or_outdated_segments() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let response = app
+        .put_attribute_settings(&serde_json::json!({
+            "attributes": [{ "name": "country", "type": "string" }]
+        }))
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let response = put_segment(&app, "korea", "attributes.country = KR").await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    // 식이 참조하는 속성의 정의를 지운다.
+    let response = app
+        .put_attribute_settings(&serde_json::json!({ "attributes": [] }))
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // 실행
+    let missing = app.get_admin("/segments/unknown/preview").await;
+    let outdated = app.get_admin("/segments/korea/preview").await;
+
+    // 확인
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    assert_eq!(outdated.status(), reqwest::StatusCode::CONFLICT);
+}
@@ -0,0 +1,490 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use zero2prod::{
+    database::basic::Zero2ProdDatabase,
+    domain::{SubscriberAttributes, SubscriberEmail},
+    routes::MAX_IMPORT_SIZE,
+};
+
+use crate::helpers::TestApp;
+
+/// 구독 시각이 1분씩 차이 나는 구독자를 추가하고 오래된 순으로 id를 반환한다.
+async fn insert_subscribers(app: &TestApp, emails: &[&str]) -> Vec<Uuid> {
+    let pool = app.db_pool();
+    let base = Utc::now() - Duration::hours(1);
+    let mut ids = Vec::new();
+    for (i, email) in emails.iter().enumerate() {
+        let id = Uuid::new_v4();
+        pool.insert_subscriptions(
+            id,
+            &SubscriberEmail::parse(email).unwrap(),
+            "name",
+            &SubscriberAttributes::default(),
+            base + Duration::minutes(i as i64),
+        )
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    ids
+}
+
+async fn get_json(app: &TestApp, path_and_query: &str) -> serde_json::Value {
+    let response = app.get_admin(path_and_query).await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+fn ids(page: &serde_json::Value) -> Vec<String> {
+    page["subscribers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|subscriber| subscriber["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn subscribers_are_paginated_with_a_stable_cursor() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let inserted =
+        insert_subscribers(&app, &["a@example.com", "b@example.com", "c@example.com"]).await;
+
+    // 실행
+    let first = get_json(&app, "/subscribers?limit=2").await;
+    // 페이지 사이에 새로 구독한 구독자는 다음 페이지에 영향을 주지 않는다.
+    app.db_pool()
+        .insert_subscriptions(
+            Uuid::new_v4(),
+            &SubscriberEmail::parse("d@example.com").unwrap(),
+            "name",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second = get_json(&app, &format!("/subscribers?limit=2&cursor={}", cursor)).await;
+
+    // 확인
+    assert_eq!(
+        ids(&first),
+        vec![inserted[2].to_string(), inserted[1].to_string()]
+    );
+    assert_eq!(ids(&second), vec![inserted[0].to_string()]);
+    assert!(second["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn subscribers_are_filtered() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let inserted = insert_subscribers(
+        &app,
+        &["ursula@example.com", "guin@example.com", "le@other.com"],
+    )
+    .await;
+
+    // 실행
+    let by_email = get_json(&app, "/subscribers?email=EXAMPLE").await;
+    let by_status = get_json(&app, "/subscribers?status=confirmed").await;
+
+    // 확인
+    assert_eq!(
+        ids(&by_email),
+        vec![inserted[1].to_string(), inserted[0].to_string()]
+    );
+    assert!(ids(&by_status).is_empty());
+}
+
+#[tokio::test]
+async fn wildcard_characters_are_matched_literally() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let inserted =
+        insert_subscribers(&app, &["first_last@example.com", "firstlast@example.com"]).await;
+
+    // 실행
+    let underscore = get_json(&app, "/subscribers?email=_").await;
+    let percent = get_json(&app, "/subscribers?email=%25").await;
+    let backslash = get_json(&app, "/subscribers?email=%5C").await;
+    let searched = app.db_pool().fetch_subscribers(Some("_")).await.unwrap();
+
+    // 확인
+    assert_eq!(ids(&underscore), vec![inserted[0].to_string()]);
+    assert!(ids(&percent).is_empty());
+    assert!(ids(&backslash).is_empty());
+    assert_eq!(searched.len(), 1);
+    assert_eq!(searched[0].id, inserted[0]);
+}
+
+#[tokio::test]
+async fn an_invalid_cursor_is_rejected() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let response = app.get_admin("/subscribers?cursor=not-a-cursor").await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn a_deleted_subscriber_is_gone_and_audited() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = insert_subscribers(&app, &["ursula@example.com"]).await[0];
+    let delete = || {
+        reqwest::Client::new()
+            .delete(format!("{}/admin/subscribers/{}", &app.http_address(), id))
+            .basic_auth(&app.test_user.username, Some(&app.test_user.password))
+            .send()
+    };
+
+    // 실행
+    let before = app.get_admin(&format!("/subscribers/{}", id)).await;
+    let first_delete = delete().await.unwrap();
+    let second_delete = delete().await.unwrap();
+    let after = app.get_admin(&format!("/subscribers/{}", id)).await;
+
+    // 확인
+    assert_eq!(before.status(), reqwest::StatusCode::OK);
+    assert_eq!(first_delete.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(second_delete.status(), reqwest::StatusCode::NOT_FOUND);
+    assert_eq!(after.status(), reqwest::StatusCode::NOT_FOUND);
+    let audit = get_json(&app, "/audit?action=subscriber.delete").await;
+    assert_eq!(audit["entries"][0]["target"], id.to_string());
+    assert_eq!(
+        audit["entries"][0]["diff"]["before"]["email"],
+        "ursula@example.com"
+    );
+}
+
+#[tokio::test]
+async fn subscribers_are_filtered_by_attributes() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let response = app
+        .put_attribute_settings(&serde_json::json!({
+            "attributes": [
+                { "name": "country", "type": "string" },
+                { "name": "interests", "type": "list" },
+            ]
+        }))
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    for body in [
+        "name=a&email=a%40example.com&country=KR&interests=rust,go",
+        "name=b&email=b%40example.com&country=KR&interests=go",
+        "name=c&email=c%40example.com&country=JP&interests=rust",
+    ] {
+        assert_eq!(
+            app.post_subscriptions(body).await.status(),
+            reqwest::StatusCode::OK
+        );
+    }
+    let emails = |page: serde_json::Value| -> Vec<String> {
+        page["subscribers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|subscriber| subscriber["email"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // 실행
+    let korean = get_json(
+        &app,
+        "/subscribers?attributes=%7B%22country%22%3A%22KR%22%7D",
+    )
+    .await;
+    let korean_rustaceans = get_json(
+        &app,
+        "/subscribers?attributes=%7B%22country%22%3A%22KR%22%2C%22interests%22%3A%22rust%22%7D",
+    )
+    .await;
+    let undefined = app
+        .get_admin("/subscribers?attributes=%7B%22company%22%3A%22acme%22%7D")
+        .await;
+    let mistyped = app
+        .get_admin("/subscribers?attributes=%7B%22country%22%3A1%7D")
+        .await;
+
+    // 확인
+    assert_eq!(emails(korean), vec!["b@example.com", "a@example.com"]);
+    assert_eq!(emails(korean_rustaceans), vec!["a@example.com"]);
+    assert_eq!(undefined.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(mistyped.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn invalid_attribute_definitions_are_rejected() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "attributes": [{ "name": "Country", "type": "string" }] }),
+            "an uppercase name",
+        ),
+        (
+            serde_json::json!({ "attributes": [{ "name": "email", "type": "string" }] }),
+            "a reserved name",
+        ),
+        (
+            serde_json::json!({ "attributes": [
+                { "name": "country", "type": "string" },
+                { "name": "country", "type": "list" },
+            ] }),
+            "a duplicate name",
+        ),
+    ];
+
+    for (invalid_body, description) in test_cases {
+        // 실행
+        let response = app.put_attribute_settings(&invalid_body).await;
+
+        // 확인
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "The API did not fail with 400 Bad Request when the definitions had {}.",
+            description
+        );
+    }
+    let settings = get_json(&app, "/settings/attributes").await;
+    assert_eq!(settings["attributes"], serde_json::json!([]));
+}
+
+async fn post_restore(app: &TestApp, id: Uuid) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!(
+            "{}/admin/subscribers/{}/restore",
+            &app.http_address(),
+            id
+        ))
+        .basic_auth(&app.test_user.username, Some(&app.test_user.password))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn a_deleted_subscriber_can_be_restored_within_the_grace_period() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let inserted = insert_subscribers(&app, &["ursula@example.com", "guin@example.com"]).await;
+    let pool = app.db_pool();
+    pool.delete_subscriber(inserted[0], Utc::now())
+        .await
+        .unwrap();
+    // 기본 유예 기간은 30일이다.
+    pool.delete_subscriber(inserted[1], Utc::now() - Duration::days(31))
+        .await
+        .unwrap();
+
+    // 실행
+    let restored = post_restore(&app, inserted[0]).await;
+    let restored_twice = post_restore(&app, inserted[0]).await;
+    let expired = post_restore(&app, inserted[1]).await;
+
+    // 확인
+    assert_eq!(restored.status(), reqwest::StatusCode::OK);
+    let subscriber: serde_json::Value = restored.json().await.unwrap();
+    assert_eq!(subscriber["email"], "ursula@example.com");
+    assert_eq!(restored_twice.status(), reqwest::StatusCode::NOT_FOUND);
+    assert_eq!(expired.status(), reqwest::StatusCode::NOT_FOUND);
+    let page = get_json(&app, "/subscribers").await;
+    assert_eq!(ids(&page), vec![inserted[0].to_string()]);
+    let audit = get_json(&app, "/audit?action=subscriber.restore").await;
+    assert_eq!(audit["entries"][0]["target"], inserted[0].to_string());
+}
+
+#[tokio::test]
+async fn a_deleted_address_can_subscribe_again_but_then_cannot_be_restored() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = insert_subscribers(&app, &["ursula@example.com"]).await[0];
+    app.db_pool()
+        .delete_subscriber(id, Utc::now())
+        .await
+        .unwrap();
+
+    // 실행
+    let subscribed = app
+        .post_subscriptions("name=le%20guin&email=ursula%40example.com")
+        .await;
+    let restored = post_restore(&app, id).await;
+
+    // 확인
+    assert_eq!(subscribed.status(), reqwest::StatusCode::OK);
+    let page = get_json(&app, "/subscribers").await;
+    assert_eq!(page["subscribers"].as_array().unwrap().len(), 1);
+    assert_ne!(page["subscribers"][0]["id"], id.to_string());
+    assert_eq!(restored.status(), reqwest::StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn the_history_of_a_subscriber_records_every_change() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = insert_subscribers(&app, &["ursula@example.com"]).await[0];
+    let pool = app.db_pool();
+
+    // 실행
+    pool.confirm_subscriber(id).await.unwrap();
+    // 바뀐 값이 없는 변경은 기록하지 않는다.
+    pool.confirm_subscriber(id).await.unwrap();
+    pool.delete_subscriber(id, Utc::now()).await.unwrap();
+    let restored = post_restore(&app, id).await;
+    let unknown = app
+        .get_admin(&format!("/subscribers/{}/history", Uuid::new_v4()))
+        .await;
+
+    // 확인
+    assert_eq!(restored.status(), reqwest::StatusCode::OK);
+    let history = get_json(&app, &format!("/subscribers/{}/history", id)).await;
+    let entries = history["entries"].as_array().unwrap();
+    let operations: Vec<_> = entries
+        .iter()
+        .map(|entry| entry["operation"].as_str().unwrap())
+        .collect();
+    assert_eq!(operations, vec!["insert", "update", "delete", "restore"]);
+    assert_eq!(entries[0]["status"], "pending_confirmation");
+    assert_eq!(entries[1]["status"], "confirmed");
+    assert_eq!(entries[3]["email"], "ursula@example.com");
+    assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn valid_rows_are_imported_and_invalid_rows_are_reported() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    insert_subscribers(&app, &["existing@example.com"]).await;
+    let csv = "\
+email,name,subscribed_at,list
+ursula@example.com,Ursula,2020-01-02T03:04:05Z,
+Existing@Example.com,Renamed,,
+not-an-email,Nobody,,
+guin@example.com,,,
+le@example.com,Le,yesterday,
+URSULA@example.com,Again,,
+tagged@example.com,Tagged,,weekly
+";
+
+    // 실행
+    let response = app.post_import(csv).await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["inserted"], 1);
+    assert_eq!(report["updated"], 1);
+    assert_eq!(report["skipped"], 0);
+    let errors: Vec<_> = report["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["line"].as_u64().unwrap())
+        .collect();
+    assert_eq!(errors, [4, 5, 6, 7, 8]);
+
+    let page = get_json(&app, "/subscribers").await;
+    let mut saved: Vec<_> = page["subscribers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|subscriber| {
+            (
+                subscriber["email"].as_str().unwrap().to_string(),
+                subscriber["name"].as_str().unwrap().to_string(),
+                subscriber["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    saved.sort();
+    assert_eq!(
+        saved,
+        [
+            (
+                "existing@example.com".into(),
+                "Renamed".into(),
+                "pending_confirmation".into()
+            ),
+            (
+                "ursula@example.com".into(),
+                "Ursula".into(),
+                "confirmed".into()
+            ),
+        ]
+    );
+    let audit = get_json(&app, "/audit?action=subscriber.import").await;
+    assert_eq!(audit["entries"][0]["diff"]["inserted"], 1);
+    assert_eq!(audit["entries"][0]["diff"]["errors"], 5);
+}
+
+#[tokio::test]
+async fn an_import_without_required_columns_is_rejected() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let response = app.post_import("email\nursula@example.com\n").await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert!(app
+        .db_pool()
+        .fetch_subscribers(None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn a_large_import_is_loaded_in_one_request() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let mut csv = String::from("email,name\n");
+    for i in 0..5000 {
+        csv.push_str(&format!("reader{}@example.com,Reader {}\n", i, i));
+    }
+    // 구독자는 나눠서 저장하므로 앞서 저장한 주소와도 중복을 확인해야 한다.
+    csv.push_str("reader0@example.com,Again\n");
+
+    // 실행
+    let response = app.post_import(&csv).await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["inserted"], 5000);
+    assert_eq!(report["errors"][0]["line"], 5002);
+    assert_eq!(report["errors"][0]["error"], "Duplicate of line 2.");
+    assert_eq!(
+        app.db_pool().fetch_subscribers(None).await.unwrap().len(),
+        5000
+    );
+}
+
+#[tokio::test]
+async fn an_import_larger_than_the_limit_is_rejected() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    // 빈 줄은 행으로 읽지 않으므로 본문의 크기만 제한을 넘는다.
+    let mut csv = String::from("email,name\nursula@example.com,Ursula\n");
+    csv.push_str(&"\n".repeat(MAX_IMPORT_SIZE));
+
+    // 실행
+    let response = app.post_import(&csv).await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(app
+        .db_pool()
+        .fetch_subscribers(None)
+        .await
+        .unwrap()
+        .is_empty());
+}
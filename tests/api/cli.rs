@@ -0,0 +1,200 @@
+use chrono::Utc;
+use clap::Parser;
+use uuid::Uuid;
+use zero2prod::{authentication::UserRole, cli::Cli, database::basic::Zero2ProdDatabase};
+
+use crate::helpers::{TestApp, TestUser};
+
+/// 테스트 애플리케이션의 DB와 구성으로 관리용 명령을 실행한다.
+async fn run(app: &TestApp, args: &[&str]) -> Result<(), anyhow::Error> {
+    let cli = Cli::try_parse_from(std::iter::once("zero2prod").chain(args.iter().copied()))
+        .expect("Failed to parse the arguments.");
+    cli.command
+        .expect("No subcommand was given.")
+        .run(&app.db_pool(), &app.configuration)
+        .await
+}
+
+async fn subscribe(app: &TestApp) -> Uuid {
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    app.db_pool()
+        .fetch_subscribers(None)
+        .await
+        .unwrap()
+        .pop()
+        .expect("No subscription was saved.")
+        .id
+}
+
+#[tokio::test]
+async fn users_are_disabled_and_enabled_from_the_cli() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let user = TestUser::generate();
+    user.store(&app.db_pool(), UserRole::Admin).await;
+
+    // 실행
+    let disabled = run(&app, &["users", "disable", &user.username]).await;
+    let login_while_disabled = app.post_login(&user).await;
+    let enabled = run(&app, &["users", "enable", &user.username]).await;
+    let unknown = run(&app, &["users", "disable", "nobody"]).await;
+
+    // 확인
+    assert!(disabled.is_ok());
+    assert_eq!(
+        login_while_disabled.status(),
+        reqwest::StatusCode::UNAUTHORIZED
+    );
+    assert!(enabled.is_ok());
+    assert!(unknown.is_err());
+    let credentials = app
+        .db_pool()
+        .fetch_user_credentials(&user.username)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!credentials.disabled);
+    let audit: serde_json::Value = app
+        .get_admin("/audit?action=user.disable")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(audit["entries"][0]["target"], user.username);
+    assert!(audit["entries"][0]["actor"]
+        .as_str()
+        .unwrap()
+        .starts_with("cli:"));
+}
+
+#[tokio::test]
+async fn a_user_change_is_rolled_back_when_the_audit_entry_fails() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let user = TestUser::generate();
+    user.store(&app.db_pool(), UserRole::Admin).await;
+    // 감사 로그 기록만 실패하게 한다.
+    sqlx::query("ALTER TABLE audit_log RENAME TO audit_log_unavailable")
+        .execute(&*app.db_pool())
+        .await
+        .unwrap();
+
+    // 실행
+    let disabled = run(&app, &["users", "disable", &user.username]).await;
+
+    // 확인
+    assert!(disabled.is_err());
+    let credentials = app
+        .db_pool()
+        .fetch_user_credentials(&user.username)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!credentials.disabled);
+}
+
+#[tokio::test]
+async fn a_username_cannot_be_inserted_twice() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+
+    // 실행
+    let duplicate = pool
+        .insert_user(
+            Uuid::new_v4(),
+            &app.test_user.username,
+            "hash",
+            UserRole::Admin.as_str(),
+            Utc::now(),
+        )
+        .await;
+    let missing = pool.set_user_disabled("nobody", true).await.unwrap();
+
+    // 확인
+    assert!(duplicate.is_err());
+    assert_eq!(missing, 0);
+}
+
+#[tokio::test]
+async fn subscribers_are_confirmed_and_removed_from_the_cli() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = subscribe(&app).await;
+    let id_arg = id.to_string();
+
+    // 실행
+    let confirmed = run(&app, &["subscribers", "confirm", &id_arg]).await;
+    let status = app
+        .db_pool()
+        .fetch_subscriber(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .status;
+    let removed = run(&app, &["subscribers", "remove", &id_arg]).await;
+    let removed_again = run(&app, &["subscribers", "remove", &id_arg]).await;
+    let unknown = run(
+        &app,
+        &["subscribers", "confirm", &Uuid::new_v4().to_string()],
+    )
+    .await;
+
+    // 확인
+    assert!(confirmed.is_ok());
+    assert_eq!(status, "confirmed");
+    assert!(removed.is_ok());
+    assert!(removed_again.is_err());
+    assert!(unknown.is_err());
+    assert!(app.db_pool().fetch_subscriber(id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn confirming_or_deleting_an_unknown_subscriber_affects_no_rows() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+
+    // 실행
+    let confirmed = pool.confirm_subscriber(Uuid::new_v4()).await.unwrap();
+    let deleted = pool
+        .delete_subscriber(Uuid::new_v4(), Utc::now())
+        .await
+        .unwrap();
+
+    // 확인
+    assert_eq!(confirmed, 0);
+    assert_eq!(deleted, 0);
+}
+
+#[tokio::test]
+async fn subscribers_are_exported_as_csv() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = subscribe(&app).await;
+    let output = std::env::temp_dir().join(format!("{}.csv", Uuid::new_v4()));
+
+    // 실행
+    let exported = run(
+        &app,
+        &[
+            "subscribers",
+            "export",
+            "--output",
+            output.to_str().unwrap(),
+        ],
+    )
+    .await;
+
+    // 확인
+    assert!(exported.is_ok());
+    let csv = std::fs::read_to_string(&output).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines[0], "id,email,name,status,subscribed_at,attributes");
+    assert!(lines[1].starts_with(&format!("{},ursula_le_guin@gmail.com,le guin,", id)));
+    assert_eq!(lines.len(), 2);
+    std::fs::remove_file(&output).unwrap();
+}
@@ -0,0 +1,233 @@
+use chrono::Utc;
+use uuid::Uuid;
+use zero2prod::{
+    database::{
+        basic::{Zero2ProdDatabase, Zero2ProdTransaction},
+        transaction::SharedTransaction,
+    },
+    domain::{SubscriberAttributes, SubscriberEmail},
+};
+
+use crate::helpers::TestApp;
+
+// 모든 백엔드가 Postgres와 같은 제약 조건을 따르는지 확인한다.
+// `cargo test --features sqlite`로 SQLite에 대해서도 실행한다.
+
+#[tokio::test]
+async fn a_duplicate_email_is_rejected() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    pool.insert_subscriptions(
+        Uuid::new_v4(),
+        &SubscriberEmail::parse("ursula@example.com").unwrap(),
+        "le guin",
+        &SubscriberAttributes::default(),
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    // 실행
+    let result = pool
+        .insert_subscriptions(
+            Uuid::new_v4(),
+            &SubscriberEmail::parse("ursula@example.com").unwrap(),
+            "ursula",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await;
+
+    // 확인
+    assert!(result.is_err());
+    assert_eq!(pool.fetch_subscribers(None).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn emails_differing_only_in_case_are_duplicates() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    pool.insert_subscriptions(
+        Uuid::new_v4(),
+        &SubscriberEmail::parse("ursula@example.com").unwrap(),
+        "le guin",
+        &SubscriberAttributes::default(),
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    // 실행
+    let result = pool
+        .insert_subscriptions(
+            Uuid::new_v4(),
+            &SubscriberEmail::parse("Ursula@Example.com").unwrap(),
+            "ursula",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await;
+
+    // 확인
+    assert!(result.is_err());
+    assert_eq!(pool.fetch_subscribers(None).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn a_new_subscriber_is_pending_until_confirmed() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let id = Uuid::new_v4();
+    pool.insert_subscriptions(
+        id,
+        &SubscriberEmail::parse("ursula@example.com").unwrap(),
+        "le guin",
+        &SubscriberAttributes::default(),
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    // 실행
+    let before = pool.fetch_subscriber(id).await.unwrap().unwrap();
+    let rows_affected = pool.confirm_subscriber(id).await.unwrap();
+    let after = pool.fetch_subscriber(id).await.unwrap().unwrap();
+
+    // 확인
+    assert_eq!(before.status, "pending_confirmation");
+    assert_eq!(rows_affected, 1);
+    assert_eq!(after.status, "confirmed");
+    assert_eq!(pool.confirm_subscriber(Uuid::new_v4()).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn only_committed_transactions_are_applied() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (committed, rolled_back, dropped) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    // 실행
+    let transaction = pool.begin().await.unwrap();
+    transaction
+        .insert_subscriptions(
+            committed,
+            &SubscriberEmail::parse("ursula@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    let transaction = pool.begin().await.unwrap();
+    transaction
+        .insert_subscriptions(
+            rolled_back,
+            &SubscriberEmail::parse("guin@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    transaction.rollback().await.unwrap();
+    let transaction = pool.begin().await.unwrap();
+    transaction
+        .insert_subscriptions(
+            dropped,
+            &SubscriberEmail::parse("le@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    drop(transaction);
+
+    // 확인
+    assert!(pool.fetch_subscriber(committed).await.unwrap().is_some());
+    assert!(pool.fetch_subscriber(rolled_back).await.unwrap().is_none());
+    assert!(pool.fetch_subscriber(dropped).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn a_nested_transaction_is_rolled_back_to_its_savepoint() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (outer, inner) = (Uuid::new_v4(), Uuid::new_v4());
+    let transaction = pool.begin().await.unwrap();
+    transaction
+        .insert_subscriptions(
+            outer,
+            &SubscriberEmail::parse("ursula@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+
+    // 실행
+    let nested = transaction.begin().await.unwrap();
+    nested
+        .insert_subscriptions(
+            inner,
+            &SubscriberEmail::parse("guin@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    // 세이브포인트가 열려 있는 동안 바깥 트랜잭션은 사용할 수 없다.
+    let while_nested = transaction.fetch_subscriber(outer).await;
+    nested.rollback().await.unwrap();
+    transaction.commit().await.unwrap();
+
+    // 확인
+    assert!(while_nested.is_err());
+    assert!(pool.fetch_subscriber(outer).await.unwrap().is_some());
+    assert!(pool.fetch_subscriber(inner).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn a_transaction_dropped_while_its_connection_is_busy_is_rolled_back() {
+    // 준비
+    // 커넥션이 하나뿐이므로 되돌리지 않으면 다음 조회에서 변경이 보인다.
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::query("CREATE TABLE items (id INTEGER)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let outer = SharedTransaction::begin(&pool).await.unwrap();
+    sqlx::query("INSERT INTO items VALUES (1)")
+        .execute(&mut *outer.connection().await.unwrap())
+        .await
+        .unwrap();
+    let inner = outer.begin_nested().await.unwrap();
+
+    // 실행
+    // 안쪽 트랜잭션이 커넥션을 사용하는 동안 바깥쪽 트랜잭션을 버린다.
+    let connection = inner.connection().await.unwrap();
+    drop(outer);
+    drop(connection);
+    let after_drop = inner.connection().await.map(|_| ());
+    drop(inner);
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    // 확인
+    assert!(after_drop.is_err());
+    assert_eq!(count, 0);
+}
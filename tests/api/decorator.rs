@@ -0,0 +1,477 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use zero2prod::{
+    audit::{AuditEntry, AuditFilter, NewAuditEntry},
+    database::{
+        basic::{
+            ImportCounts, LoginThrottle, NewSubscriber, Segment, SegmentSize, SessionUser,
+            Subscriber, SubscriberFilter, SubscriberHistoryEntry, TotpState, UserCredentials,
+            Zero2ProdDatabase,
+        },
+        error::StorageError,
+        sqlite::pool::SqlitePool,
+    },
+    domain::{AttributeSchema, SubscriberAttributes, SubscriberEmail, SubscriberTag},
+    segment::SegmentExpression,
+};
+
+use crate::helpers::TestApp;
+
+// 핸들러는 `Zero2ProdDatabase`에만 의존하므로 저장소를 감싼 데코레이터를
+// 그대로 서버에 전달할 수 있다.
+
+#[derive(Clone, Copy)]
+enum Fault {
+    Conflict,
+    Unavailable,
+}
+
+/// 지정한 작업을 실패시키고 나머지는 감싼 저장소에 맡기는 데코레이터
+#[derive(Clone)]
+struct FaultyDatabase<D> {
+    inner: D,
+    operation: &'static str,
+    fault: Fault,
+}
+
+impl<D> FaultyDatabase<D> {
+    fn check(&self, operation: &str) -> Result<(), StorageError> {
+        if operation != self.operation {
+            return Ok(());
+        }
+        Err(match self.fault {
+            Fault::Conflict => StorageError::Conflict("Injected conflict.".into()),
+            Fault::Unavailable => StorageError::Other("Injected failure.".into()),
+        })
+    }
+}
+
+/// 트랜잭션은 감싼 저장소의 것을 그대로 사용한다.
+impl<D: Zero2ProdDatabase> Zero2ProdDatabase for FaultyDatabase<D> {
+    type Transaction = D::Transaction;
+
+    async fn begin(&self) -> Result<Self::Transaction, StorageError> {
+        self.check("begin")?;
+        self.inner.begin().await
+    }
+
+    async fn insert_subscriptions(
+        &self,
+        id: Uuid,
+        email: &SubscriberEmail,
+        name: &str,
+        attributes: &SubscriberAttributes,
+        subscribed_at: DateTime<Utc>,
+    ) -> Result<(), StorageError> {
+        self.check("insert_subscriptions")?;
+        self.inner
+            .insert_subscriptions(id, email, name, attributes, subscribed_at)
+            .await
+    }
+
+    async fn fetch_subscribers(
+        &self,
+        search: Option<&str>,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        self.check("fetch_subscribers")?;
+        self.inner.fetch_subscribers(search).await
+    }
+
+    async fn fetch_subscriber_page(
+        &self,
+        filter: &SubscriberFilter,
+        limit: i64,
+    ) -> Result<Vec<Subscriber>, StorageError> {
+        self.check("fetch_subscriber_page")?;
+        self.inner.fetch_subscriber_page(filter, limit).await
+    }
+
+    async fn fetch_subscriber(&self, id: Uuid) -> Result<Option<Subscriber>, StorageError> {
+        self.check("fetch_subscriber")?;
+        self.inner.fetch_subscriber(id).await
+    }
+
+    async fn confirm_subscriber(&self, id: Uuid) -> Result<u64, StorageError> {
+        self.check("confirm_subscriber")?;
+        self.inner.confirm_subscriber(id).await
+    }
+
+    async fn delete_subscriber(
+        &self,
+        id: Uuid,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("delete_subscriber")?;
+        self.inner.delete_subscriber(id, deleted_at).await
+    }
+
+    async fn restore_subscriber(
+        &self,
+        id: Uuid,
+        deleted_after: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("restore_subscriber")?;
+        self.inner.restore_subscriber(id, deleted_after).await
+    }
+
+    async fn purge_deleted_subscribers(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("purge_deleted_subscribers")?;
+        self.inner.purge_deleted_subscribers(deleted_before).await
+    }
+
+    async fn fetch_subscriber_history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<SubscriberHistoryEntry>, StorageError> {
+        self.check("fetch_subscriber_history")?;
+        self.inner.fetch_subscriber_history(id).await
+    }
+
+    async fn delete_unconfirmed_subscribers(
+        &self,
+        subscribed_before: DateTime<Utc>,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("delete_unconfirmed_subscribers")?;
+        self.inner
+            .delete_unconfirmed_subscribers(subscribed_before, deleted_at)
+            .await
+    }
+
+    async fn anonymise_unconfirmed_subscribers(
+        &self,
+        subscribed_before: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("anonymise_unconfirmed_subscribers")?;
+        self.inner
+            .anonymise_unconfirmed_subscribers(subscribed_before)
+            .await
+    }
+
+    async fn import_subscribers(
+        &self,
+        subscribers: &[NewSubscriber],
+    ) -> Result<ImportCounts, StorageError> {
+        self.check("import_subscribers")?;
+        self.inner.import_subscribers(subscribers).await
+    }
+
+    async fn fetch_subscriber_tags(&self, id: Uuid) -> Result<Vec<String>, StorageError> {
+        self.check("fetch_subscriber_tags")?;
+        self.inner.fetch_subscriber_tags(id).await
+    }
+
+    async fn set_subscriber_tags(
+        &self,
+        id: Uuid,
+        tags: &[SubscriberTag],
+    ) -> Result<(), StorageError> {
+        self.check("set_subscriber_tags")?;
+        self.inner.set_subscriber_tags(id, tags).await
+    }
+
+    async fn fetch_segments(&self) -> Result<Vec<Segment>, StorageError> {
+        self.check("fetch_segments")?;
+        self.inner.fetch_segments().await
+    }
+
+    async fn fetch_segment(&self, name: &str) -> Result<Option<Segment>, StorageError> {
+        self.check("fetch_segment")?;
+        self.inner.fetch_segment(name).await
+    }
+
+    async fn save_segment(
+        &self,
+        name: &str,
+        expression: &str,
+        updated_at: DateTime<Utc>,
+    ) -> Result<(), StorageError> {
+        self.check("save_segment")?;
+        self.inner.save_segment(name, expression, updated_at).await
+    }
+
+    async fn delete_segment(&self, name: &str) -> Result<u64, StorageError> {
+        self.check("delete_segment")?;
+        self.inner.delete_segment(name).await
+    }
+
+    async fn count_segment(
+        &self,
+        expression: &SegmentExpression,
+    ) -> Result<SegmentSize, StorageError> {
+        self.check("count_segment")?;
+        self.inner.count_segment(expression).await
+    }
+
+    async fn fetch_attribute_schema(&self) -> Result<AttributeSchema, StorageError> {
+        self.check("fetch_attribute_schema")?;
+        self.inner.fetch_attribute_schema().await
+    }
+
+    async fn set_attribute_schema(&self, schema: &AttributeSchema) -> Result<(), StorageError> {
+        self.check("set_attribute_schema")?;
+        self.inner.set_attribute_schema(schema).await
+    }
+
+    async fn insert_user(
+        &self,
+        user_id: Uuid,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), StorageError> {
+        self.check("insert_user")?;
+        self.inner
+            .insert_user(user_id, username, password_hash, role, created_at)
+            .await
+    }
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<u64, StorageError> {
+        self.check("set_user_disabled")?;
+        self.inner.set_user_disabled(username, disabled).await
+    }
+
+    async fn fetch_user_credentials(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserCredentials>, StorageError> {
+        self.check("fetch_user_credentials")?;
+        self.inner.fetch_user_credentials(username).await
+    }
+
+    async fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: Uuid,
+        created_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+        second_factor_pending: bool,
+    ) -> Result<(), StorageError> {
+        self.check("insert_session")?;
+        self.inner
+            .insert_session(
+                token_hash,
+                user_id,
+                created_at,
+                expires_at,
+                second_factor_pending,
+            )
+            .await
+    }
+
+    async fn fetch_session_user(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<SessionUser>, StorageError> {
+        self.check("fetch_session_user")?;
+        self.inner.fetch_session_user(token_hash, now).await
+    }
+
+    async fn complete_second_factor(
+        &self,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("complete_second_factor")?;
+        self.inner
+            .complete_second_factor(token_hash, expires_at)
+            .await
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> Result<u64, StorageError> {
+        self.check("delete_session")?;
+        self.inner.delete_session(token_hash).await
+    }
+
+    async fn fetch_totp_state(&self, user_id: Uuid) -> Result<Option<TotpState>, StorageError> {
+        self.check("fetch_totp_state")?;
+        self.inner.fetch_totp_state(user_id).await
+    }
+
+    async fn set_pending_totp_secret(
+        &self,
+        user_id: Uuid,
+        secret: &str,
+    ) -> Result<u64, StorageError> {
+        self.check("set_pending_totp_secret")?;
+        self.inner.set_pending_totp_secret(user_id, secret).await
+    }
+
+    async fn enable_totp(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), StorageError> {
+        self.check("enable_totp")?;
+        self.inner.enable_totp(user_id, recovery_code_hashes).await
+    }
+
+    async fn disable_totp(&self, user_id: Uuid) -> Result<(), StorageError> {
+        self.check("disable_totp")?;
+        self.inner.disable_totp(user_id).await
+    }
+
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> Result<u64, StorageError> {
+        self.check("record_totp_step")?;
+        self.inner.record_totp_step(user_id, step).await
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("use_recovery_code")?;
+        self.inner
+            .use_recovery_code(user_id, code_hash, used_at)
+            .await
+    }
+
+    async fn fetch_require_two_factor(&self) -> Result<bool, StorageError> {
+        self.check("fetch_require_two_factor")?;
+        self.inner.fetch_require_two_factor().await
+    }
+
+    async fn set_require_two_factor(&self, required: bool) -> Result<(), StorageError> {
+        self.check("set_require_two_factor")?;
+        self.inner.set_require_two_factor(required).await
+    }
+
+    async fn fetch_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> Result<Option<LoginThrottle>, StorageError> {
+        self.check("fetch_login_throttle")?;
+        self.inner.fetch_login_throttle(scope, subject).await
+    }
+
+    async fn lock_login_throttle(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: DateTime<Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        self.check("lock_login_throttle")?;
+        self.inner.lock_login_throttle(scope, subject, now).await
+    }
+
+    async fn record_login_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+    ) -> Result<LoginThrottle, StorageError> {
+        self.check("record_login_failure")?;
+        self.inner
+            .record_login_failure(scope, subject, now, window_start)
+            .await
+    }
+
+    async fn lock_login(
+        &self,
+        scope: &str,
+        subject: &str,
+        locked_until: DateTime<Utc>,
+    ) -> Result<u64, StorageError> {
+        self.check("lock_login")?;
+        self.inner.lock_login(scope, subject, locked_until).await
+    }
+
+    async fn forgive_login_failure(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        self.check("forgive_login_failure")?;
+        self.inner.forgive_login_failure(scope, subject).await
+    }
+
+    async fn clear_login_failures(&self, scope: &str, subject: &str) -> Result<u64, StorageError> {
+        self.check("clear_login_failures")?;
+        self.inner.clear_login_failures(scope, subject).await
+    }
+
+    async fn insert_audit_entry(&self, entry: &NewAuditEntry) -> Result<(), StorageError> {
+        self.check("insert_audit_entry")?;
+        self.inner.insert_audit_entry(entry).await
+    }
+
+    async fn fetch_audit_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, StorageError> {
+        self.check("fetch_audit_entries")?;
+        self.inner.fetch_audit_entries(filter, limit).await
+    }
+}
+
+async fn spawn_faulty(
+    operation: &'static str,
+    fault: Fault,
+) -> TestApp<FaultyDatabase<SqlitePool>> {
+    let inner = SqlitePool::temporary()
+        .await
+        .expect("Failed to create a temporary database.");
+    TestApp::spawn_with_pool(FaultyDatabase {
+        inner,
+        operation,
+        fault,
+    })
+    .await
+}
+
+#[tokio::test]
+async fn a_conflict_reported_by_the_storage_is_not_revealed() {
+    // 준비
+    let app = spawn_faulty("insert_subscriptions", Fault::Conflict).await;
+
+    // 실행
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let saved = app.db_pool().inner.fetch_subscribers(None).await.unwrap();
+    assert!(saved.is_empty());
+}
+
+#[tokio::test]
+async fn a_storage_failure_is_reported_as_a_server_error() {
+    // 준비
+    let app = spawn_faulty("insert_subscriptions", Fault::Unavailable).await;
+
+    // 실행
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // 확인
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[tokio::test]
+async fn other_operations_pass_through_the_decorator() {
+    // 준비
+    let app = spawn_faulty("fetch_subscriber_page", Fault::Unavailable).await;
+
+    // 실행
+    let subscribed = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+    let listed = app.get_admin("/subscribers").await;
+
+    // 확인
+    assert_eq!(subscribed.status(), reqwest::StatusCode::OK);
+    assert_eq!(listed.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let saved = app.db_pool().fetch_subscribers(None).await.unwrap();
+    assert_eq!(saved.len(), 1);
+}
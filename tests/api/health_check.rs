@@ -0,0 +1,27 @@
+use crate::helpers::TestApp;
+
+// `tokio::test`는 테스팅에 있어서 `tokio::main`과 동등하다.
+// `#[test]` 속성을 지정하는 수고를 덜 수 있다.
+//
+// `cargo expand --test api`을 사용해서 코드가 무엇을 생성하는지 확인할 수 있다.
+#[tokio::test]
+async fn health_check_works() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    // `reqwest`를 사용해서 애플리케이션에 대한 HTTP 요청을 수행한다.
+    let client = reqwest::Client::new();
+
+    // 실행
+    let response = client
+        // 반환된 애플리케이션 주소를 사용한다.
+        .get(&format!("{}/health_check", &app.http_address()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // 확인
+    // 응답이 200 OK인지 확인한다.
+    assert!(response.status().is_success());
+    // 응답 본문의 길이가 0인지 확인한다.
+    assert_eq!(Some(0), response.content_length());
+}
@@ -0,0 +1,260 @@
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+#[cfg(not(feature = "sqlite"))]
+use sqlx::{Connection, Executor, PgConnection};
+use std::sync::Once;
+use tracing::Subscriber;
+use uuid::Uuid;
+#[cfg(not(feature = "sqlite"))]
+use zero2prod::database::basic::SqlxDatabase;
+use zero2prod::{
+    authentication::{compute_password_hash, UserRole},
+    configuration::{DatabaseSettings, DefaultDBPool, Settings},
+    database::{basic::Zero2ProdDatabase, sqlite::pool::SqlitePool},
+    startup::new_server,
+    telemetry::{get_tracing_subscriber, init_tracing_subscriber},
+};
+
+/// `TEST_LOG` 값이 설정되어 있으면 `stdout`에 출력하는 tracing_subscriber를 생성한다.
+/// 그렇지 않으면 버린다.
+/// 한번만 초기화 된다.
+fn init_test_tracing_subscriber() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let default_filter_level = "info".to_string();
+        let tracing_subscriber_name = "test".to_string();
+        let boxed_tracing_subscriber: Box<dyn Subscriber + Send + Sync> =
+            if std::env::var("TEST_LOG").is_ok() {
+                let tracing_subscriber = get_tracing_subscriber(
+                    tracing_subscriber_name,
+                    default_filter_level,
+                    std::io::stdout,
+                );
+                Box::new(tracing_subscriber)
+            } else {
+                let tracing_subscriber = get_tracing_subscriber(
+                    tracing_subscriber_name,
+                    default_filter_level,
+                    std::io::sink,
+                );
+                Box::new(tracing_subscriber)
+            };
+        init_tracing_subscriber(boxed_tracing_subscriber);
+    })
+}
+
+/// 테스트용 관리자 계정
+pub struct TestUser {
+    pub username: String,
+    pub password: String,
+}
+
+impl TestUser {
+    pub fn generate() -> Self {
+        Self {
+            username: Uuid::new_v4().to_string(),
+            password: Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub async fn store(&self, pool: &impl Zero2ProdDatabase, role: UserRole) {
+        let password_hash = compute_password_hash(Secret::new(self.password.clone()))
+            .expect("Failed to hash password.");
+        pool.insert_user(
+            Uuid::new_v4(),
+            &self.username,
+            password_hash.expose_secret(),
+            role.as_str(),
+            Utc::now(),
+        )
+        .await
+        .expect("Failed to store test user.");
+    }
+}
+
+pub struct TestApp<D = DefaultDBPool> {
+    pub configuration: Settings,
+    /// owner 역할을 가진 관리자
+    pub test_user: TestUser,
+    /// 서버와 같은 DB를 사용하는 풀
+    db_pool: D,
+}
+
+impl TestApp {
+    // 백그라운드에서 애플리케이션을 구동한다.
+    // 이 함수는 이제 비동기이다.
+    pub async fn spawn_app() -> Self {
+        init_test_tracing_subscriber();
+
+        // 설정을 읽어온다.
+        let mut configuration =
+            Settings::get_configuration().expect("Failed to read configuration.");
+
+        // 데이터베이스를 설정한다.
+        let db_pool = set_database(&mut configuration.database).await;
+        TestApp::with_pool(configuration, db_pool).await
+    }
+}
+
+impl TestApp<SqlitePool> {
+    /// 일회용 SQLite DB를 사용하는 애플리케이션을 구동한다.
+    /// DB 서버가 필요 없으므로 핸들러 테스트를 빠르게 실행할 수 있다.
+    pub async fn spawn_with_sqlite() -> Self {
+        let db_pool = SqlitePool::temporary()
+            .await
+            .expect("Failed to create a temporary database.");
+        TestApp::spawn_with_pool(db_pool).await
+    }
+}
+
+impl<D: Zero2ProdDatabase + Clone> TestApp<D> {
+    /// 주어진 저장소를 사용하는 애플리케이션을 구동한다.
+    /// 다른 저장소를 감싼 데코레이터나 테스트 더블을 서버에 전달할 때 사용한다.
+    pub async fn spawn_with_pool(db_pool: D) -> Self {
+        init_test_tracing_subscriber();
+        let configuration = Settings::get_configuration().expect("Failed to read configuration.");
+        TestApp::with_pool(configuration, db_pool).await
+    }
+
+    async fn with_pool(configuration: Settings, db_pool: D) -> Self {
+        let app = TestApp {
+            configuration,
+            test_user: TestUser::generate(),
+            db_pool,
+        };
+        app.test_user.store(&app.db_pool, UserRole::Owner).await;
+        app.start_server().await
+    }
+
+    async fn start_server(mut self) -> Self {
+        // TcpListener를 설정한다.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind random port.");
+        self.configuration.application.host = "127.0.0.1".to_string();
+        // OS가 할당한 포트 번호를 추출한다.
+        self.configuration.application.port = listener.local_addr().unwrap().port();
+
+        // 반짝반짝한 새 서버를 생성한다.
+        let server = new_server(
+            listener,
+            self.db_pool.clone(),
+            self.configuration.application.email_rules.clone(),
+            self.configuration.retention.clone(),
+        )
+        .unwrap();
+
+        // 서버를 백그라운드로 구동한다.
+        // tokio::spawn은 생성된 퓨처에 대한 핸들을 반환한다.
+        // 하지면 여기에서는 사용하지 않으므로 let을 바인딩하지 않는다.
+        let _ = tokio::spawn(server);
+
+        self
+    }
+
+    pub fn http_address(&self) -> String {
+        format!(
+            "http://{}:{}",
+            &self.configuration.application.host, &self.configuration.application.port
+        )
+    }
+
+    pub fn subcriptions_url(&self) -> String {
+        format!("{}/subscriptions", &self.http_address())
+    }
+
+    /// 구독 요청 본문을 폼 형식으로 보낸다.
+    pub async fn post_subscriptions(&self, body: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(self.subcriptions_url())
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body.to_string())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// 관리용 엔드포인트에 테스트 사용자로 `GET` 요청을 보낸다.
+    pub async fn get_admin(&self, path_and_query: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin{}", &self.http_address(), path_and_query))
+            .basic_auth(&self.test_user.username, Some(&self.test_user.password))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_import(&self, csv: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/admin/subscribers/import", &self.http_address()))
+            .basic_auth(&self.test_user.username, Some(&self.test_user.password))
+            .header(reqwest::header::CONTENT_TYPE, "text/csv")
+            .body(csv.to_string())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_attribute_settings(&self, body: &serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/settings/attributes",
+                &self.http_address()
+            ))
+            .basic_auth(&self.test_user.username, Some(&self.test_user.password))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub fn db_pool(&self) -> D {
+        self.db_pool.clone()
+    }
+
+    /// Basic 인증으로 로그인한다.
+    pub async fn post_login(&self, user: &TestUser) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/admin/login", &self.http_address()))
+            .basic_auth(&user.username, Some(&user.password))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+}
+
+/// 테스트를 위한 무작위 데이터베이스를 생성하고 마이그레이션 한다.
+#[cfg(not(feature = "sqlite"))]
+async fn set_database(settings: &mut DatabaseSettings) -> DefaultDBPool {
+    settings.database_name = Uuid::new_v4().to_string();
+    let mut connection =
+        PgConnection::connect_with(&DefaultDBPool::connect_option_without_db(settings))
+            .await
+            .expect("Failed to connect to Postgres.");
+    connection
+        .execute(format!(r#"CREATE DATABASE "{}""#, &settings.database_name).as_str())
+        .await
+        .expect("Failed to create database.");
+
+    // 데이터베이스를 마이그레이션 한다.
+    let db_pool = DefaultDBPool::connect(settings)
+        .await
+        .expect("Failed to connect to the database.");
+    db_pool
+        .migrate()
+        .await
+        .expect("Failed to migrate the database.");
+    db_pool
+}
+
+/// 테스트마다 독립된 일회용 DB를 사용한다.
+/// 서버가 필요 없으므로 Postgres보다 훨씬 빠르다.
+#[cfg(feature = "sqlite")]
+async fn set_database(_settings: &mut DatabaseSettings) -> DefaultDBPool {
+    DefaultDBPool::temporary()
+        .await
+        .expect("Failed to create a temporary database.")
+}
@@ -0,0 +1,211 @@
+use clap::Parser;
+use zero2prod::{
+    authentication::{record_login_failure, LoginSource, UserRole},
+    cli::Cli,
+};
+
+use crate::helpers::{TestApp, TestUser};
+
+async fn login_with_password(app: &TestApp, password: &str) -> reqwest::Response {
+    let user = TestUser {
+        username: app.test_user.username.clone(),
+        password: password.into(),
+    };
+    app.post_login(&user).await
+}
+
+#[tokio::test]
+async fn repeated_failures_delay_further_attempts() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    for _ in 0..3 {
+        let response = login_with_password(&app, "wrong-password").await;
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+    // 실패는 비밀번호를 확인하기 전에 기록되므로 디버그 빌드의 느린 해싱이
+    // 1초의 지연을 다 써버릴 수 있다. 실패를 하나 더 기록해서 지연을 늘린다.
+    record_login_failure(
+        &app.db_pool(),
+        &app.test_user.username,
+        &LoginSource::default(),
+    )
+    .await
+    .unwrap();
+
+    // 실행
+    // 지연 중에는 올바른 비밀번호도 확인하지 않는다.
+    let response = login_with_password(&app, &app.test_user.password).await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("Retry-After"));
+}
+
+#[tokio::test]
+async fn a_lockout_is_recorded_in_the_audit_log() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let source = LoginSource::default();
+    for _ in 0..10 {
+        record_login_failure(&pool, &app.test_user.username, &source)
+            .await
+            .unwrap();
+    }
+
+    // 실행
+    let response = login_with_password(&app, &app.test_user.password).await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: i64 = response.headers()["Retry-After"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 60);
+    let other = TestUser::generate();
+    other.store(&pool, UserRole::Owner).await;
+    let audit: serde_json::Value = reqwest::Client::new()
+        .get(format!(
+            "{}/admin/audit?action=login.lockout",
+            &app.http_address()
+        ))
+        .basic_auth(&other.username, Some(&other.password))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(audit["entries"][0]["target"], app.test_user.username);
+    assert_eq!(audit["entries"][0]["diff"]["scope"], "username");
+}
+
+#[tokio::test]
+async fn concurrent_failures_cannot_bypass_the_delay() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let mut attempts = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let url = format!("{}/admin/login", &app.http_address());
+        let username = app.test_user.username.clone();
+        attempts.spawn(async move {
+            reqwest::Client::new()
+                .post(url)
+                .basic_auth(username, Some("wrong-password"))
+                .send()
+                .await
+                .expect("Failed to execute request.")
+                .status()
+        });
+    }
+    let mut statuses = Vec::new();
+    while let Some(status) = attempts.join_next().await {
+        statuses.push(status.unwrap());
+    }
+
+    // 확인
+    // 지연 없이 허용하는 3번만 비밀번호를 확인한다.
+    let count = |expected| {
+        statuses
+            .iter()
+            .filter(|status| **status == expected)
+            .count()
+    };
+    let checked = count(reqwest::StatusCode::UNAUTHORIZED);
+    let throttled = count(reqwest::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!((checked, throttled), (3, 7));
+}
+
+#[tokio::test]
+async fn concurrent_valid_basic_requests_are_not_throttled() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let mut requests = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let url = format!("{}/admin/audit", &app.http_address());
+        let username = app.test_user.username.clone();
+        let password = app.test_user.password.clone();
+        requests.spawn(async move {
+            reqwest::Client::new()
+                .get(url)
+                .basic_auth(username, Some(password))
+                .send()
+                .await
+                .expect("Failed to execute request.")
+                .status()
+        });
+    }
+    let mut statuses = Vec::new();
+    while let Some(status) = requests.join_next().await {
+        statuses.push(status.unwrap());
+    }
+
+    // 확인
+    assert!(statuses
+        .iter()
+        .all(|status| *status == reqwest::StatusCode::OK));
+}
+
+#[tokio::test]
+async fn a_successful_login_does_not_count_against_the_ip() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    // IP는 실패 10번까지 지연 없이 허용한다.
+    for _ in 0..12 {
+        let response = app.post_login(&app.test_user).await;
+
+        // 확인
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn unlock_clears_the_given_ip() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let source = LoginSource {
+        ip: Some("127.0.0.1".into()),
+        request_id: None,
+    };
+    // 다른 사용자 이름으로 IP만 잠근다.
+    for i in 0..50 {
+        record_login_failure(&pool, &format!("user-{}", i % 5), &source)
+            .await
+            .unwrap();
+    }
+    let locked = login_with_password(&app, &app.test_user.password).await;
+    assert_eq!(locked.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    let unlock = |args: &'static [&'static str]| {
+        let cli = Cli::try_parse_from(
+            [
+                "zero2prod",
+                "users",
+                "unlock",
+                app.test_user.username.as_str(),
+            ]
+            .into_iter()
+            .chain(args.iter().copied()),
+        )
+        .unwrap();
+        cli.command.unwrap().run(&pool, &app.configuration)
+    };
+
+    // 실행
+    unlock(&[]).await.unwrap();
+    let without_ip = login_with_password(&app, &app.test_user.password).await;
+    unlock(&["--ip", "127.0.0.1"]).await.unwrap();
+    let with_ip = login_with_password(&app, &app.test_user.password).await;
+
+    // 확인
+    assert_eq!(without_ip.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(with_ip.status(), reqwest::StatusCode::OK);
+}
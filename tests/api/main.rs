@@ -0,0 +1,24 @@
+// 기존 테스트의 형식을 유지한다.
+#![allow(
+    clippy::needless_borrows_for_generic_args,
+    clippy::let_underscore_future
+)]
+
+mod admin_audit;
+mod admin_segments;
+mod admin_subscribers;
+mod cli;
+mod configuration;
+mod database;
+mod decorator;
+mod health_check;
+mod helpers;
+mod login_throttle;
+mod migrations;
+// SQLite는 복제본을 지원하지 않는다.
+#[cfg(not(feature = "sqlite"))]
+mod replicas;
+mod retention;
+mod sqlite_backend;
+mod subscriptions;
+mod two_factor;
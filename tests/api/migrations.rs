@@ -0,0 +1,114 @@
+use zero2prod::database::{
+    basic::SqlxDatabase,
+    migration::{check_schema, prepare_schema, MigrationState, SchemaMismatch},
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn a_migrated_database_matches_the_binary() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let statuses = app.db_pool().migration_status().await.unwrap();
+
+    // 확인
+    assert!(!statuses.is_empty());
+    assert!(statuses
+        .iter()
+        .all(|status| status.state == MigrationState::Applied));
+    assert!(check_schema(&statuses).is_ok());
+}
+
+#[tokio::test]
+async fn a_reverted_database_is_refused_until_migrated() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let statuses = pool.migration_status().await.unwrap();
+    let latest = statuses.last().unwrap();
+    assert!(latest.reversible);
+    let previous = statuses[statuses.len() - 2].version;
+
+    // 실행
+    pool.revert_migrations(previous).await.unwrap();
+    let reverted = pool.migration_status().await.unwrap();
+    let refused = prepare_schema(&pool, false).await;
+    let migrated = prepare_schema(&pool, true).await;
+
+    // 확인
+    assert!(matches!(
+        check_schema(&reverted),
+        Err(SchemaMismatch::Older(pending)) if pending == vec![latest.version]
+    ));
+    assert!(refused.is_err());
+    assert!(migrated.is_ok());
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[tokio::test]
+async fn migrations_are_not_cut_short_by_the_statement_timeout() {
+    use zero2prod::configuration::DefaultDBPool;
+
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let mut settings = app.configuration.database.clone();
+    settings.statement_timeout_milliseconds = 1;
+    let pool = DefaultDBPool::connect(&settings).await.unwrap();
+
+    // 실행
+    let reverted = pool.revert_migrations(0).await;
+    let migrated = pool.migrate().await;
+    let slow_query = sqlx::query("SELECT pg_sleep(1)").execute(&*pool).await;
+
+    // 확인
+    assert!(reverted.is_ok());
+    assert!(migrated.is_ok());
+    // 다른 커넥션에는 설정한 제한이 그대로 적용된다.
+    assert!(slow_query.is_err());
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[tokio::test]
+async fn emails_differing_in_whitespace_and_case_are_merged_by_the_normalisation() {
+    // 준비
+    // 이메일을 정규화하기 직전의 스키마로 되돌린다.
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    pool.revert_migrations(20240728102233).await.unwrap();
+    for (email, status) in [
+        ("ursula@Example.com", "pending_confirmation"),
+        (" ursula@example.com", "confirmed"),
+        ("ursula@example.com ", "pending_confirmation"),
+        ("le.guin@example.com", "confirmed"),
+    ] {
+        sqlx::query(
+            "INSERT INTO subscriptions (id, email, name, subscribed_at, status) \
+             VALUES (gen_random_uuid(), $1, 'le guin', now(), $2)",
+        )
+        .bind(email)
+        .bind(status)
+        .execute(&*pool)
+        .await
+        .unwrap();
+    }
+
+    // 실행
+    let migrated = pool.migrate().await;
+
+    // 확인
+    assert!(migrated.is_ok(), "{:?}", migrated);
+    let saved: Vec<(String, String)> =
+        sqlx::query_as("SELECT email, status FROM subscriptions ORDER BY email")
+            .fetch_all(&*pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        saved,
+        vec![
+            ("le.guin@example.com".into(), "confirmed".into()),
+            ("ursula@example.com".into(), "confirmed".into()),
+        ]
+    );
+}
@@ -0,0 +1,87 @@
+use chrono::Utc;
+use uuid::Uuid;
+use zero2prod::{
+    configuration::{DatabaseSettings, DefaultDBPool, ReplicaSettings},
+    database::basic::{SqlxDatabase, Zero2ProdDatabase},
+    domain::{SubscriberAttributes, SubscriberEmail},
+};
+
+use crate::helpers::TestApp;
+
+/// 연결할 수 없는 주소
+fn unreachable(settings: &DatabaseSettings) -> ReplicaSettings {
+    ReplicaSettings {
+        host: settings.host.clone(),
+        port: 1,
+    }
+}
+
+async fn insert_subscriber(app: &TestApp) -> Uuid {
+    let id = Uuid::new_v4();
+    app.db_pool()
+        .insert_subscriptions(
+            id,
+            &SubscriberEmail::parse("ursula@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn reads_are_served_by_a_replica() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = insert_subscriber(&app).await;
+    // 주 DB에 연결할 수 없어도 복제본에서 읽을 수 있어야 한다.
+    let mut settings = app.configuration.database.clone();
+    settings.replicas = vec![ReplicaSettings {
+        host: settings.host.clone(),
+        port: settings.port,
+    }];
+    let primary = unreachable(&settings);
+    settings.port = primary.port;
+    settings.acquire_timeout_seconds = 1;
+    let pool = DefaultDBPool::connect(&settings).await.unwrap();
+
+    // 실행
+    let read = pool.fetch_subscriber(id).await;
+    let write = pool
+        .insert_subscriptions(
+            Uuid::new_v4(),
+            &SubscriberEmail::parse("guin@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await;
+
+    // 확인
+    assert!(read.unwrap().is_some());
+    assert!(write.is_err());
+}
+
+#[tokio::test]
+async fn reads_fall_back_to_the_primary_when_replicas_are_down() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let id = insert_subscriber(&app).await;
+    let mut settings = app.configuration.database.clone();
+    settings.replicas = vec![unreachable(&settings), unreachable(&settings)];
+    settings.acquire_timeout_seconds = 1;
+    let pool = DefaultDBPool::connect(&settings).await.unwrap();
+
+    // 실행
+    let first = pool.fetch_subscriber(id).await;
+    // 실패한 복제본은 잠시 건너뛰므로 다시 기다리지 않는다.
+    let started = std::time::Instant::now();
+    let second = pool.fetch_subscribers(None).await;
+
+    // 확인
+    assert!(first.unwrap().is_some());
+    assert_eq!(second.unwrap().len(), 1);
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+}
@@ -0,0 +1,196 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use zero2prod::{
+    configuration::{RetentionAction, RetentionSettings},
+    database::basic::Zero2ProdDatabase,
+    domain::{SubscriberAttributes, SubscriberEmail},
+    maintenance::{run_retention, RetentionReport},
+};
+
+use crate::helpers::TestApp;
+
+/// 오래된 미확인 구독자, 최근의 미확인 구독자, 오래된 확인된 구독자를 추가한다.
+async fn insert_subscribers(app: &TestApp) -> (Uuid, Uuid, Uuid) {
+    let pool = app.db_pool();
+    let now = Utc::now();
+    let stale = Uuid::new_v4();
+    let recent = Uuid::new_v4();
+    let confirmed = Uuid::new_v4();
+    for (id, email, subscribed_at) in [
+        (stale, "stale@example.com", now - Duration::days(31)),
+        (recent, "recent@example.com", now - Duration::days(29)),
+        (confirmed, "confirmed@example.com", now - Duration::days(31)),
+    ] {
+        pool.insert_subscriptions(
+            id,
+            &SubscriberEmail::parse(email).unwrap(),
+            "name",
+            &SubscriberAttributes::default(),
+            subscribed_at,
+        )
+        .await
+        .unwrap();
+    }
+    pool.confirm_subscriber(confirmed).await.unwrap();
+    (stale, recent, confirmed)
+}
+
+#[tokio::test]
+async fn stale_unconfirmed_subscribers_are_deleted() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (stale, recent, confirmed) = insert_subscribers(&app).await;
+
+    // 실행
+    let report = run_retention(&pool, &RetentionSettings::default(), Utc::now())
+        .await
+        .unwrap();
+
+    // 확인
+    assert_eq!(
+        report,
+        Some(RetentionReport {
+            deleted: 1,
+            anonymised: 0,
+            purged: 0,
+        })
+    );
+    assert!(pool.fetch_subscriber(stale).await.unwrap().is_none());
+    assert!(pool.fetch_subscriber(recent).await.unwrap().is_some());
+    assert!(pool.fetch_subscriber(confirmed).await.unwrap().is_some());
+    let response = app.get_admin("/audit?action=subscriber.purge").await;
+    let audit: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(audit["entries"][0]["actor"], "system:retention");
+    assert_eq!(audit["entries"][0]["diff"]["deleted"], 1);
+}
+
+#[tokio::test]
+async fn subscribers_deleted_by_retention_keep_their_history_until_purged() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (stale, _, _) = insert_subscribers(&app).await;
+    let settings = RetentionSettings::default();
+    let now = Utc::now();
+
+    // 실행
+    run_retention(&pool, &settings, now).await.unwrap();
+    let history = pool.fetch_subscriber_history(stale).await.unwrap();
+    let later = now + Duration::days(i64::from(settings.deleted_subscriber_grace_days) + 1);
+    let report = run_retention(&pool, &settings, later).await.unwrap();
+
+    // 확인
+    // 다른 관리자가 삭제한 구독자와 같이 삭제 기록이 남는다.
+    let operations: Vec<_> = history
+        .iter()
+        .map(|entry| entry.operation.as_str())
+        .collect();
+    assert_eq!(operations, vec!["insert", "delete"]);
+    assert_eq!(report.unwrap().purged, 1);
+    assert!(pool
+        .fetch_subscriber_history(stale)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn stale_unconfirmed_subscribers_can_be_anonymised() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (stale, _, _) = insert_subscribers(&app).await;
+    let settings = RetentionSettings {
+        action: RetentionAction::Anonymise,
+        ..RetentionSettings::default()
+    };
+
+    // 실행
+    let first = run_retention(&pool, &settings, Utc::now()).await.unwrap();
+    let second = run_retention(&pool, &settings, Utc::now()).await.unwrap();
+
+    // 확인
+    assert_eq!(first.unwrap().anonymised, 1);
+    assert_eq!(second, Some(RetentionReport::default()));
+    let anonymised = pool.fetch_subscriber(stale).await.unwrap().unwrap();
+    assert_eq!(anonymised.status, "anonymised");
+    assert_eq!(anonymised.name, "");
+    // 모든 백엔드에서 같은 형식의 주소를 사용한다.
+    assert_eq!(anonymised.email, format!("{}@anonymised.invalid", stale));
+    // 변경 이력에도 개인 정보가 남지 않는다.
+    let history = pool.fetch_subscriber_history(stale).await.unwrap();
+    assert!(history
+        .iter()
+        .all(|entry| !entry.email.contains("stale") && entry.name.is_empty()));
+}
+
+#[tokio::test]
+async fn subscribers_deleted_before_the_grace_period_are_purged_with_their_history() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (_, recent, confirmed) = insert_subscribers(&app).await;
+    let now = Utc::now();
+    pool.delete_subscriber(confirmed, now - Duration::days(31))
+        .await
+        .unwrap();
+    pool.delete_subscriber(recent, now - Duration::days(29))
+        .await
+        .unwrap();
+
+    // 실행
+    let report = run_retention(&pool, &RetentionSettings::default(), now)
+        .await
+        .unwrap();
+
+    // 확인
+    assert_eq!(report.unwrap().purged, 1);
+    assert!(pool
+        .fetch_subscriber_history(confirmed)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(!pool
+        .fetch_subscriber_history(recent)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        pool.restore_subscriber(recent, now - Duration::days(30))
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+// SQLite는 프로세스 하나에서만 사용하므로 잠금을 경쟁하지 않는다.
+#[cfg(not(feature = "sqlite"))]
+#[tokio::test]
+async fn the_retention_task_is_skipped_while_another_instance_holds_the_lock() {
+    use zero2prod::{database::basic::Zero2ProdTransaction, maintenance::RETENTION_LOCK_KEY};
+
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let pool = app.db_pool();
+    let (stale, _, _) = insert_subscribers(&app).await;
+    let other_instance = pool.begin().await.unwrap();
+    assert!(other_instance
+        .try_advisory_lock(RETENTION_LOCK_KEY)
+        .await
+        .unwrap());
+
+    // 실행
+    let skipped = run_retention(&pool, &RetentionSettings::default(), Utc::now())
+        .await
+        .unwrap();
+    other_instance.commit().await.unwrap();
+    let ran = run_retention(&pool, &RetentionSettings::default(), Utc::now())
+        .await
+        .unwrap();
+
+    // 확인
+    assert_eq!(skipped, None);
+    assert_eq!(ran.unwrap().deleted, 1);
+    assert!(pool.fetch_subscriber(stale).await.unwrap().is_none());
+}
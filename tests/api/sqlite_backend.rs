@@ -0,0 +1,87 @@
+use chrono::Utc;
+use uuid::Uuid;
+use zero2prod::{
+    database::basic::{Zero2ProdDatabase, Zero2ProdTransaction},
+    domain::{SubscriberAttributes, SubscriberEmail},
+};
+
+use crate::helpers::TestApp;
+
+// 핸들러 테스트를 DB 서버 없이 일회용 SQLite DB로 실행한다.
+// 같은 빌드의 다른 테스트는 Postgres에 대해 실행되며 기준이 된다.
+
+#[tokio::test]
+async fn subscribe_stores_a_pending_subscriber() {
+    // 준비
+    let app = TestApp::spawn_with_sqlite().await;
+
+    // 실행
+    let first = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+    let second = app
+        .post_subscriptions("name=ursula&email=Ursula_Le_Guin%40Gmail.com")
+        .await;
+    let invalid = app.post_subscriptions("name=le%20guin").await;
+
+    // 확인
+    assert_eq!(first.status(), reqwest::StatusCode::OK);
+    assert_eq!(second.status(), reqwest::StatusCode::OK);
+    assert_eq!(invalid.status(), reqwest::StatusCode::BAD_REQUEST);
+    let saved = app.db_pool().fetch_subscribers(None).await.unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].name, "le guin");
+    assert_eq!(saved[0].status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn admin_requests_are_served_from_sqlite() {
+    // 준비
+    let app = TestApp::spawn_with_sqlite().await;
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // 실행
+    let response = app.get_admin("/subscribers").await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let page: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(page["subscribers"][0]["email"], "ursula_le_guin@gmail.com");
+}
+
+#[tokio::test]
+async fn the_pool_can_be_used_while_a_transaction_is_open() {
+    // 준비
+    let app = TestApp::spawn_with_sqlite().await;
+    let pool = app.db_pool();
+    let transaction = pool.begin().await.unwrap();
+    transaction
+        .insert_subscriptions(
+            Uuid::new_v4(),
+            &SubscriberEmail::parse("ursula@example.com").unwrap(),
+            "le guin",
+            &SubscriberAttributes::default(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+
+    // 실행
+    // 커넥션이 하나뿐이면 트랜잭션이 끝날 때까지 기다리게 된다.
+    let during = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        pool.fetch_subscribers(None),
+    )
+    .await
+    .expect("The pool was blocked by the open transaction.")
+    .unwrap();
+    transaction.commit().await.unwrap();
+    let after = pool.fetch_subscribers(None).await.unwrap();
+
+    // 확인
+    assert!(during.is_empty());
+    assert_eq!(after.len(), 1);
+}
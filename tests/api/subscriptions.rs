@@ -0,0 +1,292 @@
+use zero2prod::{
+    database::basic::Zero2ProdDatabase,
+    domain::{EmailRule, EmailRules, SubscriberEmail},
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn subscribe_returns_a_200_for_valid_form_data() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // 실행
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    let response = client
+        .post(app.subcriptions_url())
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // 확인
+    // 응답이 200 OK인지 확인한다.
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let db_pool = app.db_pool();
+    let saved = db_pool
+        .fetch_subscribers(None)
+        .await
+        .expect("Failed to fetch save subcriptions.")
+        .pop()
+        .expect("No subscription was saved.");
+
+    assert_eq!(saved.email, "ursula_le_guin@gmail.com");
+    assert_eq!(saved.name, "le guin");
+    assert_eq!(saved.status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_when_data_is_missing() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let client = reqwest::Client::new();
+    let test_cases = vec![
+        ("name=le%20guin", "missing the email"),
+        ("email=ursula_le_guin%40gmail.com", "missing the name"),
+        ("", "missing both name and email"),
+    ];
+
+    for (invalid_body, error_messages) in test_cases {
+        // 실행
+        let response = client
+            .post(&app.subcriptions_url())
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(invalid_body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        // 확인
+        // 잘못된 바디가 전송됐으므로 `BAD_REQUEST` 응답을 받아야 한다.
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            // 테스트 실패시 출력할 커스터마이즈된 추가 오류 메시지
+            "The API did not fail with 400 BAD_REQUEST when the payload was {}.",
+            error_messages
+        );
+    }
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_for_an_invalid_email() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let test_cases = vec![
+        ("name=le%20guin&email=", "empty email"),
+        ("name=le%20guin&email=ursula_le_guin", "missing the @"),
+        (
+            "name=le%20guin&email=%40gmail.com",
+            "missing the local part",
+        ),
+        ("name=le%20guin&email=ursula%40", "missing the domain"),
+        (
+            "name=le%20guin&email=ursula%40localhost",
+            "domain without a dot",
+        ),
+        ("name=le%20guin&email=ursula%20le%40gmail.com", "whitespace"),
+    ];
+
+    for (invalid_body, description) in test_cases {
+        // 실행
+        let response = app.post_subscriptions(invalid_body).await;
+
+        // 확인
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "The API did not return a 400 BAD_REQUEST when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn subscribe_normalises_the_email_address() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let latin = app
+        .post_subscriptions("name=le%20guin&email=%20Ursula%40GMail.COM%20")
+        .await;
+    let idn = app
+        .post_subscriptions("name=le%20guin&email=ursula%40b%C3%BCcher.example")
+        .await;
+
+    // 확인
+    assert_eq!(latin.status(), reqwest::StatusCode::OK);
+    assert_eq!(idn.status(), reqwest::StatusCode::OK);
+    let mut emails: Vec<_> = app
+        .db_pool()
+        .fetch_subscribers(None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|subscriber| subscriber.email)
+        .collect();
+    emails.sort();
+    assert_eq!(emails, ["Ursula@gmail.com", "ursula@xn--bcher-kva.example"]);
+}
+
+#[tokio::test]
+async fn subscribing_twice_with_a_different_case_keeps_one_subscriber() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let first = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // 실행
+    let second = app
+        .post_subscriptions("name=ursula&email=Ursula_Le_Guin%40Gmail.com")
+        .await;
+
+    // 확인
+    assert_eq!(first.status(), reqwest::StatusCode::OK);
+    assert_eq!(second.status(), reqwest::StatusCode::OK);
+    let saved = app.db_pool().fetch_subscribers(None).await.unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].name, "le guin");
+}
+
+#[test]
+fn provider_rules_are_applied_to_the_canonical_address() {
+    // 준비
+    let rules = EmailRules(vec![EmailRule {
+        domains: vec!["gmail.com".into(), "googlemail.com".into()],
+        canonical_domain: Some("gmail.com".into()),
+        strip_plus_tags: true,
+        ignore_dots: true,
+    }]);
+    let parse = |email| SubscriberEmail::parse(email).unwrap().canonicalize(&rules);
+
+    // 실행
+    let tagged = parse("Ursula.Le.Guin+news@googlemail.com");
+    let only_tag = parse("+news@gmail.com");
+    let other_provider = parse("Ursula.Le+news@Example.com");
+
+    // 확인
+    assert_eq!(tagged.as_str(), "Ursula.Le.Guin+news@googlemail.com");
+    assert_eq!(tagged.canonical(), "ursulaleguin@gmail.com");
+    assert_eq!(only_tag.canonical(), "+news@gmail.com");
+    assert_eq!(other_provider.canonical(), "ursula.le+news@example.com");
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_for_an_invalid_name() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let test_cases = vec![
+        ("name=%20%20&email=ursula_le_guin%40gmail.com", "blank name"),
+        (
+            "name=le%0Aguin&email=ursula_le_guin%40gmail.com",
+            "control character",
+        ),
+    ];
+
+    for (invalid_body, description) in test_cases {
+        // 실행
+        let response = app.post_subscriptions(invalid_body).await;
+
+        // 확인
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "The API did not return a 400 BAD_REQUEST when the payload was {}.",
+            description
+        );
+    }
+}
+
+/// `country`(문자열), `employees`(숫자), `digest`(불리언), `interests`(목록) 속성을 정의한다.
+async fn define_attributes(app: &TestApp) {
+    let response = app
+        .put_attribute_settings(&serde_json::json!({
+            "attributes": [
+                { "name": "country", "type": "string" },
+                { "name": "employees", "type": "number" },
+                { "name": "digest", "type": "boolean" },
+                { "name": "interests", "type": "list" },
+            ]
+        }))
+        .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn subscribe_stores_defined_attributes() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    define_attributes(&app).await;
+
+    // 실행
+    let response = app
+        .post_subscriptions(
+            "name=le%20guin&email=ursula%40example.com&country=%20KR%20&employees=12\
+             &digest=on&interests=rust,%20go,,rust",
+        )
+        .await;
+
+    // 확인
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let saved = app.db_pool().fetch_subscribers(None).await.unwrap();
+    assert_eq!(
+        saved[0].attributes,
+        serde_json::json!({
+            "country": "KR",
+            "employees": 12,
+            "digest": true,
+            "interests": ["rust", "go"],
+        })
+    );
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_for_undefined_or_invalid_attributes() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    define_attributes(&app).await;
+    let test_cases = vec![
+        (
+            "name=le%20guin&email=ursula%40example.com&company=acme",
+            "an undefined attribute",
+        ),
+        (
+            "name=le%20guin&email=ursula%40example.com&employees=many",
+            "an invalid number",
+        ),
+        (
+            "name=le%20guin&email=ursula%40example.com&digest=maybe",
+            "an invalid boolean",
+        ),
+    ];
+
+    for (invalid_body, description) in test_cases {
+        // 실행
+        let response = app.post_subscriptions(invalid_body).await;
+
+        // 확인
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "The API did not fail with 400 Bad Request when the payload had {}.",
+            description
+        );
+    }
+    assert!(app
+        .db_pool()
+        .fetch_subscribers(None)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,310 @@
+use secrecy::Secret;
+use zero2prod::{
+    authentication::{totp, UserRole},
+    database::basic::Zero2ProdDatabase,
+};
+
+use crate::helpers::{TestApp, TestUser};
+
+/// 세션 토큰으로 `GET /admin/audit`을 요청한다.
+async fn get_audit_with_token(app: &TestApp, token: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/admin/audit", &app.http_address()))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn post_admin_json<D: Zero2ProdDatabase + Clone>(
+    app: &TestApp<D>,
+    path: &str,
+    token: &str,
+    body: serde_json::Value,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/admin{}", &app.http_address(), path))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn login_token<D: Zero2ProdDatabase + Clone>(app: &TestApp<D>) -> serde_json::Value {
+    let response = app.post_login(&app.test_user).await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn login_issues_a_session_token_without_two_factor() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+
+    // 실행
+    let login = login_token(&app).await;
+
+    // 확인
+    assert_eq!(login["second_factor_required"], false);
+    let response = get_audit_with_token(&app, login["token"].as_str().unwrap()).await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn two_factor_requires_a_second_login_step() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let token = login_token(&app).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let enroll: serde_json::Value =
+        post_admin_json(&app, "/two-factor/enroll", &token, serde_json::json!({}))
+            .await
+            .json()
+            .await
+            .unwrap();
+    assert!(enroll["provisioning_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/"));
+    let code = totp(
+        &Secret::new(enroll["secret"].as_str().unwrap().to_string()),
+        &app.test_user.username,
+    )
+    .unwrap()
+    .generate_current()
+    .unwrap();
+    let confirm: serde_json::Value = post_admin_json(
+        &app,
+        "/two-factor/confirm",
+        &token,
+        serde_json::json!({ "code": code }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    let recovery_code = confirm["recovery_codes"][0].as_str().unwrap().to_string();
+
+    // 실행
+    let basic = app.get_admin("/audit").await;
+    let login = login_token(&app).await;
+    let pending_token = login["token"].as_str().unwrap();
+    let before_second_factor = get_audit_with_token(&app, pending_token).await;
+    let second_factor = post_admin_json(
+        &app,
+        "/login/two-factor",
+        pending_token,
+        serde_json::json!({ "code": recovery_code }),
+    )
+    .await;
+    let after_second_factor = get_audit_with_token(&app, pending_token).await;
+
+    // 확인
+    // 2단계 인증을 활성화하면 Basic 인증만으로는 접근할 수 없다.
+    assert_eq!(basic.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(login["second_factor_required"], true);
+    assert_eq!(
+        before_second_factor.status(),
+        reqwest::StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(second_factor.status(), reqwest::StatusCode::OK);
+    assert_eq!(after_second_factor.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn a_recovery_code_can_be_used_only_once() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let token = login_token(&app).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let enroll: serde_json::Value =
+        post_admin_json(&app, "/two-factor/enroll", &token, serde_json::json!({}))
+            .await
+            .json()
+            .await
+            .unwrap();
+    let code = totp(
+        &Secret::new(enroll["secret"].as_str().unwrap().to_string()),
+        &app.test_user.username,
+    )
+    .unwrap()
+    .generate_current()
+    .unwrap();
+    let confirm: serde_json::Value = post_admin_json(
+        &app,
+        "/two-factor/confirm",
+        &token,
+        serde_json::json!({ "code": code }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    let recovery_code = confirm["recovery_codes"][0].as_str().unwrap();
+
+    // 실행
+    let mut statuses = Vec::new();
+    for _ in 0..2 {
+        let login = login_token(&app).await;
+        let response = post_admin_json(
+            &app,
+            "/login/two-factor",
+            login["token"].as_str().unwrap(),
+            serde_json::json!({ "code": recovery_code }),
+        )
+        .await;
+        statuses.push(response.status());
+    }
+
+    // 확인
+    assert_eq!(
+        statuses,
+        vec![reqwest::StatusCode::OK, reqwest::StatusCode::UNAUTHORIZED]
+    );
+}
+
+#[tokio::test]
+async fn only_owners_can_require_two_factor_for_all_admins() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let admin = TestUser::generate();
+    admin.store(&app.db_pool(), UserRole::Admin).await;
+    let client = reqwest::Client::new();
+    let put_settings = |user: &TestUser| {
+        client
+            .put(format!("{}/admin/settings/security", &app.http_address()))
+            .basic_auth(&user.username, Some(&user.password))
+            .json(&serde_json::json!({ "require_two_factor": true }))
+            .send()
+    };
+
+    // 실행
+    let by_admin = put_settings(&admin).await.unwrap();
+    let by_owner = put_settings(&app.test_user).await.unwrap();
+    let audit_without_two_factor = app.get_admin("/audit").await;
+    let enroll_without_two_factor = client
+        .post(format!("{}/admin/two-factor/enroll", &app.http_address()))
+        .basic_auth(&admin.username, Some(&admin.password))
+        .send()
+        .await
+        .unwrap();
+
+    // 확인
+    assert_eq!(by_admin.status(), reqwest::StatusCode::FORBIDDEN);
+    assert_eq!(by_owner.status(), reqwest::StatusCode::OK);
+    // 2단계 인증을 등록하지 않은 관리자는 등록 엔드포인트만 사용할 수 있다.
+    assert_eq!(
+        audit_without_two_factor.status(),
+        reqwest::StatusCode::FORBIDDEN
+    );
+    assert_eq!(enroll_without_two_factor.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn a_password_login_does_not_reset_second_factor_failures() {
+    // 준비
+    let app = TestApp::spawn_app().await;
+    let token = login_token(&app).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let enroll: serde_json::Value =
+        post_admin_json(&app, "/two-factor/enroll", &token, serde_json::json!({}))
+            .await
+            .json()
+            .await
+            .unwrap();
+    let code = totp(
+        &Secret::new(enroll["secret"].as_str().unwrap().to_string()),
+        &app.test_user.username,
+    )
+    .unwrap()
+    .generate_current()
+    .unwrap();
+    let confirm = post_admin_json(
+        &app,
+        "/two-factor/confirm",
+        &token,
+        serde_json::json!({ "code": code }),
+    )
+    .await;
+    assert_eq!(confirm.status(), reqwest::StatusCode::OK);
+
+    // 실행
+    // 비밀번호로 다시 로그인할 때마다 잘못된 코드를 보낸다.
+    let mut statuses = Vec::new();
+    for _ in 0..6 {
+        let login = app.post_login(&app.test_user).await;
+        statuses.push(login.status());
+        if login.status() != reqwest::StatusCode::OK {
+            break;
+        }
+        let login: serde_json::Value = login.json().await.unwrap();
+        let second_factor = post_admin_json(
+            &app,
+            "/login/two-factor",
+            login["token"].as_str().unwrap(),
+            serde_json::json!({ "code": "invalid" }),
+        )
+        .await;
+        statuses.push(second_factor.status());
+    }
+
+    // 확인
+    assert!(statuses.contains(&reqwest::StatusCode::TOO_MANY_REQUESTS));
+}
+
+#[tokio::test]
+async fn two_factor_is_not_enabled_when_the_audit_entry_fails() {
+    // 준비
+    let app = TestApp::spawn_with_sqlite().await;
+    let token = login_token(&app).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let enroll: serde_json::Value =
+        post_admin_json(&app, "/two-factor/enroll", &token, serde_json::json!({}))
+            .await
+            .json()
+            .await
+            .unwrap();
+    let code = totp(
+        &Secret::new(enroll["secret"].as_str().unwrap().to_string()),
+        &app.test_user.username,
+    )
+    .unwrap()
+    .generate_current()
+    .unwrap();
+    // 감사 로그 기록만 실패하게 한다.
+    sqlx::query(
+        "CREATE TRIGGER reject_audit BEFORE INSERT ON audit_log \
+        BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+    )
+    .execute(&*app.db_pool())
+    .await
+    .unwrap();
+
+    // 실행
+    let confirm = post_admin_json(
+        &app,
+        "/two-factor/confirm",
+        &token,
+        serde_json::json!({ "code": code }),
+    )
+    .await;
+
+    // 확인
+    assert_eq!(confirm.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let credentials = app
+        .db_pool()
+        .fetch_user_credentials(&app.test_user.username)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!credentials.totp_enabled);
+}